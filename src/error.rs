@@ -11,6 +11,9 @@ pub enum Error {
     #[cfg(feature = "wizard")]
     #[error("cannot write TOML config at {1}")]
     WriteTomlConfigError(#[source] std::io::Error, std::path::PathBuf),
+    #[cfg(feature = "wizard")]
+    #[error("cannot back up TOML config at {1}")]
+    BackupTomlConfigError(#[source] std::io::Error, std::path::PathBuf),
 
     #[cfg(feature = "config")]
     #[error("cannot create TOML config from invalid or missing paths")]
@@ -18,20 +21,45 @@ pub enum Error {
     #[cfg(feature = "config")]
     #[error("cannot create TOML config from wizard")]
     CreateTomlConfigFromWizardError(#[source] color_eyre::eyre::Error),
-    #[error("cannot prompt unsigned integer (u16)")]
-    PromptU16Error(#[source] InquireError),
-    #[error("cannot prompt unsigned integer (usize)")]
-    PromptUsizeError(#[source] InquireError),
+    #[error("cannot prompt number")]
+    PromptNumberError(#[source] InquireError),
     #[error("cannot prompt secret")]
     PromptSecretError(#[source] InquireError),
     #[error("cannot prompt password")]
     PromptPasswordError(#[source] InquireError),
     #[error("cannot prompt text")]
     PromptTextError(#[source] InquireError),
+    #[error("cannot prompt text with autocompletion")]
+    PromptAutocompleteError(#[source] InquireError),
     #[error("cannot prompt boolean")]
     PromptBoolError(#[source] InquireError),
     #[error("cannot prompt item from list")]
     PromptItemError(#[source] InquireError),
+    #[error("cannot prompt items from list")]
+    PromptItemsError(#[source] InquireError),
+    #[error("cannot prompt color")]
+    PromptColorError(#[source] InquireError),
+    #[error("prompt cancelled by user")]
+    PromptCancelled,
+    #[error("prompt left to go back to the previous one")]
+    PromptBack,
+    #[error("cannot prompt: too many invalid answers ({0} attempts)")]
+    PromptMaxAttemptsReached(usize),
+    #[error("prompt timed out after {0:?}")]
+    PromptTimedOut(std::time::Duration),
+    #[error("invalid answer {1:?} for prompt {0}")]
+    InvalidPromptAnswerError(String, String),
+    #[error("missing answer for prompt {0}")]
+    MissingPromptAnswerError(String),
+    #[cfg(feature = "wizard")]
+    #[error("cannot parse answer {0}: expected key=value")]
+    ParseAnswerArgError(String),
+    #[cfg(feature = "config")]
+    #[error("cannot read answers file at {}", .1.display())]
+    ReadAnswersFileError(#[source] std::io::Error, std::path::PathBuf),
+    #[cfg(feature = "config")]
+    #[error("cannot parse answers file at {}", .1.display())]
+    ParseAnswersFileError(#[source] toml::de::Error, std::path::PathBuf),
     #[cfg(feature = "email")]
     #[error("cannot prompt email")]
     PromptEmailError(#[source] InquireError),
@@ -51,7 +79,7 @@ pub enum Error {
     #[cfg(feature = "smtp")]
     #[error(transparent)]
     SmtpError(#[from] email::smtp::Error),
-    #[cfg(feature = "imap")]
+    #[cfg(any(feature = "imap", feature = "smtp", feature = "pgp-native"))]
     #[error(transparent)]
     SecretError(#[from] secret::Error),
 
@@ -66,18 +94,69 @@ pub enum Error {
     #[error("cannot read config file at {}", .1.display())]
     ReadTomlConfigFile(#[source] std::io::Error, std::path::PathBuf),
     #[cfg(feature = "config")]
-    #[error("cannot parse config file at {}", .1.display())]
-    ParseTomlConfigFile(#[source] toml::de::Error, std::path::PathBuf),
+    #[error("cannot parse config file at {}{}", .1.display(), .2.as_ref().map(|hint| format!(": {hint}")).unwrap_or_default())]
+    ParseTomlConfigFile(
+        #[source] toml::de::Error,
+        std::path::PathBuf,
+        /// Hint about unknown fields, see
+        /// [`crate::terminal::config::TomlConfig::from_paths`].
+        Option<String>,
+    ),
+    #[cfg(feature = "config")]
+    #[error("cannot parse config files merged from {}{}", .1.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", "), .2.as_ref().map(|hint| format!(": {hint}")).unwrap_or_default())]
+    ParseMergedTomlConfigFiles(
+        #[source] toml::de::Error,
+        Vec<std::path::PathBuf>,
+        /// Hint about unknown fields, see
+        /// [`crate::terminal::config::TomlConfig::from_paths`].
+        Option<String>,
+    ),
+    #[cfg(feature = "config")]
+    #[error("cannot parse YAML config file at {}", .1.display())]
+    ParseYamlConfigFile(#[source] serde_yaml::Error, std::path::PathBuf),
+    #[cfg(feature = "config")]
+    #[error("cannot parse JSON config file at {}", .1.display())]
+    ParseJsonConfigFile(#[source] serde_json::Error, std::path::PathBuf),
     #[cfg(feature = "config")]
     #[error("cannot merge config files: {0}")]
     MergeTomlConfigFiles(serde_toml_merge::Error),
     #[cfg(feature = "config")]
+    #[error("cannot include config file at {}: it includes itself", .0.display())]
+    IncludeTomlConfigCycleError(std::path::PathBuf),
+    #[cfg(feature = "config")]
+    #[error("cannot parse includes of config file at {}: expected a path or a list of paths", .0.display())]
+    ParseTomlConfigIncludeError(std::path::PathBuf),
+    #[cfg(feature = "config")]
+    #[error("cannot find environment variable {0} referenced in config")]
+    InterpolateTomlConfigEnvVarError(String),
+    #[cfg(feature = "config-watch")]
+    #[error("cannot watch config files")]
+    WatchTomlConfigError(#[source] notify::Error),
+    #[cfg(feature = "config")]
+    #[error("cannot run command {1} to decrypt config value")]
+    DecryptTomlConfigValueError(#[source] std::io::Error, String),
+    #[cfg(feature = "config")]
+    #[error("cannot decrypt config value using command {0}: {1}")]
+    DecryptTomlConfigValueCommandError(String, String),
+    #[cfg(feature = "wizard")]
+    #[error("cannot run command {1} to encrypt config value")]
+    EncryptTomlConfigValueError(#[source] std::io::Error, String),
+    #[cfg(feature = "wizard")]
+    #[error("cannot encrypt config value using command {0}: {1}")]
+    EncryptTomlConfigValueCommandError(String, String),
+    #[cfg(feature = "config")]
     #[error("cannot get XDG config directory")]
     GetXdgConfigDirectory,
     #[cfg(feature = "config")]
     #[error("cannot serialize TOML config")]
     SerializeTomlConfigError(#[source] toml::ser::Error),
     #[cfg(feature = "config")]
+    #[error("cannot serialize config to YAML")]
+    SerializeYamlConfigError(#[source] serde_yaml::Error),
+    #[cfg(feature = "config")]
+    #[error("cannot serialize config to JSON")]
+    SerializeJsonConfigError(#[source] serde_json::Error),
+    #[cfg(feature = "config")]
     #[error("cannot parse serialized TOML config as document")]
     ParseSerializedTomlConfigError(#[source] toml_edit::TomlError),
     #[cfg(feature = "config")]
@@ -86,9 +165,60 @@ pub enum Error {
     #[cfg(feature = "config")]
     #[error("cannot find default account configuration")]
     GetDefaultAccountConfigError,
+    #[cfg(feature = "himalaya")]
+    #[error("cannot choose between default accounts {}: set a different priority to each of them", .0.join(", "))]
+    MultipleDefaultAccountsError(Vec<String>),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot choose between accounts {names} sharing the alias {0}", names = .1.join(", "))]
+    AmbiguousAccountAliasError(String, Vec<String>),
     #[cfg(feature = "config")]
     #[error("cannot find configuration for account {0}")]
     GetAccountConfigError(String),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot rename account: an account named {0} already exists")]
+    RenameAccountConflictError(String),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot parse date {1}: expected YYYY-MM-DD, today or yesterday")]
+    ParseEnvelopesDateError(#[source] chrono::ParseError, String),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot search envelopes after {0} and before {1}: the date range is empty")]
+    EmptyEnvelopesDateRangeError(chrono::NaiveDate, chrono::NaiveDate),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot create downloads directory {}", .1.display())]
+    CreateDownloadsDirError(#[source] std::io::Error, std::path::PathBuf),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot write attachment to file {}", .1.display())]
+    WriteAttachmentError(#[source] std::io::Error, std::path::PathBuf),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot export messages to {}", .1.display())]
+    ExportMessagesError(#[source] std::io::Error, std::path::PathBuf),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot import messages from {}", .1.display())]
+    ImportMessagesError(#[source] std::io::Error, std::path::PathBuf),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot process {failed} out of {1} messages", failed = .0.iter().map(|failure| failure.ids.len()).sum::<usize>())]
+    BatchOperationError(Vec<crate::himalaya::backend::BatchFailure>, usize),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot export id aliases of folder {1}")]
+    ExportIdAliasesError(#[source] serde_json::Error, String),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot import id aliases of folder {1}")]
+    ImportIdAliasesError(#[source] serde_json::Error, String),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot connect to backend: timed out after {}s", .0.as_secs())]
+    BackendConnectionTimeoutError(std::time::Duration),
+    #[cfg(feature = "himalaya")]
+    #[error("cannot connect to backend: proxies are not supported yet, remove the proxy option")]
+    ProxyNotSupportedError,
+    #[cfg(feature = "himalaya")]
+    #[error("cannot complete backend operation: timed out after {}s", .0.as_secs())]
+    BackendCommandTimeoutError(std::time::Duration),
+    #[cfg(feature = "himalaya")]
+    #[error("backend operation cancelled")]
+    BackendOperationCancelled,
+    #[cfg(feature = "himalaya")]
+    #[error("cannot listen to interruption signal")]
+    ListenInterruptionSignalError(#[source] std::io::Error),
     #[cfg(all(feature = "config", feature = "himalaya"))]
     #[error("cannot create config file {}", .1.display())]
     CreateConfigFileError(#[source] std::io::Error, std::path::PathBuf),
@@ -97,6 +227,150 @@ pub enum Error {
     WriteConfigFileError(#[source] std::io::Error, std::path::PathBuf),
 }
 
+impl Error {
+    /// Get the name of the error variant, used as a stable code by
+    /// machine-readable outputs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "wizard")]
+            Self::CreateTomlConfigParentDirectoryError { .. } => {
+                "CreateTomlConfigParentDirectoryError"
+            }
+            #[cfg(feature = "wizard")]
+            Self::WriteTomlConfigError { .. } => "WriteTomlConfigError",
+            #[cfg(feature = "wizard")]
+            Self::BackupTomlConfigError { .. } => "BackupTomlConfigError",
+            #[cfg(feature = "config")]
+            Self::CreateTomlConfigFromInvalidPathsError { .. } => {
+                "CreateTomlConfigFromInvalidPathsError"
+            }
+            #[cfg(feature = "config")]
+            Self::CreateTomlConfigFromWizardError { .. } => "CreateTomlConfigFromWizardError",
+            Self::PromptNumberError { .. } => "PromptNumberError",
+            Self::PromptSecretError { .. } => "PromptSecretError",
+            Self::PromptPasswordError { .. } => "PromptPasswordError",
+            Self::PromptTextError { .. } => "PromptTextError",
+            Self::PromptAutocompleteError { .. } => "PromptAutocompleteError",
+            Self::PromptBoolError { .. } => "PromptBoolError",
+            Self::PromptItemError { .. } => "PromptItemError",
+            Self::PromptItemsError { .. } => "PromptItemsError",
+            Self::PromptColorError { .. } => "PromptColorError",
+            Self::PromptCancelled { .. } => "PromptCancelled",
+            Self::PromptBack { .. } => "PromptBack",
+            Self::PromptMaxAttemptsReached { .. } => "PromptMaxAttemptsReached",
+            Self::PromptTimedOut { .. } => "PromptTimedOut",
+            Self::InvalidPromptAnswerError { .. } => "InvalidPromptAnswerError",
+            Self::MissingPromptAnswerError { .. } => "MissingPromptAnswerError",
+            #[cfg(feature = "wizard")]
+            Self::ParseAnswerArgError { .. } => "ParseAnswerArgError",
+            #[cfg(feature = "config")]
+            Self::ReadAnswersFileError { .. } => "ReadAnswersFileError",
+            #[cfg(feature = "config")]
+            Self::ParseAnswersFileError { .. } => "ParseAnswersFileError",
+            #[cfg(feature = "email")]
+            Self::PromptEmailError { .. } => "PromptEmailError",
+            #[cfg(feature = "path")]
+            Self::PromptPathError { .. } => "PromptPathError",
+            #[cfg(feature = "oauth2")]
+            Self::OAuth2Error { .. } => "OAuth2Error",
+            #[cfg(feature = "imap")]
+            Self::AccountError { .. } => "AccountError",
+            #[cfg(feature = "imap")]
+            Self::ImapError { .. } => "ImapError",
+            #[cfg(feature = "smtp")]
+            Self::SmtpError { .. } => "SmtpError",
+            #[cfg(any(feature = "imap", feature = "smtp", feature = "pgp-native"))]
+            Self::SecretError { .. } => "SecretError",
+            #[cfg(feature = "wizard")]
+            Self::JoinError { .. } => "JoinError",
+            #[cfg(feature = "config")]
+            Self::ReadTomlConfigFileFromEmptyPaths { .. } => "ReadTomlConfigFileFromEmptyPaths",
+            #[cfg(feature = "config")]
+            Self::ReadTomlConfigFile { .. } => "ReadTomlConfigFile",
+            #[cfg(feature = "config")]
+            Self::ParseTomlConfigFile { .. } => "ParseTomlConfigFile",
+            #[cfg(feature = "config")]
+            Self::ParseMergedTomlConfigFiles { .. } => "ParseMergedTomlConfigFiles",
+            #[cfg(feature = "config")]
+            Self::ParseYamlConfigFile { .. } => "ParseYamlConfigFile",
+            #[cfg(feature = "config")]
+            Self::ParseJsonConfigFile { .. } => "ParseJsonConfigFile",
+            #[cfg(feature = "config")]
+            Self::MergeTomlConfigFiles { .. } => "MergeTomlConfigFiles",
+            #[cfg(feature = "config")]
+            Self::IncludeTomlConfigCycleError { .. } => "IncludeTomlConfigCycleError",
+            #[cfg(feature = "config")]
+            Self::ParseTomlConfigIncludeError { .. } => "ParseTomlConfigIncludeError",
+            #[cfg(feature = "config")]
+            Self::InterpolateTomlConfigEnvVarError { .. } => "InterpolateTomlConfigEnvVarError",
+            #[cfg(feature = "config-watch")]
+            Self::WatchTomlConfigError { .. } => "WatchTomlConfigError",
+            #[cfg(feature = "config")]
+            Self::DecryptTomlConfigValueError { .. } => "DecryptTomlConfigValueError",
+            #[cfg(feature = "config")]
+            Self::DecryptTomlConfigValueCommandError { .. } => "DecryptTomlConfigValueCommandError",
+            #[cfg(feature = "wizard")]
+            Self::EncryptTomlConfigValueError { .. } => "EncryptTomlConfigValueError",
+            #[cfg(feature = "wizard")]
+            Self::EncryptTomlConfigValueCommandError { .. } => "EncryptTomlConfigValueCommandError",
+            #[cfg(feature = "config")]
+            Self::GetXdgConfigDirectory { .. } => "GetXdgConfigDirectory",
+            #[cfg(feature = "config")]
+            Self::SerializeTomlConfigError { .. } => "SerializeTomlConfigError",
+            #[cfg(feature = "config")]
+            Self::SerializeYamlConfigError { .. } => "SerializeYamlConfigError",
+            #[cfg(feature = "config")]
+            Self::SerializeJsonConfigError { .. } => "SerializeJsonConfigError",
+            #[cfg(feature = "config")]
+            Self::ParseSerializedTomlConfigError { .. } => "ParseSerializedTomlConfigError",
+            #[cfg(feature = "config")]
+            Self::BuildAccountConfigError { .. } => "BuildAccountConfigError",
+            #[cfg(feature = "config")]
+            Self::GetDefaultAccountConfigError { .. } => "GetDefaultAccountConfigError",
+            #[cfg(feature = "himalaya")]
+            Self::MultipleDefaultAccountsError { .. } => "MultipleDefaultAccountsError",
+            #[cfg(feature = "himalaya")]
+            Self::AmbiguousAccountAliasError { .. } => "AmbiguousAccountAliasError",
+            #[cfg(feature = "config")]
+            Self::GetAccountConfigError { .. } => "GetAccountConfigError",
+            #[cfg(feature = "himalaya")]
+            Self::RenameAccountConflictError { .. } => "RenameAccountConflictError",
+            #[cfg(feature = "himalaya")]
+            Self::ParseEnvelopesDateError { .. } => "ParseEnvelopesDateError",
+            #[cfg(feature = "himalaya")]
+            Self::EmptyEnvelopesDateRangeError { .. } => "EmptyEnvelopesDateRangeError",
+            #[cfg(feature = "himalaya")]
+            Self::CreateDownloadsDirError { .. } => "CreateDownloadsDirError",
+            #[cfg(feature = "himalaya")]
+            Self::WriteAttachmentError { .. } => "WriteAttachmentError",
+            #[cfg(feature = "himalaya")]
+            Self::ExportMessagesError { .. } => "ExportMessagesError",
+            #[cfg(feature = "himalaya")]
+            Self::ImportMessagesError { .. } => "ImportMessagesError",
+            #[cfg(feature = "himalaya")]
+            Self::BatchOperationError { .. } => "BatchOperationError",
+            #[cfg(feature = "himalaya")]
+            Self::ExportIdAliasesError { .. } => "ExportIdAliasesError",
+            #[cfg(feature = "himalaya")]
+            Self::ImportIdAliasesError { .. } => "ImportIdAliasesError",
+            #[cfg(feature = "himalaya")]
+            Self::BackendConnectionTimeoutError { .. } => "BackendConnectionTimeoutError",
+            #[cfg(feature = "himalaya")]
+            Self::ProxyNotSupportedError { .. } => "ProxyNotSupportedError",
+            #[cfg(feature = "himalaya")]
+            Self::BackendCommandTimeoutError { .. } => "BackendCommandTimeoutError",
+            #[cfg(feature = "himalaya")]
+            Self::BackendOperationCancelled { .. } => "BackendOperationCancelled",
+            #[cfg(feature = "himalaya")]
+            Self::ListenInterruptionSignalError { .. } => "ListenInterruptionSignalError",
+            #[cfg(all(feature = "config", feature = "himalaya"))]
+            Self::CreateConfigFileError { .. } => "CreateConfigFileError",
+            #[cfg(all(feature = "config", feature = "himalaya"))]
+            Self::WriteConfigFileError { .. } => "WriteConfigFileError",
+        }
+    }
+}
+
 pub type Result<T> = result::Result<T, Error>;
 
 impl From<Error> for io::Error {
@@ -1,7 +1,18 @@
-use std::{ops::Deref, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    fs::{self, File, OpenOptions},
+    future::Future,
+    io::{self, BufRead, BufReader, Write},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
-use color_eyre::Result;
+use chrono::{Local, NaiveDate};
+use color_eyre::{eyre::eyre, Result};
 #[cfg(feature = "imap")]
 use email::imap::{ImapContext, ImapContextBuilder};
 #[cfg(feature = "maildir")]
@@ -12,6 +23,8 @@ use email::notmuch::{NotmuchContextBuilder, NotmuchContextSync};
 use email::sendmail::{SendmailContextBuilder, SendmailContextSync};
 #[cfg(feature = "smtp")]
 use email::smtp::{SmtpContextBuilder, SmtpContextSync};
+#[cfg(feature = "sync")]
+use email::sync::{SyncBuilder, SyncEvent, SyncReport};
 use email::{
     account::config::AccountConfig,
     backend::{
@@ -23,12 +36,13 @@ use email::{
     envelope::{
         list::{ListEnvelopes, ListEnvelopesOptions},
         thread::ThreadEnvelopes,
+        watch::WatchEnvelopes,
         Id, SingleId,
     },
     flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags, Flag, Flags},
     folder::{
         add::AddFolder, delete::DeleteFolder, expunge::ExpungeFolder, list::ListFolders,
-        purge::PurgeFolder,
+        purge::PurgeFolder, TRASH,
     },
     message::{
         add::AddMessage,
@@ -41,15 +55,28 @@ use email::{
         send::{SendMessage, SendMessageThenSaveCopy},
         Messages,
     },
+    search_query::{filter::SearchEmailsFilterQuery, sort::SearchEmailsSorter, SearchEmailsQuery},
+    template::Template,
+    watch::config::WatchFn,
     AnyResult,
 };
+use futures::{stream, StreamExt};
+use serde::{Serialize, Serializer};
+use tokio::{
+    sync::{oneshot, Mutex as AsyncMutex, Semaphore, SemaphorePermit},
+    time::{sleep_until, timeout},
+};
 
+use crate::{terminal::print, Error};
+
+#[cfg(feature = "sled")]
+use super::cache::EnvelopesCache;
 use super::{
     config::{self, Envelopes, HimalayaTomlAccountConfig, ThreadedEnvelopes},
-    id_mapper::IdMapper,
+    id_mapper::{AsyncIdMapper, IdMapper},
 };
 
-#[derive(BackendContext)]
+#[derive(BackendContext, Default)]
 pub struct Context {
     #[cfg(feature = "imap")]
     imap: Option<ImapContext>,
@@ -136,9 +163,19 @@ impl ContextBuilder {
                     return None;
                 };
 
+                let mut imap = imap.clone();
+
+                if let Some(size) = toml_account_config
+                    .imap_tuning
+                    .as_ref()
+                    .and_then(|tuning| tuning.clients_pool_size)
+                {
+                    imap.clients_pool_size = Some(size);
+                }
+
                 Some(ImapContextBuilder::new(
                     account_config.clone(),
-                    Arc::new(imap.clone()),
+                    Arc::new(imap),
                 ))
             }),
             #[cfg(feature = "maildir")]
@@ -205,6 +242,25 @@ impl ContextBuilder {
                 }),
         }
     }
+
+    /// Remove the sending backends, so that they are not built when
+    /// they are not needed, like during synchronization.
+    #[cfg(feature = "sync")]
+    fn without_sending_backends(mut self) -> Self {
+        self.sending_backend = None;
+
+        #[cfg(feature = "sendmail")]
+        {
+            self.sendmail = None;
+        }
+
+        #[cfg(feature = "smtp")]
+        {
+            self.smtp = None;
+        }
+
+        self
+    }
 }
 
 #[async_trait]
@@ -307,6 +363,18 @@ impl BackendContextBuilder for ContextBuilder {
         }
     }
 
+    fn watch_envelopes(&self) -> Option<BackendFeature<Self::Context, dyn WatchEnvelopes>> {
+        match self.backend.as_ref()? {
+            config::Backend::None => None,
+            #[cfg(feature = "imap")]
+            config::Backend::Imap(_) => self.watch_envelopes_with_some(&self.imap),
+            #[cfg(feature = "maildir")]
+            config::Backend::Maildir(_) => self.watch_envelopes_with_some(&self.maildir),
+            #[cfg(feature = "notmuch")]
+            config::Backend::Notmuch(_) => None,
+        }
+    }
+
     fn add_flags(&self) -> Option<BackendFeature<Self::Context, dyn AddFlags>> {
         match self.backend.as_ref()? {
             config::Backend::None => None,
@@ -438,71 +506,666 @@ impl BackendContextBuilder for ContextBuilder {
     }
 
     async fn build(self) -> AnyResult<Self::Context> {
-        #[cfg(feature = "imap")]
-        let imap = match self.imap {
-            Some(imap) => Some(imap.build().await?),
-            None => None,
+        let Self {
+            #[cfg(feature = "imap")]
+            imap,
+            #[cfg(feature = "maildir")]
+            maildir,
+            #[cfg(feature = "notmuch")]
+            notmuch,
+            #[cfg(feature = "smtp")]
+            smtp,
+            #[cfg(feature = "sendmail")]
+            sendmail,
+            ..
+        } = self;
+
+        // receiving and sending contexts both connect to their
+        // servers, so they are built concurrently
+        let build_backend = async move {
+            #[allow(unused_mut)]
+            let mut ctx = Context::default();
+
+            #[cfg(feature = "imap")]
+            if let Some(imap) = imap {
+                ctx.imap = Some(imap.build().await?);
+            }
+
+            #[cfg(feature = "maildir")]
+            if let Some(maildir) = maildir {
+                ctx.maildir = Some(maildir.build().await?);
+            }
+
+            #[cfg(feature = "notmuch")]
+            if let Some(notmuch) = notmuch {
+                ctx.notmuch = Some(notmuch.build().await?);
+            }
+
+            AnyResult::Ok(ctx)
         };
 
-        #[cfg(feature = "maildir")]
-        let maildir = match self.maildir {
-            Some(maildir) => Some(maildir.build().await?),
-            None => None,
+        let build_sending_backend = async move {
+            #[allow(unused_mut)]
+            let mut ctx = Context::default();
+
+            #[cfg(feature = "smtp")]
+            if let Some(smtp) = smtp {
+                ctx.smtp = Some(smtp.build().await?);
+            }
+
+            #[cfg(feature = "sendmail")]
+            if let Some(sendmail) = sendmail {
+                ctx.sendmail = Some(sendmail.build().await?);
+            }
+
+            AnyResult::Ok(ctx)
         };
 
-        #[cfg(feature = "notmuch")]
-        let notmuch = match self.notmuch {
-            Some(notmuch) => Some(notmuch.build().await?),
-            None => None,
+        let (ctx, sending_ctx) = tokio::join!(build_backend, build_sending_backend);
+        #[allow(unused_variables)]
+        let (ctx, sending_ctx) = (ctx?, sending_ctx?);
+
+        Ok(Context {
+            #[cfg(feature = "smtp")]
+            smtp: sending_ctx.smtp,
+            #[cfg(feature = "sendmail")]
+            sendmail: sending_ctx.sendmail,
+            ..ctx
+        })
+    }
+}
+
+/// Backend feature a command needs, see
+/// [`BackendBuilder::with_required_features`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RequiredFeature {
+    Folders,
+    Envelopes,
+    Flags,
+    Messages,
+    /// Sending messages, which is the only feature relying on the
+    /// sending backend.
+    SendMessage,
+}
+
+impl RequiredFeature {
+    fn needs_sending_backend(&self) -> bool {
+        matches!(self, Self::SendMessage)
+    }
+}
+
+/// Options of operations on many messages.
+///
+/// Ids are split into chunks processed concurrently, so that large
+/// operations do not time out, and so that a failing chunk does not
+/// abort the others.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BatchOptions {
+    /// Maximum number of messages per backend call.
+    pub chunk_size: usize,
+    /// Maximum number of backend calls running at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 500,
+            concurrency: 4,
+        }
+    }
+}
+
+/// Chunk of ids that failed during a batched operation.
+#[derive(Debug)]
+pub struct BatchFailure {
+    pub ids: Vec<usize>,
+    pub err: color_eyre::Report,
+}
+
+/// Total amount of work of a backend operation, see
+/// [`ProgressObserver`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProgressTotal {
+    /// The amount of work is not known in advance, like when listing
+    /// envelopes.
+    Unknown,
+    Items(u64),
+    Bytes(u64),
+}
+
+/// Observer of the progress of long backend operations, see
+/// [`BackendBuilder::with_progress`].
+pub trait ProgressObserver: Send + Sync {
+    /// An operation started, with the given amount of work.
+    fn start(&self, label: &str, total: ProgressTotal);
+
+    /// The running operation processed the given amount of work.
+    fn inc(&self, delta: u64);
+
+    /// The running operation is over, successfully or not.
+    fn finish(&self);
+}
+
+/// Progress observer drawing on the terminal: a progress bar when
+/// the amount of work is known, a spinner otherwise.
+#[derive(Debug, Default)]
+pub struct TerminalProgress(Mutex<Option<TerminalProgressState>>);
+
+#[derive(Debug)]
+enum TerminalProgressState {
+    Bar(print::ProgressBar),
+    Spinner(print::Spinner),
+}
+
+impl ProgressObserver for TerminalProgress {
+    fn start(&self, label: &str, total: ProgressTotal) {
+        let state = match total {
+            ProgressTotal::Unknown => TerminalProgressState::Spinner(print::spinner(label)),
+            ProgressTotal::Items(total) => {
+                TerminalProgressState::Bar(print::progress(label, total))
+            }
+            ProgressTotal::Bytes(total) => {
+                TerminalProgressState::Bar(print::progress(label, total).with_bytes())
+            }
         };
 
-        #[cfg(feature = "smtp")]
-        let smtp = match self.smtp {
-            Some(smtp) => Some(smtp.build().await?),
-            None => None,
+        if let Ok(mut current) = self.0.lock() {
+            *current = Some(state);
+        }
+    }
+
+    fn inc(&self, delta: u64) {
+        if let Ok(mut current) = self.0.lock() {
+            if let Some(TerminalProgressState::Bar(bar)) = current.as_mut() {
+                bar.inc(delta);
+            }
+        }
+    }
+
+    fn finish(&self) {
+        let state = match self.0.lock() {
+            Ok(mut current) => current.take(),
+            Err(_) => None,
         };
 
-        #[cfg(feature = "sendmail")]
-        let sendmail = match self.sendmail {
-            Some(sendmail) => Some(sendmail.build().await?),
+        if let Some(TerminalProgressState::Bar(bar)) = state {
+            bar.finish();
+        }
+    }
+}
+
+/// Progress of the running operation, finished when dropped.
+struct ProgressGuard<'a>(Option<&'a dyn ProgressObserver>);
+
+impl ProgressGuard<'_> {
+    fn inc(&self, delta: u64) {
+        if let Some(observer) = self.0 {
+            observer.inc(delta);
+        }
+    }
+}
+
+impl Drop for ProgressGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(observer) = self.0 {
+            observer.finish();
+        }
+    }
+}
+
+/// Client-side limiter of backend operations, see
+/// [`config::RateLimitConfig`].
+#[derive(Debug)]
+struct RateLimiter {
+    semaphore: Option<Semaphore>,
+    interval: Option<Duration>,
+    /// Instant from which the next operation can start.
+    next: AsyncMutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    fn new(config: &config::RateLimitConfig) -> Self {
+        Self {
+            semaphore: config
+                .max_concurrent_commands
+                .map(|max| Semaphore::new(max.max(1))),
+            interval: config
+                .requests_per_second
+                .map(|rps| Duration::from_secs(1) / rps.max(1)),
+            next: AsyncMutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    /// Wait until an operation can start.
+    ///
+    /// The returned permit must be held while the operation runs.
+    async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match &self.semaphore {
+            // the semaphore is never closed
+            Some(semaphore) => semaphore.acquire().await.ok(),
             None => None,
         };
 
-        Ok(Context {
-            #[cfg(feature = "imap")]
-            imap,
-            #[cfg(feature = "maildir")]
-            maildir,
-            #[cfg(feature = "notmuch")]
-            notmuch,
-            #[cfg(feature = "smtp")]
-            smtp,
-            #[cfg(feature = "sendmail")]
-            sendmail,
+        if let Some(interval) = self.interval {
+            let mut next = self.next.lock().await;
+            let start = (*next).max(tokio::time::Instant::now());
+            *next = start + interval;
+            drop(next);
+            sleep_until(start).await;
+        }
+
+        permit
+    }
+}
+
+/// Statistics of a backend operation, see [`OperationMetrics`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct OperationStats {
+    pub calls: u64,
+    pub failures: u64,
+    /// Retries are counted when recorded with
+    /// [`OperationMetrics::record_retry`], the backend does not retry
+    /// operations by itself.
+    pub retries: u64,
+    /// Size of the messages sent or received, when known.
+    pub bytes: u64,
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_millis())
+}
+
+/// Metrics of the operations of a backend, per operation name, see
+/// [`BackendBuilder::with_metrics`].
+///
+/// Metrics help diagnosing slow providers: they can be logged with
+/// [`OperationMetrics::trace`], or printed as they implement both
+/// [`fmt::Display`] and [`Serialize`].
+#[derive(Debug, Default)]
+pub struct OperationMetrics(Mutex<BTreeMap<&'static str, OperationStats>>);
+
+impl OperationMetrics {
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut OperationStats)) {
+        if let Ok(mut stats) = self.0.lock() {
+            f(stats.entry(name).or_default())
+        }
+    }
+
+    /// Record a call of the given operation.
+    pub fn record(&self, name: &'static str, duration: Duration, success: bool) {
+        self.update(name, |stats| {
+            stats.calls += 1;
+            stats.duration += duration;
+
+            if !success {
+                stats.failures += 1;
+            }
         })
     }
+
+    pub fn record_bytes(&self, name: &'static str, bytes: u64) {
+        self.update(name, |stats| stats.bytes += bytes)
+    }
+
+    pub fn record_retry(&self, name: &'static str) {
+        self.update(name, |stats| stats.retries += 1)
+    }
+
+    /// Get a snapshot of the statistics, sorted by operation name.
+    pub fn stats(&self) -> BTreeMap<&'static str, OperationStats> {
+        match self.0.lock() {
+            Ok(stats) => stats.clone(),
+            Err(_) => Default::default(),
+        }
+    }
+
+    /// Log the statistics of every operation at debug level.
+    pub fn trace(&self) {
+        for (name, stats) in self.stats() {
+            tracing::debug!(
+                calls = stats.calls,
+                failures = stats.failures,
+                retries = stats.retries,
+                bytes = stats.bytes,
+                duration_ms = stats.duration.as_millis() as u64,
+                "backend operation {name}"
+            );
+        }
+    }
+}
+
+impl fmt::Display for OperationMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, stats) in self.stats() {
+            let average = stats.duration / stats.calls.max(1) as u32;
+
+            write!(
+                f,
+                "{name}: {} call(s) in {:.3}s (avg {}ms)",
+                stats.calls,
+                stats.duration.as_secs_f64(),
+                average.as_millis(),
+            )?;
+
+            if stats.failures > 0 {
+                write!(f, ", {} failure(s)", stats.failures)?;
+            }
+
+            if stats.retries > 0 {
+                write!(f, ", {} retry(ies)", stats.retries)?;
+            }
+
+            if stats.bytes > 0 {
+                write!(f, ", {} byte(s)", stats.bytes)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for OperationMetrics {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.stats().serialize(serializer)
+    }
 }
 
 pub struct Backend {
     toml_account_config: Arc<HimalayaTomlAccountConfig>,
     backend: email::backend::Backend<Context>,
+    batch_options: BatchOptions,
+    progress: Option<Arc<dyn ProgressObserver>>,
+    metrics: Option<Arc<OperationMetrics>>,
+    rate_limiter: Option<RateLimiter>,
+    memory_id_mappers: Mutex<HashMap<String, IdMapper>>,
+    #[cfg(feature = "sled")]
+    envelopes_cache: bool,
+    command_timeout: Option<Duration>,
 }
 
 impl Backend {
+    /// Run the given operation, failing after the command timeout of
+    /// the backend, see [`config::ImapTuningConfig`].
+    ///
+    /// The operation waits for the rate limiter first, see
+    /// [`config::RateLimitConfig`]. It is then recorded under the
+    /// given name when metrics are enabled, see
+    /// [`BackendBuilder::with_metrics`].
+    async fn timed<T>(
+        &self,
+        name: &'static str,
+        op: impl Future<Output = AnyResult<T>>,
+    ) -> Result<T> {
+        let _permit = match &self.rate_limiter {
+            Some(limiter) => limiter.acquire().await,
+            None => None,
+        };
+
+        let start = Instant::now();
+
+        let res = match self.command_timeout {
+            None => op.await.map_err(Into::into),
+            Some(duration) => match timeout(duration, op).await {
+                Ok(res) => res.map_err(Into::into),
+                Err(_) => Err(Error::BackendCommandTimeoutError(duration).into()),
+            },
+        };
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record(name, start.elapsed(), res.is_ok());
+        }
+
+        res
+    }
+
+    fn record_bytes(&self, name: &'static str, bytes: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_bytes(name, bytes as u64);
+        }
+    }
+
+    /// Get the metrics of the backend operations, when enabled, see
+    /// [`BackendBuilder::with_metrics`].
+    pub fn metrics(&self) -> Option<&OperationMetrics> {
+        self.metrics.as_deref()
+    }
+
+    fn progress(&self, label: &str, total: ProgressTotal) -> ProgressGuard<'_> {
+        let observer = self.progress.as_deref();
+
+        if let Some(observer) = observer {
+            observer.start(label, total);
+        }
+
+        ProgressGuard(observer)
+    }
+
+    /// Run the given operation on chunks of the given ids, see
+    /// [`BatchOptions`].
+    ///
+    /// Ids fitting in one chunk are processed in one call, and its
+    /// error is returned as it is. Otherwise failures of all chunks
+    /// are collected into [`Error::BatchOperationError`].
+    async fn batch<F, Fut>(&self, label: &str, ids: &[usize], f: F) -> Result<()>
+    where
+        F: Fn(Vec<usize>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let BatchOptions {
+            chunk_size,
+            concurrency,
+        } = self.batch_options;
+        let chunk_size = chunk_size.max(1);
+        let progress = self.progress(label, ProgressTotal::Items(ids.len() as u64));
+
+        if ids.len() <= chunk_size {
+            f(ids.to_vec()).await?;
+            progress.inc(ids.len() as u64);
+            return Ok(());
+        }
+
+        let progress = &progress;
+        let tasks: Vec<_> = ids
+            .chunks(chunk_size)
+            .map(|ids| {
+                let ids = ids.to_vec();
+                let task = f(ids.clone());
+                async move {
+                    let res = task.await;
+                    progress.inc(ids.len() as u64);
+                    res.err().map(|err| BatchFailure { ids, err })
+                }
+            })
+            .collect();
+
+        let failures: Vec<BatchFailure> = stream::iter(tasks)
+            .buffer_unordered(concurrency.max(1))
+            .filter_map(|failure| async move { failure })
+            .collect()
+            .await;
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::BatchOperationError(failures, ids.len()).into())
+        }
+    }
+
     fn build_id_mapper(&self, folder: &str, backend: Option<&config::Backend>) -> Result<IdMapper> {
-        #[cfg(all(feature = "maildir", feature = "sled"))]
+        #[cfg(feature = "maildir")]
         if let Some(config::Backend::Maildir(_)) = backend {
-            return Ok(IdMapper::new(&self.backend.account_config, folder)?);
+            return self.build_folder_id_mapper(folder);
         }
 
-        #[cfg(all(feature = "notmuch", feature = "sled"))]
+        #[cfg(feature = "notmuch")]
         if let Some(config::Backend::Notmuch(_)) = backend {
-            return Ok(IdMapper::new(&self.backend.account_config, folder)?);
+            return self.build_folder_id_mapper(folder);
         }
 
         Ok(IdMapper::Dummy)
     }
 
+    /// Build the id mapper of the given folder, depending on the id
+    /// mapper kind of the account, see [`config::IdMapperKind`].
+    ///
+    /// In-memory mappers are shared by all the operations of the
+    /// backend, so that aliases are stable for its lifetime.
+    #[cfg(any(feature = "maildir", feature = "notmuch"))]
+    fn build_folder_id_mapper(&self, folder: &str) -> Result<IdMapper> {
+        match self.toml_account_config.id_mapper.unwrap_or_default() {
+            config::IdMapperKind::Memory => {
+                let mut id_mappers = self
+                    .memory_id_mappers
+                    .lock()
+                    .map_err(|_| eyre!("cannot lock in-memory id mappers"))?;
+
+                let id_mapper = id_mappers
+                    .entry(folder.to_owned())
+                    .or_insert_with(IdMapper::new_memory);
+
+                Ok(id_mapper.clone())
+            }
+            #[cfg(feature = "sled")]
+            config::IdMapperKind::Persistent => Ok(IdMapper::new(
+                self.toml_account_config.id_map_dir.as_deref(),
+                &self.backend.account_config,
+                folder,
+            )?),
+            #[cfg(not(feature = "sled"))]
+            config::IdMapperKind::Persistent => Ok(IdMapper::Dummy),
+        }
+    }
+
+    #[cfg(feature = "sled")]
+    fn build_envelopes_cache(&self, folder: &str) -> Result<Option<EnvelopesCache>> {
+        if !self.envelopes_cache {
+            return Ok(None);
+        }
+
+        let cache = EnvelopesCache::new(&self.backend.account_config, folder)?;
+        Ok(Some(cache))
+    }
+
+    /// Store the given listing into the envelopes cache, or fall back
+    /// to the cached listing when the backend failed.
+    ///
+    /// Returns the listing along with whether it comes from the
+    /// cache, so that callers can tell the user. The cache never
+    /// fails a listing: cache errors are logged as warnings.
+    #[cfg(feature = "sled")]
+    fn cache_envelopes<T>(
+        &self,
+        folder: &str,
+        opts: &ListEnvelopesOptions,
+        res: Result<T>,
+        get: fn(&EnvelopesCache, &ListEnvelopesOptions) -> Result<Option<T>>,
+        set: fn(&EnvelopesCache, &ListEnvelopesOptions, &T) -> Result<()>,
+    ) -> Result<(T, bool)> {
+        let cache = match self.build_envelopes_cache(folder) {
+            Ok(Some(cache)) => cache,
+            Ok(None) => return Ok((res?, false)),
+            Err(err) => {
+                tracing::warn!("{err:#}");
+                return Ok((res?, false));
+            }
+        };
+
+        match res {
+            Ok(envelopes) => {
+                if let Err(err) = set(&cache, opts, &envelopes) {
+                    tracing::warn!("{err:#}");
+                }
+                Ok((envelopes, false))
+            }
+            Err(err) => match get(&cache, opts) {
+                Ok(Some(envelopes)) => {
+                    tracing::warn!("{err:#}, falling back to cached envelopes");
+                    Ok((envelopes, true))
+                }
+                Ok(None) => Err(err),
+                Err(cache_err) => {
+                    tracing::warn!("{cache_err:#}");
+                    Err(err)
+                }
+            },
+        }
+    }
+
+    /// Clear the envelopes cache of the given folder, whose cached
+    /// listings may not match the backend anymore.
+    ///
+    /// Called after every operation changing messages of the folder,
+    /// even failed ones since they may have partially succeeded.
+    #[cfg(feature = "sled")]
+    fn invalidate_envelopes_cache(&self, folder: &str) {
+        let res = self
+            .build_envelopes_cache(folder)
+            .and_then(|cache| cache.map(|cache| cache.clear()).transpose());
+
+        if let Err(err) = res {
+            tracing::warn!("{err:#}");
+        }
+    }
+
+    #[cfg(not(feature = "sled"))]
+    fn invalidate_envelopes_cache(&self, _folder: &str) {}
+
+    /// List envelopes from the envelopes cache, without reaching the
+    /// backend, so that they can be displayed while a listing
+    /// refreshes them.
+    ///
+    /// Returns `None` when the cache is disabled or empty, see
+    /// [`BackendBuilder::with_envelopes_cache`].
+    #[cfg(feature = "sled")]
+    pub fn list_cached_envelopes(
+        &self,
+        folder: &str,
+        opts: &ListEnvelopesOptions,
+    ) -> Result<Option<Envelopes>> {
+        let Some(envelopes) = self
+            .build_envelopes_cache(folder)?
+            .map(|cache| cache.list(opts))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+
+        let backend_kind = self.toml_account_config.backend.as_ref();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let envelopes =
+            Envelopes::try_from_backend(&self.backend.account_config, &id_mapper, envelopes)?;
+        Ok(Some(envelopes.with_cached(true)))
+    }
+
+    /// Thread envelopes from the envelopes cache, see
+    /// [`Backend::list_cached_envelopes`].
+    #[cfg(feature = "sled")]
+    pub fn thread_cached_envelopes(
+        &self,
+        folder: &str,
+        opts: &ListEnvelopesOptions,
+    ) -> Result<Option<ThreadedEnvelopes>> {
+        let Some(envelopes) = self
+            .build_envelopes_cache(folder)?
+            .map(|cache| cache.thread(opts))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+
+        let backend_kind = self.toml_account_config.backend.as_ref();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let envelopes = ThreadedEnvelopes::try_from_backend(&id_mapper, envelopes)?;
+        Ok(Some(envelopes.with_cached(true)))
+    }
+
     pub async fn list_envelopes(
         &self,
         folder: &str,
@@ -510,10 +1173,30 @@ impl Backend {
     ) -> Result<Envelopes> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let envelopes = self.backend.list_envelopes(folder, opts).await?;
+        let progress = self.progress("Listing envelopes", ProgressTotal::Unknown);
+        let res = self
+            .timed(
+                "list_envelopes",
+                self.backend.list_envelopes(folder, opts.clone()),
+            )
+            .await;
+        drop(progress);
+
+        #[cfg(feature = "sled")]
+        let (envelopes, cached) = self.cache_envelopes(
+            folder,
+            &opts,
+            res,
+            EnvelopesCache::list,
+            EnvelopesCache::set_list,
+        )?;
+        #[cfg(not(feature = "sled"))]
+        let (envelopes, cached) = (res?, false);
+
         let envelopes =
-            Envelopes::try_from_backend(&self.backend.account_config, &id_mapper, envelopes)?;
-        Ok(envelopes)
+            Envelopes::try_from_backend_async(&self.backend.account_config, &id_mapper, envelopes)
+                .await?;
+        Ok(envelopes.with_cached(cached))
     }
 
     pub async fn thread_envelopes(
@@ -523,9 +1206,28 @@ impl Backend {
     ) -> Result<ThreadedEnvelopes> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let envelopes = self.backend.thread_envelopes(folder, opts).await?;
-        let envelopes = ThreadedEnvelopes::try_from_backend(&id_mapper, envelopes)?;
-        Ok(envelopes)
+        let progress = self.progress("Threading envelopes", ProgressTotal::Unknown);
+        let res = self
+            .timed(
+                "thread_envelopes",
+                self.backend.thread_envelopes(folder, opts.clone()),
+            )
+            .await;
+        drop(progress);
+
+        #[cfg(feature = "sled")]
+        let (envelopes, cached) = self.cache_envelopes(
+            folder,
+            &opts,
+            res,
+            EnvelopesCache::thread,
+            EnvelopesCache::set_thread,
+        )?;
+        #[cfg(not(feature = "sled"))]
+        let (envelopes, cached) = (res?, false);
+
+        let envelopes = ThreadedEnvelopes::try_from_backend_async(&id_mapper, envelopes).await?;
+        Ok(envelopes.with_cached(cached))
     }
 
     pub async fn thread_envelope(
@@ -537,66 +1239,191 @@ impl Backend {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
         let id = id_mapper.get_id(id)?;
+        let progress = self.progress("Threading envelopes", ProgressTotal::Unknown);
         let envelopes = self
-            .backend
-            .thread_envelope(folder, SingleId::from(id), opts)
+            .timed(
+                "thread_envelope",
+                self.backend
+                    .thread_envelope(folder, SingleId::from(id), opts),
+            )
             .await?;
-        let envelopes = ThreadedEnvelopes::try_from_backend(&id_mapper, envelopes)?;
+        drop(progress);
+        let envelopes = ThreadedEnvelopes::try_from_backend_async(&id_mapper, envelopes).await?;
         Ok(envelopes)
     }
 
+    /// Watch the given folder for envelope changes, using IMAP IDLE
+    /// or file system notifications for Maildir.
+    ///
+    /// The handler is called for every received envelope, on top of
+    /// the watch hooks of the account configuration. Watching uses
+    /// its own backend, since IMAP IDLE holds the session for as long
+    /// as it runs. It stops on error, or when the returned future is
+    /// dropped.
+    pub async fn watch_envelopes(&self, folder: &str, handler: WatchFn) -> Result<()> {
+        let mut account_config = (*self.backend.account_config).clone();
+        account_config
+            .envelope
+            .get_or_insert_with(Default::default)
+            .watch
+            .get_or_insert_with(Default::default)
+            .received
+            .get_or_insert_with(Default::default)
+            .callback = Some(handler);
+
+        let backend = BackendBuilder::new(
+            self.toml_account_config.clone(),
+            Arc::new(account_config),
+            |builder| builder,
+        )
+        .without_sending_backend()
+        .build()
+        .await?;
+
+        // the shutdown request sender must outlive the watch, since
+        // dropping it is interpreted as a shutdown request
+        let (_shutdown_request, wait_for_shutdown_request) = oneshot::channel();
+        let (shutdown, _) = oneshot::channel();
+
+        backend
+            .backend
+            .watch_envelopes(folder, wait_for_shutdown_request, shutdown)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn add_flags(&self, folder: &str, ids: &[usize], flags: &Flags) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.add_flags(folder, &ids, flags).await?;
-        Ok(())
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Adding flags", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed("add_flags", self.backend.add_flags(folder, &ids, flags))
+                    .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
     }
 
     pub async fn add_flag(&self, folder: &str, ids: &[usize], flag: Flag) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.add_flag(folder, &ids, flag).await?;
-        Ok(())
+        let flag = &flag;
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Adding flags", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "add_flag",
+                    self.backend.add_flag(folder, &ids, flag.clone()),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
     }
 
     pub async fn set_flags(&self, folder: &str, ids: &[usize], flags: &Flags) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.set_flags(folder, &ids, flags).await?;
-        Ok(())
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Setting flags", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed("set_flags", self.backend.set_flags(folder, &ids, flags))
+                    .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
     }
 
     pub async fn set_flag(&self, folder: &str, ids: &[usize], flag: Flag) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.set_flag(folder, &ids, flag).await?;
-        Ok(())
+        let flag = &flag;
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Setting flags", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "set_flag",
+                    self.backend.set_flag(folder, &ids, flag.clone()),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
     }
 
     pub async fn remove_flags(&self, folder: &str, ids: &[usize], flags: &Flags) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.remove_flags(folder, &ids, flags).await?;
-        Ok(())
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Removing flags", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "remove_flags",
+                    self.backend.remove_flags(folder, &ids, flags),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
     }
 
     pub async fn remove_flag(&self, folder: &str, ids: &[usize], flag: Flag) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.remove_flag(folder, &ids, flag).await?;
-        Ok(())
+        let flag = &flag;
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Removing flags", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "remove_flag",
+                    self.backend.remove_flag(folder, &ids, flag.clone()),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
     }
 
     pub async fn add_message(&self, folder: &str, email: &[u8]) -> Result<SingleId> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let id = self.backend.add_message(folder, email).await?;
+        let id = self
+            .timed("add_message", self.backend.add_message(folder, email))
+            .await?;
+        self.record_bytes("add_message", email.len());
+        self.invalidate_envelopes_cache(folder);
         id_mapper.create_alias(&*id)?;
         Ok(id)
     }
@@ -610,9 +1437,13 @@ impl Backend {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
         let id = self
-            .backend
-            .add_message_with_flags(folder, email, flags)
+            .timed(
+                "add_message_with_flags",
+                self.backend.add_message_with_flags(folder, email, flags),
+            )
             .await?;
+        self.record_bytes("add_message_with_flags", email.len());
+        self.invalidate_envelopes_cache(folder);
         id_mapper.create_alias(&*id)?;
         Ok(id)
     }
@@ -620,19 +1451,303 @@ impl Backend {
     pub async fn get_messages(&self, folder: &str, ids: &[usize]) -> Result<Messages> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        let msgs = self.backend.get_messages(folder, &ids).await?;
+        let ids = Id::multiple(id_mapper.get_ids_async(ids).await?);
+        let progress = self.progress("Fetching messages", ProgressTotal::Items(ids.len() as u64));
+        let msgs = self
+            .timed("get_messages", self.backend.get_messages(folder, &ids))
+            .await?;
+        progress.inc(ids.len() as u64);
+        self.record_bytes("get_messages", messages_size(&msgs));
         Ok(msgs)
     }
 
     pub async fn peek_messages(&self, folder: &str, ids: &[usize]) -> Result<Messages> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        let msgs = self.backend.peek_messages(folder, &ids).await?;
+        let ids = Id::multiple(id_mapper.get_ids_async(ids).await?);
+        let progress = self.progress("Fetching messages", ProgressTotal::Items(ids.len() as u64));
+        let msgs = self
+            .timed("peek_messages", self.backend.peek_messages(folder, &ids))
+            .await?;
+        progress.inc(ids.len() as u64);
+        self.record_bytes("peek_messages", messages_size(&msgs));
         Ok(msgs)
     }
 
+    /// Build the template of a reply to the given message, using the
+    /// template options and the signature of the account.
+    ///
+    /// When `all` is true, the reply is also addressed to all the
+    /// recipients of the message.
+    pub async fn reply_template(&self, folder: &str, id: usize, all: bool) -> Result<Template> {
+        let msgs = self.get_messages(folder, &[id]).await?;
+        let msg = msgs
+            .first()
+            .ok_or_else(|| eyre!("cannot find message {id} in folder {folder}"))?;
+
+        let tpl = msg
+            .to_reply_tpl_builder(self.backend.account_config.clone())
+            .with_reply_all(all)
+            .build()
+            .await?;
+
+        Ok(tpl)
+    }
+
+    /// Build the template forwarding the given message, using the
+    /// template options and the signature of the account.
+    pub async fn forward_template(&self, folder: &str, id: usize) -> Result<Template> {
+        let msgs = self.get_messages(folder, &[id]).await?;
+        let msg = msgs
+            .first()
+            .ok_or_else(|| eyre!("cannot find message {id} in folder {folder}"))?;
+
+        let tpl = msg
+            .to_forward_tpl_builder(self.backend.account_config.clone())
+            .build()
+            .await?;
+
+        Ok(tpl)
+    }
+
+    /// Download the attachments of the given messages into the
+    /// downloads directory, and return the paths of the saved files.
+    ///
+    /// File names are sanitized, and suffixed with a number when a
+    /// file with the same name already exists. Messages are fetched
+    /// one by one, so that attachments are named after the right
+    /// message whatever order the backend returns messages in.
+    pub async fn download_attachments(&self, folder: &str, ids: &[usize]) -> Result<Vec<PathBuf>> {
+        let backend_kind = self.toml_account_config.backend.as_ref();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let dir = self.backend.account_config.downloads_dir();
+
+        fs::create_dir_all(&dir).map_err(|err| Error::CreateDownloadsDirError(err, dir.clone()))?;
+
+        let progress = self.progress(
+            "Downloading attachments",
+            ProgressTotal::Items(ids.len() as u64),
+        );
+        let mut paths = Vec::new();
+
+        for id in ids {
+            let msg_id = Id::single(id_mapper.get_id(id)?);
+            let msgs = self
+                .timed("get_messages", self.backend.get_messages(folder, &msg_id))
+                .await?;
+            self.record_bytes("get_messages", messages_size(&msgs));
+
+            for msg in msgs.to_vec() {
+                for (i, attachment) in msg.attachments()?.into_iter().enumerate() {
+                    let filename = attachment
+                        .filename
+                        .as_deref()
+                        .map(sanitize_filename)
+                        .filter(|filename| !filename.is_empty())
+                        .unwrap_or_else(|| format!("{id}-attachment-{}", i + 1));
+
+                    let path = write_new_file(
+                        &dir,
+                        &filename,
+                        &attachment.body,
+                        Error::WriteAttachmentError,
+                    )?;
+                    paths.push(path);
+                }
+            }
+
+            progress.inc(1);
+        }
+
+        Ok(paths)
+    }
+
+    /// Export the given messages to the given destination, and
+    /// return the paths of the written files.
+    ///
+    /// Messages are appended to the destination file when exporting
+    /// to mbox, and written as individual files into the destination
+    /// directory when exporting to EML. Messages are fetched one by
+    /// one without being marked as seen.
+    pub async fn export_messages(
+        &self,
+        folder: &str,
+        ids: &[usize],
+        format: ExportFormat,
+        dest: impl AsRef<Path>,
+    ) -> Result<Vec<PathBuf>> {
+        let dest = dest.as_ref();
+        let backend_kind = self.toml_account_config.backend.as_ref();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let progress = self.progress("Exporting messages", ProgressTotal::Items(ids.len() as u64));
+
+        let mut mbox = match format {
+            ExportFormat::Eml => {
+                fs::create_dir_all(dest)
+                    .map_err(|err| Error::ExportMessagesError(err, dest.to_owned()))?;
+                None
+            }
+            ExportFormat::Mbox => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(dest)
+                    .map_err(|err| Error::ExportMessagesError(err, dest.to_owned()))?;
+                Some(file)
+            }
+        };
+
+        let mut paths = Vec::new();
+
+        for id in ids {
+            let msg_id = Id::single(id_mapper.get_id(id)?);
+            let msgs = self
+                .timed("peek_messages", self.backend.peek_messages(folder, &msg_id))
+                .await?;
+
+            for msg in msgs.to_vec() {
+                let raw = msg.raw()?;
+
+                match &mut mbox {
+                    Some(file) => {
+                        file.write_all(&to_mbox_entry(raw))
+                            .map_err(|err| Error::ExportMessagesError(err, dest.to_owned()))?;
+                    }
+                    None => {
+                        let filename = format!("{id}.eml");
+                        let path =
+                            write_new_file(dest, &filename, raw, Error::ExportMessagesError)?;
+                        paths.push(path);
+                    }
+                }
+            }
+
+            progress.inc(1);
+        }
+
+        if mbox.is_some() {
+            paths.push(dest.to_owned());
+        }
+
+        Ok(paths)
+    }
+
+    /// Import messages into the given folder, from a mbox file or
+    /// from a directory of `.eml` files.
+    ///
+    /// Messages whose Message-ID already exists in the folder are
+    /// skipped. The mbox file is read message by message.
+    pub async fn import_messages(
+        &self,
+        folder: &str,
+        source: impl AsRef<Path>,
+    ) -> Result<ImportedMessages> {
+        let source = source.as_ref();
+        let map_err = |err: io::Error| Error::ImportMessagesError(err, source.to_owned());
+
+        let opts = ListEnvelopesOptions {
+            page: 0,
+            page_size: 0,
+            query: None,
+        };
+
+        let mut message_ids: HashSet<String> = self
+            .timed("list_envelopes", self.backend.list_envelopes(folder, opts))
+            .await?
+            .iter()
+            .map(|envelope| normalize_message_id(&envelope.message_id))
+            .collect();
+
+        let mut report = ImportedMessages::default();
+
+        if source.is_dir() {
+            let mut paths: Vec<PathBuf> = fs::read_dir(source)
+                .map_err(map_err)?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("eml"))
+                })
+                .collect();
+            paths.sort();
+
+            let progress = self.progress(
+                "Importing messages",
+                ProgressTotal::Items(paths.len() as u64),
+            );
+
+            for path in paths {
+                let raw =
+                    fs::read(&path).map_err(|err| Error::ImportMessagesError(err, path.clone()))?;
+                self.import_message(folder, &raw, &mut message_ids, &mut report)
+                    .await?;
+                progress.inc(1);
+            }
+        } else {
+            let file = File::open(source).map_err(map_err)?;
+            let size = file.metadata().map_err(map_err)?.len();
+            let progress = self.progress("Importing messages", ProgressTotal::Bytes(size));
+
+            let mut reader = BufReader::new(file);
+            let mut line = Vec::new();
+            let mut msg = Vec::new();
+
+            loop {
+                line.clear();
+                let n = reader.read_until(b'\n', &mut line).map_err(map_err)?;
+
+                // separator lines are not part of messages
+                if n == 0 || line.starts_with(b"From ") {
+                    if !msg.is_empty() {
+                        if msg.ends_with(b"\n\n") {
+                            msg.pop();
+                        }
+
+                        self.import_message(folder, &msg, &mut message_ids, &mut report)
+                            .await?;
+                        msg.clear();
+                    }
+
+                    if n == 0 {
+                        break;
+                    }
+                } else {
+                    // unquote mboxrd `>From ` lines
+                    let quotes = line.iter().take_while(|b| **b == b'>').count();
+
+                    if quotes > 0 && line[quotes..].starts_with(b"From ") {
+                        msg.extend_from_slice(&line[1..]);
+                    } else {
+                        msg.extend_from_slice(&line);
+                    }
+                }
+
+                progress.inc(n as u64);
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn import_message(
+        &self,
+        folder: &str,
+        raw: &[u8],
+        message_ids: &mut HashSet<String>,
+        report: &mut ImportedMessages,
+    ) -> Result<()> {
+        if let Some(message_id) = parse_message_id(raw) {
+            if !message_ids.insert(message_id) {
+                report.duplicates += 1;
+                return Ok(());
+            }
+        }
+
+        self.add_message(folder, raw).await?;
+        report.imported += 1;
+        Ok(())
+    }
+
     pub async fn copy_messages(
         &self,
         from_folder: &str,
@@ -641,11 +1756,22 @@ impl Backend {
     ) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(from_folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend
-            .copy_messages(from_folder, to_folder, &ids)
-            .await?;
-        Ok(())
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Copying messages", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "copy_messages",
+                    self.backend.copy_messages(from_folder, to_folder, &ids),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(to_folder);
+        res
     }
 
     pub async fn move_messages(
@@ -656,27 +1782,141 @@ impl Backend {
     ) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(from_folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend
-            .move_messages(from_folder, to_folder, &ids)
-            .await?;
-        Ok(())
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Moving messages", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "move_messages",
+                    self.backend.move_messages(from_folder, to_folder, &ids),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(from_folder);
+        self.invalidate_envelopes_cache(to_folder);
+        res
     }
 
+    /// Delete messages using the delete style of the account, see
+    /// [`Backend::delete_messages_with_style`].
     pub async fn delete_messages(&self, folder: &str, ids: &[usize]) -> Result<()> {
+        // the email crate only knows how to move and flag messages
+        if self.delete_style() == config::DeleteMessageStyle::Expunge {
+            return self.remove_messages(folder, ids).await;
+        }
+
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.delete_messages(folder, &ids).await?;
-        Ok(())
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Deleting messages", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "delete_messages",
+                    self.backend.delete_messages(folder, &ids),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
+    }
+
+    /// Export the aliases of the message ids of the given folder, as
+    /// a JSON object of aliases by id.
+    ///
+    /// Aliases can be restored on another machine with
+    /// [`Backend::import_id_aliases`], so that message numbers stay
+    /// the same. They can also be attached to bug reports.
+    pub fn export_id_aliases(&self, folder: &str) -> Result<String> {
+        let backend_kind = self.toml_account_config.backend.as_ref();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let aliases = id_mapper.aliases()?;
+        let json = serde_json::to_string_pretty(&aliases)
+            .map_err(|err| Error::ExportIdAliasesError(err, folder.to_owned()))?;
+        Ok(json)
+    }
+
+    /// Restore the aliases of the message ids of the given folder,
+    /// see [`Backend::export_id_aliases`].
+    ///
+    /// The folder must not have aliases yet, see
+    /// [`IdMapper::restore_aliases`]. Returns the number of restored
+    /// aliases.
+    pub fn import_id_aliases(&self, folder: &str, json: &str) -> Result<usize> {
+        let backend_kind = self.toml_account_config.backend.as_ref();
+        let id_mapper = self.build_id_mapper(folder, backend_kind)?;
+        let aliases = serde_json::from_str(json)
+            .map_err(|err| Error::ImportIdAliasesError(err, folder.to_owned()))?;
+        id_mapper.restore_aliases(aliases)
+    }
+
+    /// Get the delete style of the account, see
+    /// [`config::DeleteMessageConfig`].
+    pub fn delete_style(&self) -> config::DeleteMessageStyle {
+        self.toml_account_config
+            .message
+            .as_ref()
+            .and_then(|msg| msg.delete.as_ref())
+            .map(config::DeleteMessageConfig::style)
+            .unwrap_or_default()
+    }
+
+    /// Delete messages with the given style, whatever the delete
+    /// style of the account.
+    ///
+    /// Folder aliases are resolved, so that messages of the trash
+    /// folder are flagged as deleted instead of being moved to it.
+    pub async fn delete_messages_with_style(
+        &self,
+        folder: &str,
+        ids: &[usize],
+        style: config::DeleteMessageStyle,
+    ) -> Result<()> {
+        let account_config = &self.backend.account_config;
+
+        match style {
+            config::DeleteMessageStyle::Trash => {
+                let folder_alias = account_config.get_folder_alias(folder);
+                let trash_alias = account_config.get_folder_alias(TRASH);
+
+                if folder_alias == trash_alias {
+                    self.add_flag(folder, ids, Flag::Deleted).await
+                } else {
+                    self.move_messages(folder, TRASH, ids).await
+                }
+            }
+            config::DeleteMessageStyle::Flag => self.add_flag(folder, ids, Flag::Deleted).await,
+            config::DeleteMessageStyle::Expunge => self.remove_messages(folder, ids).await,
+        }
     }
 
     pub async fn remove_messages(&self, folder: &str, ids: &[usize]) -> Result<()> {
         let backend_kind = self.toml_account_config.backend.as_ref();
         let id_mapper = self.build_id_mapper(folder, backend_kind)?;
-        let ids = Id::multiple(id_mapper.get_ids(ids)?);
-        self.backend.remove_messages(folder, &ids).await?;
-        Ok(())
+        let id_mapper = &id_mapper;
+
+        let res = self
+            .batch("Removing messages", ids, |ids| async move {
+                let ids = Id::multiple(id_mapper.get_ids_async(&ids).await?);
+                self.timed(
+                    "remove_messages",
+                    self.backend.remove_messages(folder, &ids),
+                )
+                .await?;
+                Ok(())
+            })
+            .await;
+
+        self.invalidate_envelopes_cache(folder);
+        res
     }
 
     pub async fn send_message_then_save_copy(&self, msg: &[u8]) -> Result<()> {
@@ -688,6 +1928,11 @@ impl Backend {
 pub struct BackendBuilder {
     toml_account_config: Arc<HimalayaTomlAccountConfig>,
     builder: email::backend::BackendBuilder<ContextBuilder>,
+    batch_options: BatchOptions,
+    progress: Option<Arc<dyn ProgressObserver>>,
+    metrics: Option<Arc<OperationMetrics>>,
+    #[cfg(feature = "sled")]
+    envelopes_cache: bool,
 }
 
 impl BackendBuilder {
@@ -706,6 +1951,112 @@ impl BackendBuilder {
         Self {
             toml_account_config,
             builder: f(builder),
+            batch_options: Default::default(),
+            progress: None,
+            metrics: None,
+            #[cfg(feature = "sled")]
+            envelopes_cache: false,
+        }
+    }
+
+    /// Override the id mapper kind of the account configuration.
+    pub fn with_id_mapper(mut self, kind: config::IdMapperKind) -> Self {
+        let mut toml_account_config = (*self.toml_account_config).clone();
+        toml_account_config.id_mapper = Some(kind);
+        self.toml_account_config = Arc::new(toml_account_config);
+        self
+    }
+
+    /// Override the rate limit of the account configuration.
+    pub fn with_rate_limit(mut self, rate_limit: config::RateLimitConfig) -> Self {
+        let mut toml_account_config = (*self.toml_account_config).clone();
+        toml_account_config.rate_limit = Some(rate_limit);
+        self.toml_account_config = Arc::new(toml_account_config);
+        self
+    }
+
+    /// Override the IMAP tuning options of the account configuration.
+    #[cfg(feature = "imap")]
+    pub fn with_imap_tuning(mut self, tuning: config::ImapTuningConfig) -> Self {
+        let mut toml_account_config = (*self.toml_account_config).clone();
+        toml_account_config.imap_tuning = Some(tuning);
+        self.toml_account_config = Arc::new(toml_account_config);
+
+        let ctx_builder = ContextBuilder::new(
+            self.toml_account_config.clone(),
+            self.builder.account_config.clone(),
+        );
+
+        if self.builder.ctx_builder.imap.is_some() {
+            self.builder.ctx_builder.imap = ctx_builder.imap;
+        }
+
+        self
+    }
+
+    /// Cache envelopes listings locally, see [`EnvelopesCache`].
+    ///
+    /// Listings are cached when they succeed, and cached listings
+    /// are returned when they fail, for example when offline.
+    #[cfg(feature = "sled")]
+    pub fn with_envelopes_cache(mut self, enabled: bool) -> Self {
+        self.envelopes_cache = enabled;
+        self
+    }
+
+    /// Report the progress of long operations to the given observer,
+    /// like [`TerminalProgress`].
+    pub fn with_progress(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.progress = Some(Arc::new(observer));
+        self
+    }
+
+    /// Record the duration, the size and the failures of backend
+    /// operations into the given metrics.
+    ///
+    /// The metrics are shared, so that they can be summarized once
+    /// the backend is dropped, for example in verbose mode.
+    pub fn with_metrics(mut self, metrics: Arc<OperationMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn with_batch_options(mut self, batch_options: BatchOptions) -> Self {
+        self.batch_options = batch_options;
+        self
+    }
+
+    /// Build only the contexts needed by the given features.
+    ///
+    /// Contexts are built when the backend is built, which connects
+    /// to the servers. A command listing envelopes does not need to
+    /// connect to the SMTP server, and a command sending a message
+    /// does not need to connect to the IMAP server, unless it also
+    /// saves a copy of it.
+    pub fn with_required_features(
+        self,
+        features: impl IntoIterator<Item = RequiredFeature>,
+    ) -> Self {
+        let (mut backend, mut sending_backend) = (false, false);
+
+        for feature in features {
+            if feature.needs_sending_backend() {
+                sending_backend = true;
+            } else {
+                backend = true;
+            }
+        }
+
+        let builder = if backend {
+            self
+        } else {
+            self.without_backend()
+        };
+
+        if sending_backend {
+            builder
+        } else {
+            builder.without_sending_backend()
         }
     }
 
@@ -740,9 +2091,56 @@ impl BackendBuilder {
     }
 
     pub async fn build(self) -> Result<Backend> {
+        // connecting directly would leak the address the proxy hides
+        if self.toml_account_config.proxy.is_some() {
+            return Err(Error::ProxyNotSupportedError.into());
+        }
+
+        #[allow(unused_mut)]
+        let (mut connection_timeout, mut command_timeout) = (None, None);
+
+        #[cfg(feature = "imap")]
+        if let (Some(config::Backend::Imap(_)), Some(tuning)) = (
+            &self.toml_account_config.backend,
+            &self.toml_account_config.imap_tuning,
+        ) {
+            connection_timeout = tuning.connection_timeout.map(Duration::from_secs);
+            command_timeout = tuning.command_timeout.map(Duration::from_secs);
+        }
+
+        let start = Instant::now();
+
+        let res = match connection_timeout {
+            None => self.builder.build().await.map_err(Into::into),
+            Some(duration) => match timeout(duration, self.builder.build()).await {
+                Ok(res) => res.map_err(Into::into),
+                Err(_) => Err(Error::BackendConnectionTimeoutError(duration).into()),
+            },
+        };
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record("connect", start.elapsed(), res.is_ok());
+        }
+
+        let backend = res?;
+
+        let rate_limiter = self
+            .toml_account_config
+            .rate_limit
+            .as_ref()
+            .map(RateLimiter::new);
+
         Ok(Backend {
             toml_account_config: self.toml_account_config,
-            backend: self.builder.build().await?,
+            backend,
+            batch_options: self.batch_options,
+            progress: self.progress,
+            metrics: self.metrics,
+            rate_limiter,
+            memory_id_mappers: Default::default(),
+            #[cfg(feature = "sled")]
+            envelopes_cache: self.envelopes_cache,
+            command_timeout,
         })
     }
 }
@@ -754,3 +2152,421 @@ impl Deref for Backend {
         &self.backend
     }
 }
+
+/// Format of exported messages, see [`Backend::export_messages`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// All messages in a single mbox file.
+    #[default]
+    Mbox,
+    /// One `.eml` file per message.
+    Eml,
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mbox => write!(f, "mbox"),
+            Self::Eml => write!(f, "eml"),
+        }
+    }
+}
+
+/// Outcome of [`Backend::import_messages`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ImportedMessages {
+    pub imported: usize,
+    /// Messages skipped because their Message-ID already exists.
+    pub duplicates: usize,
+}
+
+/// Sum the sizes of the given messages, for metrics.
+fn messages_size(msgs: &Messages) -> usize {
+    msgs.iter()
+        .filter_map(|msg| msg.raw().ok())
+        .map(|raw| raw.len())
+        .sum()
+}
+
+/// Read the Message-ID header of the given raw message.
+fn parse_message_id(raw: &[u8]) -> Option<String> {
+    let mut lines = raw
+        .split(|b| *b == b'\n')
+        .map(|line| String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)))
+        .take_while(|line| !line.is_empty())
+        .peekable();
+
+    while let Some(line) = lines.next() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        if !key.trim().eq_ignore_ascii_case("message-id") {
+            continue;
+        }
+
+        let mut value = value.trim().to_owned();
+
+        // unfold the header value
+        while let Some(next) = lines.next_if(|line| line.starts_with([' ', '\t'])) {
+            value.push_str(next.trim());
+        }
+
+        return Some(normalize_message_id(&value)).filter(|id| !id.is_empty());
+    }
+
+    None
+}
+
+fn normalize_message_id(message_id: &str) -> String {
+    message_id
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_owned()
+}
+
+/// Build the mbox entry of the given raw message, using the mboxrd
+/// variant: lines starting with `From `, possibly quoted with `>`,
+/// get one more `>` so that they are not read as separators.
+fn to_mbox_entry(raw: &[u8]) -> Vec<u8> {
+    let date = Local::now().format("%a %b %e %H:%M:%S %Y");
+    let mut entry = format!("From MAILER-DAEMON {date}\n").into_bytes();
+
+    let raw = raw.strip_suffix(b"\n").unwrap_or(raw);
+
+    for line in raw.split(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let unquoted = line.iter().position(|b| *b != b'>').unwrap_or(line.len());
+
+        if line[unquoted..].starts_with(b"From ") {
+            entry.push(b'>');
+        }
+
+        entry.extend_from_slice(line);
+        entry.push(b'\n');
+    }
+
+    entry.push(b'\n');
+    entry
+}
+
+/// Replace characters that cannot safely appear in a file name, and
+/// strip leading dots so that names cannot be hidden or point to a
+/// parent directory.
+fn sanitize_filename(filename: &str) -> String {
+    let filename: String = filename
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    filename
+        .trim()
+        .trim_start_matches('.')
+        .trim_start()
+        .to_owned()
+}
+
+/// Write the given contents into a new file of the given directory.
+///
+/// When the file name is taken, a number is appended to its stem
+/// until a free name is found: `file.pdf`, `file_1.pdf`, etc.
+fn write_new_file(
+    dir: &Path,
+    filename: &str,
+    contents: &[u8],
+    map_err: impl Fn(io::Error, PathBuf) -> Error,
+) -> Result<PathBuf> {
+    let filename = Path::new(filename);
+    let stem = filename.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = filename.extension().map(|ext| ext.to_string_lossy());
+
+    for n in 0.. {
+        let path = match (n, &ext) {
+            (0, _) => dir.join(filename),
+            (n, Some(ext)) => dir.join(format!("{stem}_{n}.{ext}")),
+            (n, None) => dir.join(format!("{stem}_{n}")),
+        };
+
+        let file = OpenOptions::new().write(true).create_new(true).open(&path);
+
+        match file {
+            Ok(mut file) => {
+                file.write_all(contents)
+                    .map_err(|err| map_err(err, path.clone()))?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(map_err(err, path).into()),
+        }
+    }
+
+    unreachable!("a free file name should always be found")
+}
+
+/// Builder of [`ListEnvelopesOptions`], for CLI layers exposing
+/// search filters as separate arguments.
+///
+/// Filters are combined with a logical and. Dates are parsed from
+/// `YYYY-MM-DD`, `today` or `yesterday`.
+#[derive(Clone, Debug, Default)]
+pub struct ListEnvelopesOptionsBuilder {
+    page: usize,
+    page_size: usize,
+    filters: Vec<SearchEmailsFilterQuery>,
+    sorters: Vec<SearchEmailsSorter>,
+    after: Option<NaiveDate>,
+    before: Option<NaiveDate>,
+}
+
+impl ListEnvelopesOptionsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_page(mut self, page: usize) -> Self {
+        self.page = page;
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn with_from(self, pattern: impl ToString) -> Self {
+        self.with_filter(SearchEmailsFilterQuery::From(pattern.to_string()))
+    }
+
+    pub fn with_to(self, pattern: impl ToString) -> Self {
+        self.with_filter(SearchEmailsFilterQuery::To(pattern.to_string()))
+    }
+
+    pub fn with_subject(self, pattern: impl ToString) -> Self {
+        self.with_filter(SearchEmailsFilterQuery::Subject(pattern.to_string()))
+    }
+
+    pub fn with_body(self, pattern: impl ToString) -> Self {
+        self.with_filter(SearchEmailsFilterQuery::Body(pattern.to_string()))
+    }
+
+    pub fn with_flag(self, flag: Flag) -> Self {
+        self.with_filter(SearchEmailsFilterQuery::Flag(flag))
+    }
+
+    pub fn without_flag(self, flag: Flag) -> Self {
+        self.with_filter(SearchEmailsFilterQuery::Not(Box::new(
+            SearchEmailsFilterQuery::Flag(flag),
+        )))
+    }
+
+    /// Keep envelopes of the given day only.
+    pub fn with_date(self, date: impl AsRef<str>) -> Result<Self> {
+        let date = parse_date(date.as_ref())?;
+        Ok(self.with_filter(SearchEmailsFilterQuery::Date(date)))
+    }
+
+    /// Keep envelopes dated after the given day.
+    pub fn with_after(mut self, date: impl AsRef<str>) -> Result<Self> {
+        self.after = Some(parse_date(date.as_ref())?);
+        Ok(self)
+    }
+
+    /// Keep envelopes dated before the given day.
+    pub fn with_before(mut self, date: impl AsRef<str>) -> Result<Self> {
+        self.before = Some(parse_date(date.as_ref())?);
+        Ok(self)
+    }
+
+    pub fn with_filter(mut self, filter: SearchEmailsFilterQuery) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn with_sorter(mut self, sorter: SearchEmailsSorter) -> Self {
+        self.sorters.push(sorter);
+        self
+    }
+
+    /// Build the options, failing if the date range is empty.
+    pub fn build(self) -> Result<ListEnvelopesOptions> {
+        let mut filters = self.filters;
+
+        if let (Some(after), Some(before)) = (self.after, self.before) {
+            if after >= before {
+                return Err(Error::EmptyEnvelopesDateRangeError(after, before).into());
+            }
+        }
+
+        if let Some(after) = self.after {
+            filters.push(SearchEmailsFilterQuery::AfterDate(after));
+        }
+
+        if let Some(before) = self.before {
+            filters.push(SearchEmailsFilterQuery::BeforeDate(before));
+        }
+
+        let filter = filters
+            .into_iter()
+            .reduce(|left, right| SearchEmailsFilterQuery::And(Box::new(left), Box::new(right)));
+
+        let sort = if self.sorters.is_empty() {
+            None
+        } else {
+            Some(self.sorters)
+        };
+
+        let query = if filter.is_none() && sort.is_none() {
+            None
+        } else {
+            Some(SearchEmailsQuery { filter, sort })
+        };
+
+        Ok(ListEnvelopesOptions {
+            page: self.page,
+            page_size: self.page_size,
+            query,
+        })
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
+
+    match date.trim() {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - chrono::Duration::days(1)),
+        date => Ok(NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| Error::ParseEnvelopesDateError(err, date.to_owned()))?),
+    }
+}
+
+/// Synchronization of the backend of an account with a replica
+/// backend, like a local Maildir.
+///
+/// Folders, messages and flags are mirrored both ways by the sync
+/// engine of the email crate.
+#[cfg(feature = "sync")]
+pub struct BackendSyncBuilder {
+    account_config: Arc<AccountConfig>,
+    left: ContextBuilder,
+    right: ContextBuilder,
+    dry_run: bool,
+    progress: Option<Arc<dyn ProgressObserver>>,
+}
+
+#[cfg(feature = "sync")]
+impl BackendSyncBuilder {
+    pub fn new(
+        toml_account_config: Arc<HimalayaTomlAccountConfig>,
+        account_config: Arc<AccountConfig>,
+        replica: config::Backend,
+    ) -> Self {
+        let mut replica_config = (*toml_account_config).clone();
+        replica_config.backend = Some(replica);
+
+        let left = ContextBuilder::new(toml_account_config, account_config.clone())
+            .without_sending_backends();
+        let right = ContextBuilder::new(Arc::new(replica_config), account_config.clone())
+            .without_sending_backends();
+
+        Self {
+            account_config,
+            left,
+            right,
+            dry_run: false,
+            progress: None,
+        }
+    }
+
+    /// Compute the changes without applying them.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Observe the progress of the synchronization: the amount of
+    /// work is unknown until message changes are computed, then each
+    /// applied change counts as one item.
+    pub fn with_progress(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.progress = Some(Arc::new(observer));
+        self
+    }
+
+    /// Synchronize both backends, and return the report of the
+    /// applied changes, or of the changes to apply in dry run mode.
+    pub async fn sync(self) -> Result<SyncReport> {
+        let left = email::backend::BackendBuilder::new(self.account_config.clone(), self.left);
+        let right = email::backend::BackendBuilder::new(self.account_config, self.right);
+
+        let progress = ProgressGuard(self.progress.as_deref());
+        if let Some(observer) = progress.0 {
+            observer.start("Synchronizing", ProgressTotal::Unknown);
+        }
+
+        // the amount of work is only known once the message changes
+        // are computed, then every processed change counts as one
+        let observer = self.progress.clone();
+
+        let report = SyncBuilder::new(left, right)
+            .with_dry_run(self.dry_run)
+            .with_handler(move |evt| {
+                if let Some(observer) = observer.as_deref() {
+                    match &evt {
+                        SyncEvent::GeneratedEmailPatch(patches) => {
+                            let total: usize = patches.values().map(|hunks| hunks.len()).sum();
+                            let total = ProgressTotal::Items(total as u64);
+                            observer.start("Synchronizing messages", total);
+                        }
+                        SyncEvent::ProcessedEmailHunk(_) => observer.inc(1),
+                        _ => (),
+                    }
+                }
+
+                async move {
+                    tracing::debug!("{evt}");
+                    Ok(())
+                }
+            })
+            .sync()
+            .await?;
+
+        Ok(report)
+    }
+}
+
+/// Run the given backend operation until it completes, or until the
+/// given cancellation future resolves, like the `cancelled()` future
+/// of a cancellation token.
+///
+/// Cancelling drops the operation: its progress is finished, and
+/// what it already did on the backend side is kept. Since a request
+/// may be interrupted half-way, the backend should be rebuilt before
+/// being used again.
+pub async fn cancellable<T>(
+    op: impl Future<Output = Result<T>>,
+    cancel: impl Future<Output = ()>,
+) -> Result<T> {
+    tokio::select! {
+        res = op => res,
+        () = cancel => Err(Error::BackendOperationCancelled.into()),
+    }
+}
+
+/// Run the given backend operation until it completes, or until the
+/// user presses Ctrl-C, see [`cancellable`].
+pub async fn interruptible<T>(op: impl Future<Output = Result<T>>) -> Result<T> {
+    let interrupted = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            // never cancel when signals cannot be listened to
+            print::warn(Error::ListenInterruptionSignalError(err).to_string());
+            std::future::pending::<()>().await;
+        }
+    };
+
+    cancellable(op, interrupted).await
+}
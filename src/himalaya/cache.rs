@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use color_eyre::{eyre::eyre, eyre::Context, Result};
+use dirs::data_dir;
+use email::{
+    account::config::AccountConfig,
+    envelope::{
+        list::ListEnvelopesOptions, Address, Envelope, Envelopes, ThreadedEnvelope,
+        ThreadedEnvelopes,
+    },
+    flag::{Flag, Flags},
+};
+use petgraph::graphmap::DiGraphMap;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::id_mapper::FoldersIndex;
+
+/// Local cache of envelopes listings of an account folder.
+///
+/// Listings are stored as they come from the backend, one entry per
+/// listing options, and are replaced by every successful listing.
+/// The whole cache of a folder is cleared whenever messages of the
+/// folder are changed through the backend: flags, additions, copies,
+/// moves and deletions.
+///
+/// The IMAP UIDVALIDITY is not exposed by the email crate, so cached
+/// listings are not validated against it, and changes made by other
+/// clients are only seen at the next successful listing. Cached
+/// listings are therefore only meant to be displayed while the
+/// backend refreshes them, or while it is offline. Refreshing in the
+/// background is left to callers: display the cached listing, then
+/// list envelopes again.
+#[derive(Debug)]
+pub struct EnvelopesCache(sled::Db);
+
+impl EnvelopesCache {
+    /// Open the cache of the given account folder, and record the
+    /// folder in the index of the account, see [`FoldersIndex`].
+    pub fn new(account_config: &AccountConfig, folder: &str) -> Result<Self> {
+        let db_path = Self::db_path(&account_config.name, folder)?;
+
+        let conn = sled::Config::new()
+            .path(&db_path)
+            .open()
+            .with_context(|| format!("cannot open envelopes cache at {db_path:?}"))?;
+
+        let index = FoldersIndex::new(&Self::db_dir()?, &account_config.name);
+        if let Err(err) = index.insert(folder) {
+            warn!("cannot index envelopes cache of folder {folder}: {err:#}");
+        }
+
+        Ok(Self(conn))
+    }
+
+    /// Get the directory of the caches.
+    pub fn db_dir() -> Result<std::path::PathBuf> {
+        let db_dir = data_dir()
+            .ok_or(eyre!("cannot get XDG data directory"))?
+            .join("himalaya")
+            .join(".envelopes-caches");
+
+        Ok(db_dir)
+    }
+
+    /// Get the path of the cache of the given account folder.
+    pub fn db_path(account_name: &str, folder: &str) -> Result<std::path::PathBuf> {
+        let digest = md5::compute(account_name.to_owned() + folder);
+        Ok(Self::db_dir()?.join(format!("{digest:x}")))
+    }
+
+    /// Remove the caches of the given account folders and of the
+    /// folders of the account index, see [`FoldersIndex`].
+    ///
+    /// Returns the number of removed caches.
+    pub fn remove<F: AsRef<str>>(
+        account_name: &str,
+        folders: impl IntoIterator<Item = F>,
+    ) -> Result<usize> {
+        let index = FoldersIndex::new(&Self::db_dir()?, account_name);
+        let mut removed = 0;
+
+        for folder in index.folders_with(folders)? {
+            let db_path = Self::db_path(account_name, &folder)?;
+
+            if db_path.exists() {
+                debug!("removing envelopes cache at {db_path:?}…");
+                std::fs::remove_dir_all(&db_path)
+                    .with_context(|| format!("cannot remove envelopes cache at {db_path:?}"))?;
+                removed += 1;
+            }
+        }
+
+        index.remove()?;
+        Ok(removed)
+    }
+
+    /// Remove all the cached listings.
+    pub fn clear(&self) -> Result<()> {
+        self.0.clear().context("cannot clear envelopes cache")
+    }
+
+    pub fn list(&self, opts: &ListEnvelopesOptions) -> Result<Option<Envelopes>> {
+        let Some(envelopes) = self.get::<Vec<CachedEnvelope>>(&key("list", opts))? else {
+            return Ok(None);
+        };
+
+        let envelopes = envelopes
+            .into_iter()
+            .map(Envelope::try_from)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(Envelopes::from_iter(envelopes)))
+    }
+
+    pub fn set_list(&self, opts: &ListEnvelopesOptions, envelopes: &Envelopes) -> Result<()> {
+        let envelopes: Vec<CachedEnvelope> = envelopes.iter().map(Into::into).collect();
+        self.set(&key("list", opts), &envelopes)
+    }
+
+    pub fn thread(&self, opts: &ListEnvelopesOptions) -> Result<Option<ThreadedEnvelopes>> {
+        let Some(threads) = self.get::<CachedThreads>(&key("thread", opts))? else {
+            return Ok(None);
+        };
+
+        let envelopes = threads
+            .envelopes
+            .into_iter()
+            .map(|envelope| {
+                let envelope = Envelope::try_from(envelope)?;
+                Ok((envelope.id.clone(), envelope))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let edges = threads.edges;
+
+        let envelopes = ThreadedEnvelopes::build(envelopes, move |envelopes| {
+            let mut graph = DiGraphMap::<ThreadedEnvelope, u8>::new();
+
+            for (a, b, w) in edges.clone() {
+                let Some(eb) = envelopes.get(&b) else {
+                    continue;
+                };
+
+                match envelopes.get(&a) {
+                    Some(ea) => {
+                        graph.add_edge(ea.as_threaded(), eb.as_threaded(), w);
+                    }
+                    None => {
+                        let ea = ThreadedEnvelope {
+                            id: "0",
+                            message_id: "0",
+                            subject: "",
+                            from: "",
+                            date: Default::default(),
+                        };
+                        graph.add_edge(ea, eb.as_threaded(), w);
+                    }
+                }
+            }
+
+            graph
+        });
+
+        Ok(Some(envelopes))
+    }
+
+    pub fn set_thread(
+        &self,
+        opts: &ListEnvelopesOptions,
+        envelopes: &ThreadedEnvelopes,
+    ) -> Result<()> {
+        let threads = CachedThreads {
+            envelopes: envelopes.map().values().map(Into::into).collect(),
+            edges: envelopes
+                .graph()
+                .all_edges()
+                .map(|(a, b, w)| (a.id.to_owned(), b.id.to_owned(), *w))
+                .collect(),
+        };
+
+        self.set(&key("thread", opts), &threads)
+    }
+
+    fn get<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<Option<T>> {
+        let Some(value) = self
+            .0
+            .get(key)
+            .with_context(|| format!("cannot get cached envelopes {key}"))?
+        else {
+            return Ok(None);
+        };
+
+        // an invalid entry is a cache miss, not an error
+        match serde_json::from_slice(&value) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => {
+                debug!("ignoring invalid cached envelopes {key}: {err}");
+                Ok(None)
+            }
+        }
+    }
+
+    fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let value = serde_json::to_vec(value)
+            .with_context(|| format!("cannot serialize cached envelopes {key}"))?;
+
+        self.0
+            .insert(key, value)
+            .with_context(|| format!("cannot cache envelopes {key}"))?;
+
+        Ok(())
+    }
+}
+
+/// Build the cache key of the given listing options.
+fn key(kind: &str, opts: &ListEnvelopesOptions) -> String {
+    format!("{kind}:{}:{}:{:?}", opts.page, opts.page_size, opts.query)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedThreads {
+    envelopes: Vec<CachedEnvelope>,
+    edges: Vec<(String, String, u8)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedEnvelope {
+    id: String,
+    message_id: String,
+    in_reply_to: Option<String>,
+    flags: Vec<String>,
+    subject: String,
+    from: CachedAddress,
+    to: CachedAddress,
+    date: String,
+    has_attachment: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedAddress {
+    name: Option<String>,
+    addr: String,
+}
+
+impl From<&Envelope> for CachedEnvelope {
+    fn from(envelope: &Envelope) -> Self {
+        let flags = envelope
+            .flags
+            .iter()
+            .map(|flag| match flag {
+                Flag::Seen => String::from("\\Seen"),
+                Flag::Answered => String::from("\\Answered"),
+                Flag::Flagged => String::from("\\Flagged"),
+                Flag::Deleted => String::from("\\Deleted"),
+                Flag::Draft => String::from("\\Draft"),
+                Flag::Custom(flag) => flag.clone(),
+            })
+            .collect();
+
+        Self {
+            id: envelope.id.clone(),
+            message_id: envelope.message_id.clone(),
+            in_reply_to: envelope.in_reply_to.clone(),
+            flags,
+            subject: envelope.subject.clone(),
+            from: CachedAddress {
+                name: envelope.from.name.clone(),
+                addr: envelope.from.addr.clone(),
+            },
+            to: CachedAddress {
+                name: envelope.to.name.clone(),
+                addr: envelope.to.addr.clone(),
+            },
+            date: envelope.date.to_rfc3339(),
+            has_attachment: envelope.has_attachment,
+        }
+    }
+}
+
+impl TryFrom<CachedEnvelope> for Envelope {
+    type Error = color_eyre::Report;
+
+    fn try_from(envelope: CachedEnvelope) -> Result<Self> {
+        let flags = envelope.flags.into_iter().map(|flag| match flag.as_str() {
+            "\\Seen" => Flag::Seen,
+            "\\Answered" => Flag::Answered,
+            "\\Flagged" => Flag::Flagged,
+            "\\Deleted" => Flag::Deleted,
+            "\\Draft" => Flag::Draft,
+            _ => Flag::Custom(flag),
+        });
+
+        let date = DateTime::parse_from_rfc3339(&envelope.date)
+            .with_context(|| format!("cannot parse cached envelope date {}", envelope.date))?;
+
+        Ok(Envelope {
+            id: envelope.id,
+            message_id: envelope.message_id,
+            in_reply_to: envelope.in_reply_to,
+            flags: Flags::from_iter(flags),
+            subject: envelope.subject,
+            from: Address {
+                name: envelope.from.name,
+                addr: envelope.from.addr,
+            },
+            to: Address {
+                name: envelope.to.name,
+                addr: envelope.to.addr,
+            },
+            date,
+            has_attachment: envelope.has_attachment,
+        })
+    }
+}
@@ -36,6 +36,7 @@ pub fn pre_edit() -> Result<PreEditChoice> {
         "A draft was found, what would you like to do with it?",
         &PRE_EDIT_CHOICES,
         None,
+        None,
     )?;
 
     Ok(user_choice.clone())
@@ -79,6 +80,7 @@ pub fn post_edit() -> Result<PostEditChoice> {
         "What would you like to do with this message?",
         &POST_EDIT_CHOICES,
         None,
+        None,
     )?;
 
     Ok(user_choice.clone())
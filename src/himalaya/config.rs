@@ -7,13 +7,12 @@ use std::{
 };
 
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Local};
 use color_eyre::Result;
-use comfy_table::{presets, Attribute, Cell, ContentArrangement, Row, Table};
-use crossterm::{
-    cursor,
-    style::{Color, Stylize},
-    terminal,
+use comfy_table::{
+    presets, Attribute, Cell, ColumnConstraint, ContentArrangement, Row, Table, Width,
 };
+use crossterm::style::{Color, Stylize};
 #[cfg(feature = "pgp")]
 use email::account::config::pgp::PgpConfig;
 #[cfg(feature = "imap")]
@@ -30,17 +29,17 @@ use email::{
     account::config::AccountConfig,
     config::Config,
     envelope::ThreadedEnvelope,
-    message::{
-        add::config::MessageWriteConfig, delete::config::DeleteMessageConfig,
-        get::config::MessageReadConfig,
-    },
+    message::{add::config::MessageWriteConfig, get::config::MessageReadConfig},
     template::config::TemplateConfig,
 };
 use petgraph::graphmap::DiGraphMap;
 use process::Command;
 use serde::{Deserialize, Serialize, Serializer};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use super::id_mapper::IdMapper;
+use crate::terminal::{color, config::fill_missing, wrap};
+
+use super::id_mapper::{AsyncIdMapper, IdMapper};
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
@@ -52,10 +51,29 @@ pub struct HimalayaTomlConfig {
     pub downloads_dir: Option<PathBuf>,
     pub accounts: HashMap<String, HimalayaTomlAccountConfig>,
     pub account: Option<AccountsConfig>,
+    pub theme: Option<ThemeConfig>,
+    pub defaults: Option<HimalayaTomlDefaultsConfig>,
+}
+
+/// Represents the defaults shared by all accounts.
+///
+/// Each account inherits these options, unless it defines them
+/// itself. Folder and envelope options are inherited one by one.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HimalayaTomlDefaultsConfig {
+    pub display_name: Option<String>,
+    pub signature: Option<String>,
+    pub signature_delim: Option<String>,
+    pub downloads_dir: Option<PathBuf>,
+    pub folder: Option<FolderConfig>,
+    pub envelope: Option<EnvelopeConfig>,
 }
 
 impl From<HimalayaTomlConfig> for Config {
     fn from(config: HimalayaTomlConfig) -> Self {
+        let defaults = config.defaults.unwrap_or_default();
+
         Self {
             display_name: config.display_name,
             signature: config.signature,
@@ -65,7 +83,7 @@ impl From<HimalayaTomlConfig> for Config {
                 .accounts
                 .into_iter()
                 .map(|(name, config)| {
-                    let mut config = AccountConfig::from(config);
+                    let mut config = AccountConfig::from(config.inherit(&defaults));
                     config.name = name.clone();
                     (name, config)
                 })
@@ -75,6 +93,77 @@ impl From<HimalayaTomlConfig> for Config {
 }
 
 impl HimalayaTomlConfig {
+    /// Find the default account.
+    ///
+    /// When several accounts are marked as default, the one with the
+    /// highest priority wins. Accounts without priority have a
+    /// priority of 0.
+    ///
+    /// Returns an error if several default accounts share the
+    /// highest priority.
+    pub fn find_default_account(
+        &self,
+    ) -> crate::Result<Option<(&str, &HimalayaTomlAccountConfig)>> {
+        let mut defaults: Vec<_> = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.default.unwrap_or_default())
+            .map(|(name, account)| (account.priority.unwrap_or_default(), name.as_str(), account))
+            .collect();
+
+        defaults.sort_by(|(a, a_name, _), (b, b_name, _)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        match defaults.as_slice() {
+            [] => Ok(None),
+            [(_, name, account)] => Ok(Some((*name, *account))),
+            [(first, name, account), (second, ..), ..] if first > second => {
+                Ok(Some((*name, *account)))
+            }
+            [(first, ..), ..] => {
+                let names = defaults
+                    .iter()
+                    .filter(|(priority, ..)| priority == first)
+                    .map(|(_, name, _)| name.to_string())
+                    .collect();
+                Err(crate::Error::MultipleDefaultAccountsError(names))
+            }
+        }
+    }
+
+    /// Find the account matching the given name, or the given alias
+    /// when no account has this name.
+    ///
+    /// Returns an error if several accounts share the given alias.
+    pub fn find_account(
+        &self,
+        name: &str,
+    ) -> crate::Result<Option<(&str, &HimalayaTomlAccountConfig)>> {
+        if let Some((name, account)) = self.accounts.get_key_value(name) {
+            return Ok(Some((name, account)));
+        }
+
+        let mut matches: Vec<_> = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.aliases.iter().flatten().any(|alias| alias == name))
+            .collect();
+
+        match matches.len() {
+            0 => Ok(None),
+            1 => Ok(matches
+                .pop()
+                .map(|(name, account)| (name.as_str(), account))),
+            _ => {
+                let mut names: Vec<_> = matches.into_iter().map(|(name, _)| name.clone()).collect();
+                names.sort();
+                Err(crate::Error::AmbiguousAccountAliasError(
+                    name.to_owned(),
+                    names,
+                ))
+            }
+        }
+    }
+
     pub fn account_list_table_preset(&self) -> Option<String> {
         self.account
             .as_ref()
@@ -83,6 +172,14 @@ impl HimalayaTomlConfig {
             .and_then(|table| table.preset.clone())
     }
 
+    pub fn account_list_table_columns(&self) -> Option<Vec<AccountsTableColumn>> {
+        self.account
+            .as_ref()
+            .and_then(|account| account.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.columns.clone())
+    }
+
     pub fn account_list_table_name_color(&self) -> Option<Color> {
         self.account
             .as_ref()
@@ -106,6 +203,30 @@ impl HimalayaTomlConfig {
             .and_then(|list| list.table.as_ref())
             .and_then(|table| table.default_color)
     }
+
+    pub fn account_list_table_name_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.account
+            .as_ref()
+            .and_then(|account| account.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.name_attrs.clone())
+    }
+
+    pub fn account_list_table_backends_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.account
+            .as_ref()
+            .and_then(|account| account.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.backends_attrs.clone())
+    }
+
+    pub fn account_list_table_default_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.account
+            .as_ref()
+            .and_then(|account| account.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.default_attrs.clone())
+    }
 }
 
 #[async_trait]
@@ -116,19 +237,28 @@ impl crate::terminal::config::TomlConfig for HimalayaTomlConfig {
         "himalaya"
     }
 
+    /// Get the default account config, see
+    /// [`HimalayaTomlConfig::find_default_account`].
+    ///
+    /// Returns [`None`] if the default account is ambiguous.
     fn get_default_account_config(&self) -> Option<(String, Self::TomlAccountConfig)> {
-        self.accounts.iter().find_map(|(name, account)| {
-            account
-                .default
-                .filter(|default| *default)
-                .map(|_| (name.to_owned(), account.clone()))
-        })
+        let defaults = self.defaults.clone().unwrap_or_default();
+
+        self.find_default_account()
+            .ok()
+            .flatten()
+            .map(|(name, account)| (name.to_owned(), account.inherit(&defaults)))
     }
 
+    /// Get the config of the account matching the given name or
+    /// alias, see [`HimalayaTomlConfig::find_account`].
     fn get_account_config(&self, name: &str) -> Option<(String, Self::TomlAccountConfig)> {
-        self.accounts
-            .get(name)
-            .map(|account| (name.to_owned(), account.clone()))
+        let defaults = self.defaults.clone().unwrap_or_default();
+
+        self.find_account(name)
+            .ok()
+            .flatten()
+            .map(|(name, account)| (name.to_owned(), account.inherit(&defaults)))
     }
 
     #[cfg(feature = "wizard")]
@@ -136,18 +266,43 @@ impl crate::terminal::config::TomlConfig for HimalayaTomlConfig {
         Ok(super::wizard::edit(path, Self::default(), None, Default::default()).await?)
     }
 
+    /// Serialize the configuration with the defaults applied to each
+    /// account, see [`HimalayaTomlAccountConfig::inherit`]. The
+    /// defaults themselves are left out.
+    fn to_effective_value(&self) -> crate::Result<toml::Value> {
+        let defaults = self.defaults.clone().unwrap_or_default();
+
+        let config = Self {
+            accounts: self
+                .accounts
+                .iter()
+                .map(|(name, account)| (name.clone(), account.inherit(&defaults)))
+                .collect(),
+            defaults: None,
+            ..self.clone()
+        };
+
+        toml::Value::try_from(config).map_err(crate::Error::SerializeTomlConfigError)
+    }
+
     fn to_toml_account_config(
         &self,
         account_name: Option<&str>,
     ) -> crate::Result<(String, Self::TomlAccountConfig)> {
         #[allow(unused_mut)]
         let (name, mut config) = match account_name {
-            Some("default") | Some("") | None => self
-                .get_default_account_config()
-                .ok_or(crate::Error::GetDefaultAccountConfigError),
-            Some(name) => self
-                .get_account_config(name)
-                .ok_or_else(|| crate::Error::GetAccountConfigError(name.to_owned())),
+            Some("default") | Some("") | None => {
+                // surface ambiguous defaults instead of a missing one
+                self.find_default_account()?;
+                self.get_default_account_config()
+                    .ok_or(crate::Error::GetDefaultAccountConfigError)
+            }
+            Some(name) => {
+                // surface ambiguous aliases instead of a missing account
+                self.find_account(name)?;
+                self.get_account_config(name)
+                    .ok_or_else(|| crate::Error::GetAccountConfigError(name.to_owned()))
+            }
         }?;
 
         #[cfg(all(feature = "imap", feature = "keyring"))]
@@ -168,12 +323,30 @@ impl crate::terminal::config::TomlConfig for HimalayaTomlConfig {
 #[serde(rename_all = "kebab-case")]
 pub struct HimalayaTomlAccountConfig {
     pub default: Option<bool>,
+    /// Priority used to choose between several default accounts,
+    /// the highest wins.
+    pub priority: Option<i64>,
+    /// Alternative names the account can be selected with.
+    pub aliases: Option<Vec<String>>,
     pub email: String,
     pub display_name: Option<String>,
     pub signature: Option<String>,
     pub signature_delim: Option<String>,
     pub downloads_dir: Option<PathBuf>,
     pub backend: Option<Backend>,
+    #[cfg(feature = "imap")]
+    pub imap_tuning: Option<ImapTuningConfig>,
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Proxies are not supported by backends yet, so a configured
+    /// proxy makes the backend fail to build instead of connecting
+    /// directly, see [`BackendBuilder::build`].
+    ///
+    /// [`BackendBuilder::build`]: super::backend::BackendBuilder::build
+    #[serde(default, skip_serializing, deserialize_with = "unsupported_proxy")]
+    pub proxy: Option<()>,
+    pub id_mapper: Option<IdMapperKind>,
+    /// Directory of the id mapper databases, see [`IdMapper::db_path`].
+    pub id_map_dir: Option<PathBuf>,
 
     #[cfg(feature = "pgp")]
     pub pgp: Option<PgpConfig>,
@@ -188,6 +361,29 @@ pub struct HimalayaTomlAccountConfig {
     pub template: Option<TemplateConfig>,
 }
 
+/// Fill options missing from the given config with the given
+/// defaults, recursively.
+///
+/// Both configs are merged as TOML values, so that nested options
+/// are inherited one by one. If they cannot be merged, the config is
+/// returned as it is.
+fn inherit<T>(config: &Option<T>, defaults: &Option<T>) -> Option<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de>,
+{
+    let (Some(config), Some(defaults)) = (config, defaults) else {
+        return config.clone().or_else(|| defaults.clone());
+    };
+
+    let merged = toml::Value::try_from(config).and_then(|mut value| {
+        fill_missing(&mut value, toml::Value::try_from(defaults)?);
+        Ok(value)
+    });
+
+    let merged = merged.ok().and_then(|value| value.try_into().ok());
+    Some(merged.unwrap_or_else(|| config.clone()))
+}
+
 #[cfg(not(feature = "pgp"))]
 fn missing_pgp_feature<'de, D: serde::Deserializer<'de>>(_: D) -> Result<Option<()>, D::Error> {
     Err(serde::de::Error::custom(
@@ -195,6 +391,13 @@ fn missing_pgp_feature<'de, D: serde::Deserializer<'de>>(_: D) -> Result<Option<
     ))
 }
 
+fn unsupported_proxy<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<()>, D::Error> {
+    serde::de::IgnoredAny::deserialize(deserializer)?;
+    Ok(Some(()))
+}
+
 impl From<HimalayaTomlAccountConfig> for AccountConfig {
     fn from(config: HimalayaTomlAccountConfig) -> Self {
         Self {
@@ -218,6 +421,32 @@ impl From<HimalayaTomlAccountConfig> for AccountConfig {
 }
 
 impl HimalayaTomlAccountConfig {
+    /// Fill options missing from this account config with the given
+    /// defaults.
+    pub fn inherit(&self, defaults: &HimalayaTomlDefaultsConfig) -> Self {
+        Self {
+            display_name: self
+                .display_name
+                .clone()
+                .or_else(|| defaults.display_name.clone()),
+            signature: self
+                .signature
+                .clone()
+                .or_else(|| defaults.signature.clone()),
+            signature_delim: self
+                .signature_delim
+                .clone()
+                .or_else(|| defaults.signature_delim.clone()),
+            downloads_dir: self
+                .downloads_dir
+                .clone()
+                .or_else(|| defaults.downloads_dir.clone()),
+            folder: inherit(&self.folder, &defaults.folder),
+            envelope: inherit(&self.envelope, &defaults.envelope),
+            ..self.clone()
+        }
+    }
+
     pub fn folder_list_table_preset(&self) -> Option<String> {
         self.folder
             .as_ref()
@@ -226,6 +455,22 @@ impl HimalayaTomlAccountConfig {
             .and_then(|table| table.preset.clone())
     }
 
+    pub fn folder_list_table_columns(&self) -> Option<Vec<FoldersTableColumn>> {
+        self.folder
+            .as_ref()
+            .and_then(|folder| folder.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.columns.clone())
+    }
+
+    pub fn folder_list_table_show_summary(&self) -> Option<bool> {
+        self.folder
+            .as_ref()
+            .and_then(|folder| folder.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.show_summary)
+    }
+
     pub fn folder_list_table_name_color(&self) -> Option<Color> {
         self.folder
             .as_ref()
@@ -242,6 +487,43 @@ impl HimalayaTomlAccountConfig {
             .and_then(|table| table.desc_color)
     }
 
+    pub fn folder_list_table_name_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.folder
+            .as_ref()
+            .and_then(|folder| folder.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.name_attrs.clone())
+    }
+
+    pub fn folder_list_table_desc_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.folder
+            .as_ref()
+            .and_then(|folder| folder.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.desc_attrs.clone())
+    }
+
+    pub fn envelope_thread_max_depth(&self) -> Option<usize> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.thread_max_depth())
+    }
+
+    pub fn envelope_thread_order(&self) -> Option<ThreadsOrder> {
+        self.envelope.as_ref().and_then(|env| env.thread_order())
+    }
+
+    pub fn envelope_thread_tree(&self) -> Option<ThreadEnvelopesTreeConfig> {
+        self.envelope.as_ref().and_then(|env| env.thread_tree())
+    }
+
+    pub fn envelope_list_group_by(&self) -> Option<EnvelopesGroupBy> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.group_by)
+    }
+
     pub fn envelope_list_table_preset(&self) -> Option<String> {
         self.envelope
             .as_ref()
@@ -250,6 +532,14 @@ impl HimalayaTomlAccountConfig {
             .and_then(|table| table.preset.clone())
     }
 
+    pub fn envelope_list_table_flag_glyphs(&self) -> Option<Vec<FlagGlyph>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.flag_glyphs.clone())
+    }
+
     pub fn envelope_list_table_unseen_char(&self) -> Option<char> {
         self.envelope
             .as_ref()
@@ -282,6 +572,54 @@ impl HimalayaTomlAccountConfig {
             .and_then(|table| table.attachment_char)
     }
 
+    pub fn envelope_list_table_columns(&self) -> Option<Vec<EnvelopesTableColumn>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.columns.clone())
+    }
+
+    pub fn envelope_list_table_subject_max_width(&self) -> Option<u16> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.subject_max_width)
+    }
+
+    pub fn envelope_list_table_sender_max_width(&self) -> Option<u16> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.sender_max_width)
+    }
+
+    pub fn envelope_list_table_ellipsis(&self) -> Option<String> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.ellipsis.clone())
+    }
+
+    pub fn envelope_list_table_multiline(&self) -> Option<bool> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.multiline)
+    }
+
+    pub fn envelope_list_table_show_summary(&self) -> Option<bool> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.show_summary)
+    }
+
     pub fn envelope_list_table_id_color(&self) -> Option<Color> {
         self.envelope
             .as_ref()
@@ -322,6 +660,54 @@ impl HimalayaTomlAccountConfig {
             .and_then(|table| table.date_color)
     }
 
+    pub fn envelope_list_table_id_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.id_attrs.clone())
+    }
+
+    pub fn envelope_list_table_flags_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.flags_attrs.clone())
+    }
+
+    pub fn envelope_list_table_subject_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.subject_attrs.clone())
+    }
+
+    pub fn envelope_list_table_sender_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.sender_attrs.clone())
+    }
+
+    pub fn envelope_list_table_date_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.date_attrs.clone())
+    }
+
+    pub fn envelope_list_table_row_styles(&self) -> Option<Vec<RowStyle>> {
+        self.envelope
+            .as_ref()
+            .and_then(|env| env.list.as_ref())
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.row_styles.clone())
+    }
+
     pub fn message_send_backend(&self) -> Option<&SendingBackend> {
         self.message
             .as_ref()
@@ -364,6 +750,178 @@ impl HimalayaTomlAccountConfig {
     }
 }
 
+/// Represents the theme shared by all tables.
+///
+/// Table configs inherit from the theme, each table option taking
+/// precedence over its theme counterpart.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ThemeConfig {
+    pub preset: Option<String>,
+    #[serde(default, with = "color::serde_option")]
+    pub header_color: Option<Color>,
+    /// Color of identifying columns: envelope ids, folder and
+    /// account names.
+    #[serde(default, with = "color::serde_option")]
+    pub primary_color: Option<Color>,
+    /// Color of main content columns: envelope subjects, folder
+    /// descriptions and account backends.
+    #[serde(default, with = "color::serde_option")]
+    pub secondary_color: Option<Color>,
+    /// Color of envelope senders.
+    #[serde(default, with = "color::serde_option")]
+    pub tertiary_color: Option<Color>,
+    /// Color of secondary information: envelope flags and dates,
+    /// account default state.
+    #[serde(default, with = "color::serde_option")]
+    pub muted_color: Option<Color>,
+    /// Color of table borders.
+    #[serde(default, with = "color::serde_option")]
+    pub border_color: Option<Color>,
+}
+
+impl ThemeConfig {
+    fn header(&self, header: &str) -> Cell {
+        match self.header_color {
+            Some(color) => Cell::new(header).fg(color::to_comfy_table_color(color)),
+            None => Cell::new(header),
+        }
+    }
+
+    /// Render the given table, coloring its borders with the border
+    /// color when colors are enabled.
+    fn render(&self, table: &Table) -> String {
+        match self.border_color.filter(|_| color::is_enabled()) {
+            Some(color) => color_borders(&table.to_string(), color),
+            None => table.to_string(),
+        }
+    }
+}
+
+/// Represents a text attribute applied to table cells.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextAttribute {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    SlowBlink,
+    RapidBlink,
+    Reverse,
+    Hidden,
+    CrossedOut,
+}
+
+impl From<TextAttribute> for Attribute {
+    fn from(attr: TextAttribute) -> Self {
+        match attr {
+            TextAttribute::Bold => Attribute::Bold,
+            TextAttribute::Dim => Attribute::Dim,
+            TextAttribute::Italic => Attribute::Italic,
+            TextAttribute::Underlined => Attribute::Underlined,
+            TextAttribute::SlowBlink => Attribute::SlowBlink,
+            TextAttribute::RapidBlink => Attribute::RapidBlink,
+            TextAttribute::Reverse => Attribute::Reverse,
+            TextAttribute::Hidden => Attribute::Hidden,
+            TextAttribute::CrossedOut => Attribute::CrossedOut,
+        }
+    }
+}
+
+/// Convert the given optional attributes into comfy-table ones.
+fn attributes(attrs: &Option<Vec<TextAttribute>>) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .flatten()
+        .copied()
+        .map(Attribute::from)
+        .collect()
+}
+
+/// Represents a style applied to envelope rows matching a flag
+/// condition.
+///
+/// ```toml
+/// [[envelope.list.table.row-styles]]
+/// flag = "deleted"
+/// attrs = ["dim", "crossed-out"]
+///
+/// [[envelope.list.table.row-styles]]
+/// flag = "flagged"
+/// bg = "yellow"
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RowStyle {
+    /// The flag condition, see [`FlagGlyph::flag`].
+    pub flag: String,
+    #[serde(default, with = "color::serde_option")]
+    pub fg: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
+    pub bg: Option<Color>,
+    pub attrs: Option<Vec<TextAttribute>>,
+}
+
+impl RowStyle {
+    pub fn matches(&self, envelope: &Envelope) -> bool {
+        matches_flag(&self.flag, envelope)
+    }
+}
+
+/// Represents a glyph displayed in the flags column of envelopes
+/// matching a flag condition.
+///
+/// ```toml
+/// [[envelope.list.table.flag-glyphs]]
+/// flag = "!seen"
+/// glyph = "*"
+///
+/// [[envelope.list.table.flag-glyphs]]
+/// flag = "todo"
+/// glyph = "T"
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlagGlyph {
+    /// The flag the envelope must have, or must not have when
+    /// prefixed with `!`.
+    ///
+    /// The special name `attachment` matches envelopes having
+    /// attachments. Unknown flag names match custom flags, like
+    /// notmuch tags.
+    pub flag: String,
+    pub glyph: String,
+}
+
+impl FlagGlyph {
+    pub fn new(flag: impl ToString, glyph: impl ToString) -> Self {
+        Self {
+            flag: flag.to_string(),
+            glyph: glyph.to_string(),
+        }
+    }
+
+    pub fn matches(&self, envelope: &Envelope) -> bool {
+        matches_flag(&self.flag, envelope)
+    }
+}
+
+fn matches_flag(condition: &str, envelope: &Envelope) -> bool {
+    let (negated, name) = match condition.strip_prefix('!') {
+        Some(name) => (true, name),
+        None => (false, condition),
+    };
+
+    let matches = if name.trim().eq_ignore_ascii_case("attachment") {
+        envelope.has_attachment
+    } else {
+        envelope.flags.contains(&Flag::from(name))
+    };
+
+    matches != negated
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct AccountsConfig {
@@ -380,26 +938,83 @@ pub struct ListAccountsConfig {
 #[serde(rename_all = "kebab-case")]
 pub struct ListAccountsTableConfig {
     pub preset: Option<String>,
+    pub columns: Option<Vec<AccountsTableColumn>>,
+    #[serde(default, with = "color::serde_option")]
     pub name_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
     pub backends_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
     pub default_color: Option<Color>,
+    pub name_attrs: Option<Vec<TextAttribute>>,
+    pub backends_attrs: Option<Vec<TextAttribute>>,
+    pub default_attrs: Option<Vec<TextAttribute>>,
 }
 
 impl ListAccountsTableConfig {
+    /// Fill options missing from this config with the given theme.
+    pub fn inherit(&self, theme: &ThemeConfig) -> Self {
+        Self {
+            preset: self.preset.clone().or_else(|| theme.preset.clone()),
+            name_color: self.name_color.or(theme.primary_color),
+            backends_color: self.backends_color.or(theme.secondary_color),
+            default_color: self.default_color.or(theme.muted_color),
+            ..self.clone()
+        }
+    }
+
     pub fn preset(&self) -> &str {
         self.preset.as_deref().unwrap_or(presets::ASCII_MARKDOWN)
     }
 
+    /// Return the columns to display in order, all of them by
+    /// default.
+    pub fn columns(&self) -> Vec<AccountsTableColumn> {
+        columns(&self.columns, &AccountsTableColumn::ALL)
+    }
+
     pub fn name_color(&self) -> comfy_table::Color {
-        map_color(self.name_color.unwrap_or(Color::Green))
+        color::to_comfy_table_color(self.name_color.unwrap_or(Color::Green))
     }
 
     pub fn backends_color(&self) -> comfy_table::Color {
-        map_color(self.backends_color.unwrap_or(Color::Blue))
+        color::to_comfy_table_color(self.backends_color.unwrap_or(Color::Blue))
     }
 
     pub fn default_color(&self) -> comfy_table::Color {
-        map_color(self.default_color.unwrap_or(Color::Reset))
+        color::to_comfy_table_color(self.default_color.unwrap_or(Color::Reset))
+    }
+
+    pub fn name_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.name_attrs)
+    }
+
+    pub fn backends_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.backends_attrs)
+    }
+
+    pub fn default_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.default_attrs)
+    }
+}
+
+/// Represents the columns of the accounts table.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AccountsTableColumn {
+    Name,
+    Backends,
+    Default,
+}
+
+impl AccountsTableColumn {
+    pub const ALL: [Self; 3] = [Self::Name, Self::Backends, Self::Default];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Self::Name => "NAME",
+            Self::Backends => "BACKENDS",
+            Self::Default => "DEFAULT",
+        }
     }
 }
 
@@ -430,15 +1045,56 @@ impl ToString for Backend {
     }
 }
 
+/// Timeouts and connections of the IMAP backend.
+///
+/// Timeouts are in seconds, and are disabled when missing.
+#[cfg(feature = "imap")]
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case", tag = "type")]
-pub enum BackendDerive {
+#[serde(rename_all = "kebab-case")]
+pub struct ImapTuningConfig {
+    /// Maximum duration of the connection to the server, including
+    /// the authentication.
+    pub connection_timeout: Option<u64>,
+    /// Maximum duration of a backend operation.
+    pub command_timeout: Option<u64>,
+    /// Override of the `clients-pool-size` backend option.
+    pub clients_pool_size: Option<u8>,
+}
+
+/// Storage of the aliases of message ids, used by backends whose ids
+/// are not suited for humans, see [`IdMapper`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdMapperKind {
+    /// Aliases are stored in a database, so they are stable across
+    /// invocations.
     #[default]
-    None,
+    Persistent,
+    /// Aliases are kept in memory, so they are only stable for the
+    /// lifetime of the backend.
+    Memory,
+}
 
-    #[cfg(feature = "imap")]
-    Imap(ImapConfig),
-    #[cfg(not(feature = "imap"))]
+/// Limits of the operations sent to the backend, for providers
+/// throttling aggressive clients.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RateLimitConfig {
+    /// Maximum number of operations started per second.
+    pub requests_per_second: Option<u32>,
+    /// Maximum number of operations running at the same time.
+    pub max_concurrent_commands: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum BackendDerive {
+    #[default]
+    None,
+
+    #[cfg(feature = "imap")]
+    Imap(ImapConfig),
+    #[cfg(not(feature = "imap"))]
     #[serde(skip_serializing, deserialize_with = "missing_imap_feature")]
     Imap,
 
@@ -566,6 +1222,7 @@ fn missing_sendmail_feature<'de, D: serde::Deserializer<'de>, T>(_: D) -> Result
 #[serde(rename_all = "kebab-case")]
 pub struct EnvelopeConfig {
     pub list: Option<ListEnvelopesConfig>,
+    pub thread: Option<ThreadEnvelopesConfig>,
 }
 
 impl From<EnvelopeConfig> for email::envelope::config::EnvelopeConfig {
@@ -578,6 +1235,22 @@ impl From<EnvelopeConfig> for email::envelope::config::EnvelopeConfig {
 }
 
 impl EnvelopeConfig {
+    pub fn list_group_by(&self) -> Option<EnvelopesGroupBy> {
+        self.list.as_ref().and_then(|list| list.group_by)
+    }
+
+    pub fn thread_max_depth(&self) -> Option<usize> {
+        self.thread.as_ref().and_then(|thread| thread.max_depth)
+    }
+
+    pub fn thread_order(&self) -> Option<ThreadsOrder> {
+        self.thread.as_ref().and_then(|thread| thread.order)
+    }
+
+    pub fn thread_tree(&self) -> Option<ThreadEnvelopesTreeConfig> {
+        self.thread.as_ref().and_then(|thread| thread.tree.clone())
+    }
+
     pub fn list_table_preset(&self) -> Option<String> {
         self.list
             .as_ref()
@@ -585,6 +1258,13 @@ impl EnvelopeConfig {
             .and_then(|c| c.preset.clone())
     }
 
+    pub fn list_table_flag_glyphs(&self) -> Option<Vec<FlagGlyph>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.flag_glyphs.clone())
+    }
+
     pub fn list_table_unseen_char(&self) -> Option<char> {
         self.list
             .as_ref()
@@ -613,6 +1293,48 @@ impl EnvelopeConfig {
             .and_then(|table| table.attachment_char)
     }
 
+    pub fn list_table_columns(&self) -> Option<Vec<EnvelopesTableColumn>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.columns.clone())
+    }
+
+    pub fn list_table_subject_max_width(&self) -> Option<u16> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.subject_max_width)
+    }
+
+    pub fn list_table_sender_max_width(&self) -> Option<u16> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.sender_max_width)
+    }
+
+    pub fn list_table_ellipsis(&self) -> Option<String> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.ellipsis.clone())
+    }
+
+    pub fn list_table_multiline(&self) -> Option<bool> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.multiline)
+    }
+
+    pub fn list_table_show_summary(&self) -> Option<bool> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.show_summary)
+    }
+
     pub fn list_table_id_color(&self) -> Option<Color> {
         self.list
             .as_ref()
@@ -647,6 +1369,48 @@ impl EnvelopeConfig {
             .and_then(|list| list.table.as_ref())
             .and_then(|table| table.date_color)
     }
+
+    pub fn list_table_id_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.id_attrs.clone())
+    }
+
+    pub fn list_table_flags_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.flags_attrs.clone())
+    }
+
+    pub fn list_table_subject_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.subject_attrs.clone())
+    }
+
+    pub fn list_table_sender_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.sender_attrs.clone())
+    }
+
+    pub fn list_table_date_attrs(&self) -> Option<Vec<TextAttribute>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.date_attrs.clone())
+    }
+
+    pub fn list_table_row_styles(&self) -> Option<Vec<RowStyle>> {
+        self.list
+            .as_ref()
+            .and_then(|list| list.table.as_ref())
+            .and_then(|table| table.row_styles.clone())
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
@@ -655,9 +1419,43 @@ pub struct ListEnvelopesConfig {
     pub page_size: Option<usize>,
     pub datetime_fmt: Option<String>,
     pub datetime_local_tz: Option<bool>,
+    pub group_by: Option<EnvelopesGroupBy>,
     pub table: Option<ListEnvelopesTableConfig>,
 }
 
+/// Represents the way envelopes are grouped in the envelopes table.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvelopesGroupBy {
+    Day,
+    Sender,
+}
+
+impl EnvelopesGroupBy {
+    /// Return the label of the group the given envelope belongs to.
+    pub fn label(&self, envelope: &Envelope) -> String {
+        match self {
+            Self::Sender => envelope.from.name_or_addr().to_owned(),
+            Self::Day => {
+                let Some(date) = envelope.datetime else {
+                    return String::from("Unknown date");
+                };
+
+                let date = date.with_timezone(&Local).date_naive();
+                let today = Local::now().date_naive();
+
+                if date == today {
+                    String::from("Today")
+                } else if today.pred_opt() == Some(date) {
+                    String::from("Yesterday")
+                } else {
+                    date.format("%A, %B %-d, %Y").to_string()
+                }
+            }
+        }
+    }
+}
+
 impl From<ListEnvelopesConfig> for email::envelope::list::config::EnvelopeListConfig {
     fn from(config: ListEnvelopesConfig) -> Self {
         Self {
@@ -672,24 +1470,146 @@ impl From<ListEnvelopesConfig> for email::envelope::list::config::EnvelopeListCo
 #[serde(rename_all = "kebab-case")]
 pub struct ListEnvelopesTableConfig {
     pub preset: Option<String>,
+    pub columns: Option<Vec<EnvelopesTableColumn>>,
+
+    /// Glyphs displayed in the flags column, in order.
+    ///
+    /// When not defined, the glyphs are built from the legacy
+    /// `*-char` options below.
+    pub flag_glyphs: Option<Vec<FlagGlyph>>,
 
     pub unseen_char: Option<char>,
     pub replied_char: Option<char>,
     pub flagged_char: Option<char>,
     pub attachment_char: Option<char>,
 
+    #[serde(default, with = "color::serde_option")]
     pub id_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
     pub flags_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
     pub subject_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
     pub sender_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
     pub date_color: Option<Color>,
+
+    pub id_attrs: Option<Vec<TextAttribute>>,
+    pub flags_attrs: Option<Vec<TextAttribute>>,
+    pub subject_attrs: Option<Vec<TextAttribute>>,
+    pub sender_attrs: Option<Vec<TextAttribute>>,
+    pub date_attrs: Option<Vec<TextAttribute>>,
+
+    /// Styles applied to rows matching their flag condition, in
+    /// order. Later styles take precedence over earlier ones.
+    pub row_styles: Option<Vec<RowStyle>>,
+
+    pub subject_max_width: Option<u16>,
+    pub sender_max_width: Option<u16>,
+    pub ellipsis: Option<String>,
+
+    /// Display envelopes on two lines: the subject on the first line,
+    /// the sender and the date on the second one.
+    pub multiline: Option<bool>,
+
+    pub show_summary: Option<bool>,
 }
 
 impl ListEnvelopesTableConfig {
+    /// Fill options missing from this config with the given theme.
+    pub fn inherit(&self, theme: &ThemeConfig) -> Self {
+        Self {
+            preset: self.preset.clone().or_else(|| theme.preset.clone()),
+            id_color: self.id_color.or(theme.primary_color),
+            flags_color: self.flags_color.or(theme.muted_color),
+            subject_color: self.subject_color.or(theme.secondary_color),
+            sender_color: self.sender_color.or(theme.tertiary_color),
+            date_color: self.date_color.or(theme.muted_color),
+            ..self.clone()
+        }
+    }
+
     pub fn preset(&self) -> &str {
         self.preset.as_deref().unwrap_or(presets::ASCII_MARKDOWN)
     }
 
+    /// Return the columns to display in order, the default ones if
+    /// none is configured.
+    ///
+    /// In multi-line mode, the sender and the date are displayed
+    /// below the subject, so their columns are omitted.
+    pub fn columns(&self) -> Vec<EnvelopesTableColumn> {
+        let mut columns = columns(&self.columns, &EnvelopesTableColumn::DEFAULT);
+
+        if self.is_multiline() && columns.contains(&EnvelopesTableColumn::Subject) {
+            columns.retain(|column| {
+                !matches!(
+                    column,
+                    EnvelopesTableColumn::From | EnvelopesTableColumn::Date
+                )
+            });
+        }
+
+        columns
+    }
+
+    pub fn is_multiline(&self) -> bool {
+        self.multiline.unwrap_or_default()
+    }
+
+    pub fn show_summary(&self) -> bool {
+        self.show_summary.unwrap_or_default()
+    }
+
+    /// Return the maximum width of the given column, if any.
+    ///
+    /// The sender maximum width applies to all address columns.
+    pub fn max_width(&self, column: EnvelopesTableColumn) -> Option<u16> {
+        match column {
+            EnvelopesTableColumn::Subject => self.subject_max_width,
+            EnvelopesTableColumn::From | EnvelopesTableColumn::To => self.sender_max_width,
+            _ => None,
+        }
+    }
+
+    pub fn ellipsis(&self) -> &str {
+        self.ellipsis.as_deref().unwrap_or("…")
+    }
+
+    /// Return the flag glyphs in order.
+    ///
+    /// Defaults to the flagged, unseen, attachment and replied
+    /// glyphs.
+    pub fn flag_glyphs(&self) -> Vec<FlagGlyph> {
+        match &self.flag_glyphs {
+            Some(glyphs) => glyphs.clone(),
+            None => vec![
+                FlagGlyph::new("flagged", self.flagged_char.unwrap_or('!')),
+                FlagGlyph::new("!seen", self.unseen_char.unwrap_or('*')),
+                FlagGlyph::new("attachment", self.attachment_char.unwrap_or('@')),
+                FlagGlyph::new("answered", self.replied_char.unwrap_or('R')),
+            ],
+        }
+    }
+
+    /// Render the flags column of the given envelope.
+    ///
+    /// Each glyph takes its own slot, filled with spaces when the
+    /// envelope does not match it, so that glyphs stay aligned.
+    pub fn flags(&self, envelope: &Envelope) -> String {
+        let mut flags = String::new();
+
+        for glyph in self.flag_glyphs() {
+            if glyph.matches(envelope) {
+                flags.push_str(&glyph.glyph);
+            } else {
+                flags.push_str(&" ".repeat(glyph.glyph.width()));
+            }
+        }
+
+        flags
+    }
+
     pub fn replied_char(&self, replied: bool) -> char {
         if replied {
             self.replied_char.unwrap_or('R')
@@ -723,23 +1643,231 @@ impl ListEnvelopesTableConfig {
     }
 
     pub fn id_color(&self) -> comfy_table::Color {
-        map_color(self.id_color.unwrap_or(Color::Red))
+        color::to_comfy_table_color(self.id_color.unwrap_or(Color::Red))
     }
 
     pub fn flags_color(&self) -> comfy_table::Color {
-        map_color(self.flags_color.unwrap_or(Color::Reset))
+        color::to_comfy_table_color(self.flags_color.unwrap_or(Color::Reset))
     }
 
     pub fn subject_color(&self) -> comfy_table::Color {
-        map_color(self.subject_color.unwrap_or(Color::Green))
+        color::to_comfy_table_color(self.subject_color.unwrap_or(Color::Green))
     }
 
     pub fn sender_color(&self) -> comfy_table::Color {
-        map_color(self.sender_color.unwrap_or(Color::Blue))
+        color::to_comfy_table_color(self.sender_color.unwrap_or(Color::Blue))
     }
 
     pub fn date_color(&self) -> comfy_table::Color {
-        map_color(self.date_color.unwrap_or(Color::DarkYellow))
+        color::to_comfy_table_color(self.date_color.unwrap_or(Color::DarkYellow))
+    }
+
+    pub fn id_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.id_attrs)
+    }
+
+    pub fn flags_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.flags_attrs)
+    }
+
+    pub fn subject_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.subject_attrs)
+    }
+
+    pub fn sender_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.sender_attrs)
+    }
+
+    pub fn date_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.date_attrs)
+    }
+
+    /// Return the row styles matching the given envelope, in order.
+    pub fn row_styles<'a>(&'a self, envelope: &'a Envelope) -> impl Iterator<Item = &'a RowStyle> {
+        self.row_styles
+            .iter()
+            .flatten()
+            .filter(move |style| style.matches(envelope))
+    }
+}
+
+/// Represents the columns of the envelopes table.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvelopesTableColumn {
+    Id,
+    Flags,
+    Subject,
+    From,
+    To,
+    Date,
+    MessageId,
+    /// Indentation showing the thread structure, only filled when
+    /// the table is built from a thread tree.
+    Thread,
+}
+
+impl EnvelopesTableColumn {
+    /// Columns displayed when none is configured.
+    pub const DEFAULT: [Self; 5] = [Self::Id, Self::Flags, Self::Subject, Self::From, Self::Date];
+
+    pub const ALL: [Self; 8] = [
+        Self::Id,
+        Self::Flags,
+        Self::Subject,
+        Self::From,
+        Self::To,
+        Self::Date,
+        Self::MessageId,
+        Self::Thread,
+    ];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Self::Id => "ID",
+            Self::Flags => "FLAGS",
+            Self::Subject => "SUBJECT",
+            Self::From => "FROM",
+            Self::To => "TO",
+            Self::Date => "DATE",
+            Self::MessageId => "MESSAGE-ID",
+            Self::Thread => "THREAD",
+        }
+    }
+}
+
+impl fmt::Display for EnvelopesTableColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ThreadEnvelopesConfig {
+    /// Maximum depth of displayed replies. Deeper replies are
+    /// collapsed into a single node.
+    pub max_depth: Option<usize>,
+    pub order: Option<ThreadsOrder>,
+    pub tree: Option<ThreadEnvelopesTreeConfig>,
+}
+
+/// Represents the order of sibling envelopes in threads.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThreadsOrder {
+    #[default]
+    OldestFirst,
+    NewestFirst,
+    /// Siblings with the most recent reply among their descendants
+    /// first.
+    LastActivity,
+}
+
+/// Represents the configuration of the threaded envelopes tree.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ThreadEnvelopesTreeConfig {
+    pub connectors: Option<TreeConnectors>,
+
+    #[serde(default, with = "color::serde_option")]
+    pub id_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
+    pub subject_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
+    pub sender_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
+    pub date_color: Option<Color>,
+    /// Color of the separators around ids and senders, and of the
+    /// dots between senders and dates.
+    #[serde(default, with = "color::serde_option")]
+    pub connector_color: Option<Color>,
+
+    /// Display the number of unseen and total envelopes next to
+    /// thread roots.
+    pub show_counts: Option<bool>,
+}
+
+impl ThreadEnvelopesTreeConfig {
+    /// Fill options missing from this config with the given theme.
+    pub fn inherit(&self, theme: &ThemeConfig) -> Self {
+        Self {
+            id_color: self.id_color.or(theme.primary_color),
+            subject_color: self.subject_color.or(theme.secondary_color),
+            sender_color: self.sender_color.or(theme.tertiary_color),
+            date_color: self.date_color.or(theme.muted_color),
+            ..self.clone()
+        }
+    }
+
+    pub fn connectors(&self) -> TreeConnectors {
+        self.connectors.unwrap_or_default()
+    }
+
+    pub fn show_counts(&self) -> bool {
+        self.show_counts.unwrap_or_default()
+    }
+
+    pub fn id_color(&self) -> Color {
+        self.id_color.unwrap_or(Color::Red)
+    }
+
+    pub fn subject_color(&self) -> Color {
+        self.subject_color.unwrap_or(Color::Green)
+    }
+
+    pub fn sender_color(&self) -> Color {
+        self.sender_color.unwrap_or(Color::Blue)
+    }
+
+    pub fn date_color(&self) -> Color {
+        self.date_color.unwrap_or(Color::DarkYellow)
+    }
+
+    pub fn connector_color(&self) -> Color {
+        self.connector_color.unwrap_or(Color::DarkGrey)
+    }
+}
+
+/// Represents the glyph set used to draw the threaded envelopes
+/// tree.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeConnectors {
+    #[default]
+    Unicode,
+    /// Plain ASCII glyphs, for terminals or fonts missing box
+    /// drawing characters.
+    Ascii,
+}
+
+impl TreeConnectors {
+    /// Return the branch glyph of a child, depending on whether it
+    /// is the last one.
+    pub fn branch(&self, is_last: bool) -> &'static str {
+        match (self, is_last) {
+            (Self::Unicode, false) => "├─ ",
+            (Self::Unicode, true) => "└─ ",
+            (Self::Ascii, false) => "|- ",
+            (Self::Ascii, true) => "`- ",
+        }
+    }
+
+    /// Return the padding of the children of a child, depending on
+    /// whether it is the last one.
+    pub fn pad(&self, is_last: bool) -> &'static str {
+        match (self, is_last) {
+            (_, true) => "   ",
+            (Self::Unicode, false) => "│  ",
+            (Self::Ascii, false) => "|  ",
+        }
+    }
+
+    pub fn dot(&self) -> &'static str {
+        match self {
+            Self::Unicode => "·",
+            Self::Ascii => ".",
+        }
     }
 }
 
@@ -778,21 +1906,80 @@ impl From<ListFoldersConfig> for email::folder::list::config::FolderListConfig {
 #[serde(rename_all = "kebab-case")]
 pub struct ListFoldersTableConfig {
     pub preset: Option<String>,
+    pub columns: Option<Vec<FoldersTableColumn>>,
+    #[serde(default, with = "color::serde_option")]
     pub name_color: Option<Color>,
+    #[serde(default, with = "color::serde_option")]
     pub desc_color: Option<Color>,
+    pub name_attrs: Option<Vec<TextAttribute>>,
+    pub desc_attrs: Option<Vec<TextAttribute>>,
+    pub show_summary: Option<bool>,
 }
 
 impl ListFoldersTableConfig {
+    /// Fill options missing from this config with the given theme.
+    pub fn inherit(&self, theme: &ThemeConfig) -> Self {
+        Self {
+            preset: self.preset.clone().or_else(|| theme.preset.clone()),
+            name_color: self.name_color.or(theme.primary_color),
+            desc_color: self.desc_color.or(theme.secondary_color),
+            ..self.clone()
+        }
+    }
+
     pub fn preset(&self) -> &str {
         self.preset.as_deref().unwrap_or(presets::ASCII_MARKDOWN)
     }
 
+    pub fn show_summary(&self) -> bool {
+        self.show_summary.unwrap_or_default()
+    }
+
+    /// Return the columns to display in order, all of them by
+    /// default.
+    pub fn columns(&self) -> Vec<FoldersTableColumn> {
+        columns(&self.columns, &FoldersTableColumn::ALL)
+    }
+
     pub fn name_color(&self) -> comfy_table::Color {
-        map_color(self.name_color.unwrap_or(Color::Blue))
+        color::to_comfy_table_color(self.name_color.unwrap_or(Color::Blue))
     }
 
     pub fn desc_color(&self) -> comfy_table::Color {
-        map_color(self.desc_color.unwrap_or(Color::Green))
+        color::to_comfy_table_color(self.desc_color.unwrap_or(Color::Green))
+    }
+
+    pub fn name_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.name_attrs)
+    }
+
+    pub fn desc_attrs(&self) -> Vec<Attribute> {
+        attributes(&self.desc_attrs)
+    }
+}
+
+/// Represents the columns of the folders table.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FoldersTableColumn {
+    Name,
+    Desc,
+}
+
+impl FoldersTableColumn {
+    pub const ALL: [Self; 2] = [Self::Name, Self::Desc];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Self::Name => "NAME",
+            Self::Desc => "DESC",
+        }
+    }
+}
+
+impl fmt::Display for FoldersTableColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())
     }
 }
 
@@ -811,7 +1998,7 @@ impl From<MessageConfig> for email::message::config::MessageConfig {
             read: config.read,
             write: config.write,
             send: config.send.map(Into::into),
-            delete: config.delete,
+            delete: config.delete.map(Into::into),
         }
     }
 }
@@ -833,53 +2020,241 @@ impl From<SendMessageConfig> for email::message::send::config::MessageSendConfig
     }
 }
 
-fn map_color(color: Color) -> comfy_table::Color {
-    match color {
-        Color::Reset => comfy_table::Color::Reset,
-        Color::Black => comfy_table::Color::Black,
-        Color::DarkGrey => comfy_table::Color::DarkGrey,
-        Color::Red => comfy_table::Color::Red,
-        Color::DarkRed => comfy_table::Color::DarkRed,
-        Color::Green => comfy_table::Color::Green,
-        Color::DarkGreen => comfy_table::Color::DarkGreen,
-        Color::Yellow => comfy_table::Color::Yellow,
-        Color::DarkYellow => comfy_table::Color::DarkYellow,
-        Color::Blue => comfy_table::Color::Blue,
-        Color::DarkBlue => comfy_table::Color::DarkBlue,
-        Color::Magenta => comfy_table::Color::Magenta,
-        Color::DarkMagenta => comfy_table::Color::DarkMagenta,
-        Color::Cyan => comfy_table::Color::Cyan,
-        Color::DarkCyan => comfy_table::Color::DarkCyan,
-        Color::White => comfy_table::Color::White,
-        Color::Grey => comfy_table::Color::Grey,
-        Color::Rgb { r, g, b } => comfy_table::Color::Rgb { r, g, b },
-        Color::AnsiValue(n) => comfy_table::Color::AnsiValue(n),
-    }
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DeleteMessageConfig {
+    pub style: Option<DeleteMessageStyle>,
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
-pub struct Folder {
-    pub name: String,
-    pub desc: String,
+impl DeleteMessageConfig {
+    pub fn style(&self) -> DeleteMessageStyle {
+        self.style.unwrap_or_default()
+    }
 }
 
-impl Folder {
-    pub fn to_row(&self, config: &ListFoldersTableConfig) -> Row {
-        let mut row = Row::new();
-        row.max_height(1);
-
-        row.add_cell(Cell::new(&self.name).fg(config.name_color()));
-        row.add_cell(Cell::new(&self.desc).fg(config.desc_color()));
-
-        row
+impl From<DeleteMessageConfig> for email::message::delete::config::DeleteMessageConfig {
+    fn from(config: DeleteMessageConfig) -> Self {
+        Self {
+            style: config.style.map(Into::into),
+        }
     }
 }
 
-impl From<email::folder::Folder> for Folder {
-    fn from(folder: email::folder::Folder) -> Self {
-        Folder {
-            name: folder.name,
-            desc: folder.desc,
+/// How messages are deleted.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeleteMessageStyle {
+    /// Move messages to the trash folder, or flag them as deleted
+    /// when they already are in the trash folder.
+    #[default]
+    #[serde(alias = "folder")]
+    Trash,
+    /// Flag messages as deleted, without removing them.
+    Flag,
+    /// Remove messages immediately, without going through the trash
+    /// folder.
+    Expunge,
+}
+
+impl From<DeleteMessageStyle> for email::message::delete::config::DeleteMessageStyle {
+    fn from(style: DeleteMessageStyle) -> Self {
+        use email::message::delete::config::DeleteMessageStyle::*;
+
+        // immediate removals are handled by the backend, see
+        // `Backend::delete_messages_with_style`
+        match style {
+            DeleteMessageStyle::Trash => Folder,
+            DeleteMessageStyle::Flag | DeleteMessageStyle::Expunge => Flag,
+        }
+    }
+}
+
+/// Return the configured columns in order without duplicates, or all
+/// the given columns if none is configured.
+fn columns<T: Copy + PartialEq>(config: &Option<Vec<T>>, all: &[T]) -> Vec<T> {
+    let Some(config) = config else {
+        return all.to_vec();
+    };
+
+    let mut columns = Vec::with_capacity(config.len());
+
+    for column in config {
+        if !columns.contains(column) {
+            columns.push(*column);
+        }
+    }
+
+    columns
+}
+
+/// Format the given count followed by the given word, pluralized if
+/// needed.
+pub(crate) fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{count} {word}")
+    } else {
+        format!("{count} {word}s")
+    }
+}
+
+/// Truncate the given text to the given display width, ending it
+/// with the given ellipsis.
+fn truncate(text: &str, max: usize, ellipsis: &str) -> String {
+    if text.width() <= max {
+        return text.to_owned();
+    }
+
+    let max = max.saturating_sub(ellipsis.width());
+    let mut width = 0;
+    let mut truncated = String::new();
+
+    for c in text.chars() {
+        width += c.width().unwrap_or_default();
+
+        if width > max {
+            break;
+        }
+
+        truncated.push(c);
+    }
+
+    truncated.push_str(ellipsis);
+    truncated
+}
+
+/// Strip the table styling when colors are disabled.
+///
+/// The detected width is kept, since comfy-table stops detecting it
+/// once the table is no longer considered as attached to a TTY.
+fn disable_styling_unless_colored(table: &mut Table) {
+    if color::is_enabled() {
+        return;
+    }
+
+    if let Some(width) = table.width() {
+        table.set_width(width);
+    }
+
+    table.force_no_tty();
+}
+
+/// Color the borders of the given rendered table.
+///
+/// comfy-table cannot color borders, so they are colored once the
+/// table is rendered: lines made of border characters only are
+/// colored as a whole, while borders of other lines are colored at
+/// the columns of the junctions of the first border line.
+fn color_borders(table: &str, color: Color) -> String {
+    let is_border_line = |line: &str| {
+        line.chars().any(is_border_char) && line.chars().all(|c| c == ' ' || is_border_char(c))
+    };
+
+    // border lines only contain single-width characters
+    let junctions: Vec<usize> = table
+        .lines()
+        .find(|line| is_border_line(line))
+        .map(|line| {
+            line.chars()
+                .enumerate()
+                .filter(|(_, c)| is_border_char(*c) && !is_horizontal_border_char(*c))
+                .map(|(i, _)| i)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let lines = table.split('\n').map(|line| {
+        if is_border_line(line) {
+            return line.with(color).to_string();
+        }
+
+        let mut colored = String::with_capacity(line.len());
+        let mut column = 0;
+        let mut chars = line.chars();
+
+        while let Some(c) = chars.next() {
+            // cell styles are CSI sequences, ending with a byte in
+            // the range @ to ~
+            if c == '\x1b' {
+                colored.push(c);
+
+                if let Some(c) = chars.next() {
+                    colored.push(c);
+
+                    if c == '[' {
+                        for c in chars.by_ref() {
+                            colored.push(c);
+
+                            if ('@'..='~').contains(&c) {
+                                break;
+                            }
+                        }
+                    }
+                }
+
+                continue;
+            }
+
+            if is_border_char(c) && junctions.contains(&column) {
+                colored.push_str(&c.with(color).to_string());
+            } else {
+                colored.push(c);
+            }
+
+            column += c.width().unwrap_or_default();
+        }
+
+        colored
+    });
+
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// Return `true` if the given character is used by table presets to
+/// draw borders: box-drawing characters and their ASCII counterparts.
+fn is_border_char(c: char) -> bool {
+    matches!(c, '\u{2500}'..='\u{257f}' | '+' | '-' | '|' | '=')
+}
+
+fn is_horizontal_border_char(c: char) -> bool {
+    matches!(
+        c,
+        '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' | '-' | '='
+    )
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Folder {
+    pub name: String,
+    pub desc: String,
+}
+
+impl Folder {
+    pub fn to_row(&self, config: &ListFoldersTableConfig) -> Row {
+        let mut row = Row::new();
+        row.max_height(1);
+
+        for column in config.columns() {
+            let cell = match column {
+                FoldersTableColumn::Name => Cell::new(&self.name)
+                    .add_attributes(config.name_attrs())
+                    .fg(config.name_color()),
+                FoldersTableColumn::Desc => Cell::new(&self.desc)
+                    .add_attributes(config.desc_attrs())
+                    .fg(config.desc_color()),
+            };
+
+            row.add_cell(cell);
+        }
+
+        row
+    }
+}
+
+impl From<email::folder::Folder> for Folder {
+    fn from(folder: email::folder::Folder) -> Self {
+        Folder {
+            name: folder.name,
+            desc: folder.desc,
         }
     }
 }
@@ -904,6 +2279,7 @@ impl From<email::folder::Folders> for Folders {
 pub struct FoldersTable {
     folders: Folders,
     width: Option<u16>,
+    theme: ThemeConfig,
     config: ListFoldersTableConfig,
 }
 
@@ -913,11 +2289,21 @@ impl FoldersTable {
         self
     }
 
+    pub fn with_some_theme(mut self, theme: Option<ThemeConfig>) -> Self {
+        self.theme = theme.unwrap_or_default();
+        self
+    }
+
     pub fn with_some_preset(mut self, preset: Option<String>) -> Self {
         self.config.preset = preset;
         self
     }
 
+    pub fn with_some_columns(mut self, columns: Option<Vec<FoldersTableColumn>>) -> Self {
+        self.config.columns = columns;
+        self
+    }
+
     pub fn with_some_name_color(mut self, color: Option<Color>) -> Self {
         self.config.name_color = color;
         self
@@ -927,6 +2313,21 @@ impl FoldersTable {
         self.config.desc_color = color;
         self
     }
+
+    pub fn with_some_name_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.name_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_desc_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.desc_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_show_summary(mut self, show_summary: Option<bool>) -> Self {
+        self.config.show_summary = show_summary;
+        self
+    }
 }
 
 impl From<Folders> for FoldersTable {
@@ -934,6 +2335,7 @@ impl From<Folders> for FoldersTable {
         Self {
             folders,
             width: None,
+            theme: Default::default(),
             config: Default::default(),
         }
     }
@@ -941,25 +2343,36 @@ impl From<Folders> for FoldersTable {
 
 impl fmt::Display for FoldersTable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = self.config.inherit(&self.theme);
         let mut table = Table::new();
 
         table
-            .load_preset(self.config.preset())
+            .load_preset(config.preset())
             .set_content_arrangement(ContentArrangement::DynamicFullWidth)
-            .set_header(Row::from([Cell::new("NAME"), Cell::new("DESC")]))
-            .add_rows(
-                self.folders
+            .set_header(Row::from(
+                config
+                    .columns()
                     .iter()
-                    .map(|folder| folder.to_row(&self.config)),
-            );
+                    .map(|column| self.theme.header(column.header())),
+            ))
+            .add_rows(self.folders.iter().map(|folder| folder.to_row(&config)));
 
         if let Some(width) = self.width {
             table.set_width(width);
         }
 
+        disable_styling_unless_colored(&mut table);
+
         writeln!(f)?;
-        write!(f, "{table}")?;
+        write!(f, "{}", self.theme.render(&table))?;
         writeln!(f)?;
+
+        if config.show_summary() {
+            color::init();
+            let summary = plural(self.folders.len(), "folder");
+            writeln!(f, "{}", summary.dark_grey())?;
+        }
+
         Ok(())
     }
 }
@@ -994,9 +2407,21 @@ impl Account {
         let mut row = Row::new();
         row.max_height(1);
 
-        row.add_cell(Cell::new(&self.name).fg(config.name_color()));
-        row.add_cell(Cell::new(&self.backend).fg(config.backends_color()));
-        row.add_cell(Cell::new(if self.default { "yes" } else { "" }).fg(config.default_color()));
+        for column in config.columns() {
+            let cell = match column {
+                AccountsTableColumn::Name => Cell::new(&self.name)
+                    .add_attributes(config.name_attrs())
+                    .fg(config.name_color()),
+                AccountsTableColumn::Backends => Cell::new(&self.backend)
+                    .add_attributes(config.backends_attrs())
+                    .fg(config.backends_color()),
+                AccountsTableColumn::Default => Cell::new(if self.default { "yes" } else { "" })
+                    .add_attributes(config.default_attrs())
+                    .fg(config.default_color()),
+            };
+
+            row.add_cell(cell);
+        }
 
         row
     }
@@ -1052,6 +2477,7 @@ impl From<Iter<'_, String, HimalayaTomlAccountConfig>> for Accounts {
 pub struct AccountsTable {
     accounts: Accounts,
     width: Option<u16>,
+    theme: ThemeConfig,
     config: ListAccountsTableConfig,
 }
 
@@ -1061,11 +2487,21 @@ impl AccountsTable {
         self
     }
 
+    pub fn with_some_theme(mut self, theme: Option<ThemeConfig>) -> Self {
+        self.theme = theme.unwrap_or_default();
+        self
+    }
+
     pub fn with_some_preset(mut self, preset: Option<String>) -> Self {
         self.config.preset = preset;
         self
     }
 
+    pub fn with_some_columns(mut self, columns: Option<Vec<AccountsTableColumn>>) -> Self {
+        self.config.columns = columns;
+        self
+    }
+
     pub fn with_some_name_color(mut self, color: Option<Color>) -> Self {
         self.config.name_color = color;
         self
@@ -1080,6 +2516,21 @@ impl AccountsTable {
         self.config.default_color = color;
         self
     }
+
+    pub fn with_some_name_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.name_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_backends_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.backends_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_default_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.default_attrs = attrs;
+        self
+    }
 }
 
 impl From<Accounts> for AccountsTable {
@@ -1087,6 +2538,7 @@ impl From<Accounts> for AccountsTable {
         Self {
             accounts,
             width: None,
+            theme: Default::default(),
             config: Default::default(),
         }
     }
@@ -1094,28 +2546,28 @@ impl From<Accounts> for AccountsTable {
 
 impl fmt::Display for AccountsTable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = self.config.inherit(&self.theme);
         let mut table = Table::new();
 
         table
-            .load_preset(self.config.preset())
+            .load_preset(config.preset())
             .set_content_arrangement(ContentArrangement::DynamicFullWidth)
-            .set_header(Row::from([
-                Cell::new("NAME"),
-                Cell::new("BACKENDS"),
-                Cell::new("DEFAULT"),
-            ]))
-            .add_rows(
-                self.accounts
+            .set_header(Row::from(
+                config
+                    .columns()
                     .iter()
-                    .map(|account| account.to_row(&self.config)),
-            );
+                    .map(|column| self.theme.header(column.header())),
+            ))
+            .add_rows(self.accounts.iter().map(|account| account.to_row(&config)));
 
         if let Some(width) = self.width {
             table.set_width(width);
         }
 
+        disable_styling_unless_colored(&mut table);
+
         writeln!(f)?;
-        write!(f, "{table}")?;
+        write!(f, "{}", self.theme.render(&table))?;
         writeln!(f)?;
         Ok(())
     }
@@ -1136,105 +2588,209 @@ pub struct Mailbox {
     pub addr: String,
 }
 
+impl Mailbox {
+    /// Return the name of the mailbox, or its address if it has no
+    /// name.
+    pub fn name_or_addr(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.addr)
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize)]
 pub struct Envelope {
     pub id: String,
+    pub message_id: String,
     pub flags: Flags,
     pub subject: String,
     pub from: Mailbox,
     pub to: Mailbox,
     pub date: String,
+    /// Raw date of the envelope, used to group envelopes by day.
+    #[serde(skip)]
+    pub datetime: Option<DateTime<FixedOffset>>,
     pub has_attachment: bool,
+    /// Tree branches preceding the envelope when listed as part of a
+    /// thread, see [`EnvelopesTree::to_table`].
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub thread_prefix: String,
 }
 
 impl Envelope {
+    /// Build an envelope from the given backend envelope, using the
+    /// given id in place of the backend one.
+    fn from_backend(
+        config: &AccountConfig,
+        id: String,
+        envelope: &email::envelope::Envelope,
+    ) -> Self {
+        Envelope {
+            id,
+            message_id: envelope.message_id.clone(),
+            flags: envelope.flags.clone().into(),
+            subject: envelope.subject.clone(),
+            from: Mailbox {
+                name: envelope.from.name.clone(),
+                addr: envelope.from.addr.clone(),
+            },
+            to: Mailbox {
+                name: envelope.to.name.clone(),
+                addr: envelope.to.addr.clone(),
+            },
+            date: envelope.format_date(config),
+            datetime: Some(envelope.date),
+            has_attachment: envelope.has_attachment,
+            thread_prefix: String::new(),
+        }
+    }
+
     fn to_row(&self, config: &ListEnvelopesTableConfig) -> Row {
         let mut all_attributes = vec![];
+        let mut fg = None;
+        let mut bg = None;
 
         let unseen = !self.flags.contains(&Flag::Seen);
         if unseen {
             all_attributes.push(Attribute::Bold)
         }
 
-        let flags = {
-            let mut flags = String::new();
-
-            flags.push(config.flagged_char(self.flags.contains(&Flag::Flagged)));
-            flags.push(config.unseen_char(unseen));
-            flags.push(config.attachment_char(self.has_attachment));
-            flags.push(config.replied_char(self.flags.contains(&Flag::Answered)));
+        for style in config.row_styles(self) {
+            all_attributes.extend(attributes(&style.attrs));
+            fg = style.fg.or(fg);
+            bg = style.bg.or(bg);
+        }
 
-            flags
-        };
+        let flags = config.flags(self);
 
         let mut row = Row::new();
-        row.max_height(1);
+        row.max_height(if config.is_multiline() { 2 } else { 1 });
 
-        row.add_cell(
-            Cell::new(&self.id)
-                .add_attributes(all_attributes.clone())
-                .fg(config.id_color()),
-        )
-        .add_cell(
-            Cell::new(flags)
-                .add_attributes(all_attributes.clone())
-                .fg(config.flags_color()),
-        )
-        .add_cell(
-            Cell::new(&self.subject)
+        for column in config.columns() {
+            let (content, color, attrs) = match column {
+                EnvelopesTableColumn::Id => (self.id.clone(), config.id_color(), config.id_attrs()),
+                EnvelopesTableColumn::Flags => {
+                    (flags.clone(), config.flags_color(), config.flags_attrs())
+                }
+                EnvelopesTableColumn::Subject if config.is_multiline() => {
+                    let sender = self.from.name_or_addr();
+                    let content = format!("{}\n{sender} · {}", self.subject, self.date);
+                    (content, config.subject_color(), config.subject_attrs())
+                }
+                EnvelopesTableColumn::Subject => (
+                    self.subject.clone(),
+                    config.subject_color(),
+                    config.subject_attrs(),
+                ),
+                EnvelopesTableColumn::From => {
+                    let from = self.from.name_or_addr().to_owned();
+                    (from, config.sender_color(), config.sender_attrs())
+                }
+                EnvelopesTableColumn::To => {
+                    let to = self.to.name_or_addr().to_owned();
+                    (to, config.sender_color(), config.sender_attrs())
+                }
+                EnvelopesTableColumn::Date => {
+                    (self.date.clone(), config.date_color(), config.date_attrs())
+                }
+                EnvelopesTableColumn::MessageId => {
+                    (self.message_id.clone(), comfy_table::Color::Reset, vec![])
+                }
+                EnvelopesTableColumn::Thread => (
+                    self.thread_prefix.clone(),
+                    comfy_table::Color::Reset,
+                    vec![],
+                ),
+            };
+
+            let content = match config.max_width(column) {
+                Some(max) => content
+                    .lines()
+                    .map(|line| truncate(line, max as usize, config.ellipsis()))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                None => content,
+            };
+
+            let mut cell = Cell::new(content)
                 .add_attributes(all_attributes.clone())
-                .fg(config.subject_color()),
-        )
-        .add_cell(
-            Cell::new(if let Some(name) = &self.from.name {
-                name
-            } else {
-                &self.from.addr
-            })
-            .add_attributes(all_attributes.clone())
-            .fg(config.sender_color()),
-        )
-        .add_cell(
-            Cell::new(&self.date)
-                .add_attributes(all_attributes)
-                .fg(config.date_color()),
-        );
+                .add_attributes(attrs)
+                .fg(fg.map(color::to_comfy_table_color).unwrap_or(color));
+
+            if let Some(bg) = bg {
+                cell = cell.bg(color::to_comfy_table_color(bg));
+            }
+
+            row.add_cell(cell);
+        }
 
         row
     }
 }
 
 #[derive(Clone, Debug, Default, Serialize)]
-pub struct Envelopes(Vec<Envelope>);
+#[serde(transparent)]
+pub struct Envelopes {
+    envelopes: Vec<Envelope>,
+    #[serde(skip)]
+    cached: bool,
+}
 
 impl Envelopes {
+    /// Whether the envelopes come from the envelopes cache instead
+    /// of the backend, for example because the backend failed to
+    /// list them.
+    pub fn is_cached(&self) -> bool {
+        self.cached
+    }
+
+    pub(crate) fn with_cached(mut self, cached: bool) -> Self {
+        self.cached = cached;
+        self
+    }
+
     pub fn try_from_backend(
         config: &AccountConfig,
         id_mapper: &IdMapper,
         envelopes: email::envelope::Envelopes,
     ) -> Result<Envelopes> {
+        let aliases = id_mapper.get_or_create_aliases(&Self::backend_ids(&envelopes))?;
+        Ok(Self::from_backend(config, &envelopes, aliases))
+    }
+
+    /// Same as [`Envelopes::try_from_backend`], without blocking the
+    /// executor while resolving aliases, see [`AsyncIdMapper`].
+    pub async fn try_from_backend_async(
+        config: &AccountConfig,
+        id_mapper: &IdMapper,
+        envelopes: email::envelope::Envelopes,
+    ) -> Result<Envelopes> {
+        let aliases = id_mapper
+            .get_or_create_aliases_async(Self::backend_ids(&envelopes))
+            .await?;
+        Ok(Self::from_backend(config, &envelopes, aliases))
+    }
+
+    fn backend_ids(envelopes: &email::envelope::Envelopes) -> Vec<String> {
+        envelopes
+            .iter()
+            .map(|envelope| envelope.id.clone())
+            .collect()
+    }
+
+    fn from_backend(
+        config: &AccountConfig,
+        envelopes: &email::envelope::Envelopes,
+        aliases: Vec<String>,
+    ) -> Envelopes {
         let envelopes = envelopes
             .iter()
-            .map(|envelope| {
-                Ok(Envelope {
-                    id: id_mapper.get_or_create_alias(&envelope.id)?,
-                    flags: envelope.flags.clone().into(),
-                    subject: envelope.subject.clone(),
-                    from: Mailbox {
-                        name: envelope.from.name.clone(),
-                        addr: envelope.from.addr.clone(),
-                    },
-                    to: Mailbox {
-                        name: envelope.to.name.clone(),
-                        addr: envelope.to.addr.clone(),
-                    },
-                    date: envelope.format_date(config),
-                    has_attachment: envelope.has_attachment,
-                })
-            })
-            .collect::<Result<Vec<_>>>()?;
+            .zip(aliases)
+            .map(|(envelope, id)| Envelope::from_backend(config, id, envelope))
+            .collect();
 
-        Ok(Envelopes(envelopes))
+        Envelopes {
+            envelopes,
+            cached: false,
+        }
     }
 }
 
@@ -1242,14 +2798,17 @@ impl Deref for Envelopes {
     type Target = Vec<Envelope>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.envelopes
     }
 }
 
 pub struct EnvelopesTable {
     envelopes: Envelopes,
     width: Option<u16>,
+    group_by: Option<EnvelopesGroupBy>,
+    theme: ThemeConfig,
     config: ListEnvelopesTableConfig,
+    threaded: bool,
 }
 
 impl EnvelopesTable {
@@ -1258,11 +2817,31 @@ impl EnvelopesTable {
         self
     }
 
+    pub fn with_some_theme(mut self, theme: Option<ThemeConfig>) -> Self {
+        self.theme = theme.unwrap_or_default();
+        self
+    }
+
     pub fn with_some_preset(mut self, preset: Option<String>) -> Self {
         self.config.preset = preset;
         self
     }
 
+    pub fn with_some_group_by(mut self, group_by: Option<EnvelopesGroupBy>) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    pub fn with_some_columns(mut self, columns: Option<Vec<EnvelopesTableColumn>>) -> Self {
+        self.config.columns = columns;
+        self
+    }
+
+    pub fn with_some_flag_glyphs(mut self, glyphs: Option<Vec<FlagGlyph>>) -> Self {
+        self.config.flag_glyphs = glyphs;
+        self
+    }
+
     pub fn with_some_unseen_char(mut self, char: Option<char>) -> Self {
         self.config.unseen_char = char;
         self
@@ -1307,6 +2886,61 @@ impl EnvelopesTable {
         self.config.date_color = color;
         self
     }
+
+    pub fn with_some_id_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.id_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_flags_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.flags_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_subject_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.subject_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_sender_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.sender_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_date_attrs(mut self, attrs: Option<Vec<TextAttribute>>) -> Self {
+        self.config.date_attrs = attrs;
+        self
+    }
+
+    pub fn with_some_row_styles(mut self, styles: Option<Vec<RowStyle>>) -> Self {
+        self.config.row_styles = styles;
+        self
+    }
+
+    pub fn with_some_subject_max_width(mut self, width: Option<u16>) -> Self {
+        self.config.subject_max_width = width;
+        self
+    }
+
+    pub fn with_some_sender_max_width(mut self, width: Option<u16>) -> Self {
+        self.config.sender_max_width = width;
+        self
+    }
+
+    pub fn with_some_ellipsis(mut self, ellipsis: Option<String>) -> Self {
+        self.config.ellipsis = ellipsis;
+        self
+    }
+
+    pub fn with_some_multiline(mut self, multiline: Option<bool>) -> Self {
+        self.config.multiline = multiline;
+        self
+    }
+
+    pub fn with_some_show_summary(mut self, show_summary: Option<bool>) -> Self {
+        self.config.show_summary = show_summary;
+        self
+    }
 }
 
 impl From<Envelopes> for EnvelopesTable {
@@ -1314,38 +2948,144 @@ impl From<Envelopes> for EnvelopesTable {
         Self {
             envelopes,
             width: None,
+            group_by: None,
+            theme: Default::default(),
             config: Default::default(),
+            threaded: false,
         }
     }
 }
 
 impl fmt::Display for EnvelopesTable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut config = self.config.inherit(&self.theme);
+
+        // threads are shown in front of the default columns
+        if self.threaded && config.columns.is_none() {
+            let mut columns = vec![EnvelopesTableColumn::Thread];
+            columns.extend(EnvelopesTableColumn::DEFAULT);
+            config.columns = Some(columns);
+        }
+
         let mut table = Table::new();
 
         table
-            .load_preset(self.config.preset())
+            .load_preset(config.preset())
             .set_content_arrangement(ContentArrangement::DynamicFullWidth)
-            .set_header(Row::from([
-                Cell::new("ID"),
-                Cell::new("FLAGS"),
-                Cell::new("SUBJECT"),
-                Cell::new("FROM"),
-                Cell::new("DATE"),
-            ]))
-            .add_rows(self.envelopes.iter().map(|env| env.to_row(&self.config)));
+            .set_header(Row::from(
+                config
+                    .columns()
+                    .iter()
+                    .map(|column| self.theme.header(column.header())),
+            ));
+
+        // grouping would break the thread structure
+        match self.group_by.as_ref().filter(|_| !self.threaded) {
+            None => {
+                for envelope in self.envelopes.iter() {
+                    table.add_row(envelope.to_row(&config));
+                }
+            }
+            Some(group_by) => {
+                let columns = config.columns();
+
+                // labels go in the subject column, which is the most
+                // flexible one, so that they do not widen other columns
+                let label_column = columns
+                    .iter()
+                    .position(|column| *column == EnvelopesTableColumn::Subject)
+                    .unwrap_or(columns.len().saturating_sub(1));
+
+                for (label, envelopes) in group_envelopes(&self.envelopes, group_by) {
+                    let mut row = Row::new();
+                    row.max_height(1);
+
+                    for i in 0..columns.len() {
+                        if i == label_column {
+                            row.add_cell(Cell::new(&label).add_attribute(Attribute::Underlined));
+                        } else {
+                            row.add_cell(Cell::new(""));
+                        }
+                    }
+
+                    table.add_row(row);
+
+                    for envelope in envelopes {
+                        table.add_row(envelope.to_row(&config));
+                    }
+                }
+            }
+        }
+
+        for (i, column) in config.columns().into_iter().enumerate() {
+            if let (Some(max), Some(table_column)) = (config.max_width(column), table.column_mut(i))
+            {
+                table_column.set_constraint(ColumnConstraint::UpperBoundary(Width::Fixed(max)));
+            }
+        }
 
         if let Some(width) = self.width {
             table.set_width(width);
         }
 
+        disable_styling_unless_colored(&mut table);
+
         writeln!(f)?;
-        write!(f, "{table}")?;
+        write!(f, "{}", self.theme.render(&table))?;
         writeln!(f)?;
+
+        if config.show_summary() {
+            let unseen = self
+                .envelopes
+                .iter()
+                .filter(|envelope| !envelope.flags.contains(&Flag::Seen))
+                .count();
+            let with_attachment = self
+                .envelopes
+                .iter()
+                .filter(|envelope| envelope.has_attachment)
+                .count();
+
+            let summary = format!(
+                "{}, {unseen} unseen, {with_attachment} with attachments",
+                plural(self.envelopes.len(), "message"),
+            );
+
+            color::init();
+            writeln!(f, "{}", summary.dark_grey())?;
+        }
+
         Ok(())
     }
 }
 
+/// Group the given envelopes by label, see [`EnvelopesGroupBy`].
+///
+/// Groups are ordered by their first envelope, and envelopes keep
+/// their order within groups, so that envelopes of the same group are
+/// listed together even when they are not consecutive.
+fn group_envelopes<'a>(
+    envelopes: &'a [Envelope],
+    group_by: &EnvelopesGroupBy,
+) -> Vec<(String, Vec<&'a Envelope>)> {
+    let mut groups: Vec<(String, Vec<&Envelope>)> = Vec::new();
+    let mut indexes = HashMap::new();
+
+    for envelope in envelopes {
+        let label = group_by.label(envelope);
+
+        match indexes.get(&label) {
+            Some(&i) => groups[i].1.push(envelope),
+            None => {
+                indexes.insert(label.clone(), groups.len());
+                groups.push((label, vec![envelope]));
+            }
+        }
+    }
+
+    groups
+}
+
 impl Serialize for EnvelopesTable {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -1355,28 +3095,78 @@ impl Serialize for EnvelopesTable {
     }
 }
 
-pub struct ThreadedEnvelopes(email::envelope::ThreadedEnvelopes);
+pub struct ThreadedEnvelopes {
+    envelopes: email::envelope::ThreadedEnvelopes,
+    cached: bool,
+}
 
 impl ThreadedEnvelopes {
+    /// Whether the envelopes come from the envelopes cache instead
+    /// of the backend, see [`Envelopes::is_cached`].
+    pub fn is_cached(&self) -> bool {
+        self.cached
+    }
+
+    pub(crate) fn with_cached(mut self, cached: bool) -> Self {
+        self.cached = cached;
+        self
+    }
+
     pub fn try_from_backend(
         id_mapper: &IdMapper,
         envelopes: email::envelope::ThreadedEnvelopes,
     ) -> Result<ThreadedEnvelopes> {
-        let prev_edges = envelopes
+        let ids = Self::backend_ids(&envelopes);
+        let aliases = id_mapper.get_or_create_aliases(&ids)?;
+        let aliases = ids.into_iter().zip(aliases).collect();
+        Ok(Self::from_backend(envelopes, aliases))
+    }
+
+    /// Same as [`ThreadedEnvelopes::try_from_backend`], without
+    /// blocking the executor while resolving aliases, see
+    /// [`AsyncIdMapper`].
+    pub async fn try_from_backend_async(
+        id_mapper: &IdMapper,
+        envelopes: email::envelope::ThreadedEnvelopes,
+    ) -> Result<ThreadedEnvelopes> {
+        let ids = Self::backend_ids(&envelopes);
+        let aliases = id_mapper.get_or_create_aliases_async(ids.clone()).await?;
+        let aliases = ids.into_iter().zip(aliases).collect();
+        Ok(Self::from_backend(envelopes, aliases))
+    }
+
+    /// Get the ids of the envelopes and of the edges of the graph,
+    /// which include the id of the root of threads.
+    fn backend_ids(envelopes: &email::envelope::ThreadedEnvelopes) -> Vec<String> {
+        envelopes
             .graph()
             .all_edges()
-            .map(|(a, b, w)| {
-                let a = id_mapper.get_or_create_alias(&a.id)?;
-                let b = id_mapper.get_or_create_alias(&b.id)?;
-                Ok((a, b, *w))
-            })
-            .collect::<Result<Vec<_>>>()?;
+            .flat_map(|(a, b, _)| [a.id, b.id])
+            .chain(
+                envelopes
+                    .map()
+                    .values()
+                    .map(|envelope| envelope.id.as_str()),
+            )
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+
+    fn from_backend(
+        envelopes: email::envelope::ThreadedEnvelopes,
+        aliases: HashMap<String, String>,
+    ) -> ThreadedEnvelopes {
+        let prev_edges: Vec<_> = envelopes
+            .graph()
+            .all_edges()
+            .map(|(a, b, w)| (aliases[a.id].clone(), aliases[b.id].clone(), *w))
+            .collect();
 
         let envelopes = envelopes
             .map()
             .iter()
             .map(|(_, envelope)| {
-                let id = id_mapper.get_or_create_alias(&envelope.id)?;
+                let id = aliases[envelope.id.as_str()].clone();
                 let envelope = email::envelope::Envelope {
                     id: id.clone(),
                     message_id: envelope.message_id.clone(),
@@ -1389,9 +3179,9 @@ impl ThreadedEnvelopes {
                     has_attachment: envelope.has_attachment,
                 };
 
-                Ok((id, envelope))
+                (id, envelope)
             })
-            .collect::<Result<HashMap<_, _>>>()?;
+            .collect::<HashMap<_, _>>();
 
         let envelopes = email::envelope::ThreadedEnvelopes::build(envelopes, move |envelopes| {
             let mut graph = DiGraphMap::<ThreadedEnvelope, u8>::new();
@@ -1418,7 +3208,10 @@ impl ThreadedEnvelopes {
             graph
         });
 
-        Ok(ThreadedEnvelopes(envelopes))
+        ThreadedEnvelopes {
+            envelopes,
+            cached: false,
+        }
     }
 }
 
@@ -1426,77 +3219,274 @@ impl Deref for ThreadedEnvelopes {
     type Target = email::envelope::ThreadedEnvelopes;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.envelopes
     }
 }
 
 pub struct EnvelopesTree {
     config: Arc<AccountConfig>,
     envelopes: ThreadedEnvelopes,
+    tree_config: ThreadEnvelopesTreeConfig,
+    theme: ThemeConfig,
+    width: Option<u16>,
+    max_depth: Option<usize>,
+    order: ThreadsOrder,
 }
 
 impl EnvelopesTree {
-    pub fn new(config: Arc<AccountConfig>, envelopes: ThreadedEnvelopes) -> Self {
-        Self { config, envelopes }
+    pub fn new(
+        config: Arc<AccountConfig>,
+        envelopes: ThreadedEnvelopes,
+        tree_config: Option<ThreadEnvelopesTreeConfig>,
+    ) -> Self {
+        Self {
+            config,
+            envelopes,
+            tree_config: tree_config.unwrap_or_default(),
+            theme: Default::default(),
+            width: None,
+            max_depth: None,
+            order: Default::default(),
+        }
+    }
+
+    pub fn with_some_order(mut self, order: Option<ThreadsOrder>) -> Self {
+        self.order = order.unwrap_or_default();
+        self
+    }
+
+    /// Collapse replies deeper than the given depth into a single
+    /// "… N more" node.
+    pub fn with_some_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the width used to align dates, which defaults to the
+    /// terminal width, or to [`wrap::DEFAULT_WIDTH`] when the
+    /// terminal width cannot be detected.
+    pub fn with_some_width(mut self, width: Option<u16>) -> Self {
+        self.width = width;
+        self
+    }
+
+    fn width(&self) -> usize {
+        match self.width {
+            Some(width) => width as usize,
+            None => wrap::width(),
+        }
+    }
+
+    pub fn with_some_theme(mut self, theme: Option<ThemeConfig>) -> Self {
+        self.theme = theme.unwrap_or_default();
+        self
+    }
+
+    /// Flatten the tree into a table, one row per envelope.
+    ///
+    /// Rows follow the tree order, and the thread structure is shown
+    /// in the [`EnvelopesTableColumn::Thread`] column. The maximum
+    /// depth is not applied, so that every envelope gets its row.
+    pub fn to_table(&self) -> EnvelopesTable {
+        let root = ThreadedEnvelope {
+            id: "0",
+            message_id: "0",
+            from: "",
+            subject: "",
+            date: Default::default(),
+        };
+
+        let index = EnvelopesTreeIndex::new(&self.envelopes, self.order);
+        let mut envelopes = Vec::new();
+
+        for child in index.children(root, 0) {
+            self.flatten(
+                &index,
+                &mut envelopes,
+                *child,
+                1,
+                String::new(),
+                String::new(),
+            );
+        }
+
+        let mut table = EnvelopesTable::from(Envelopes {
+            envelopes,
+            cached: false,
+        });
+        table.threaded = true;
+        table
     }
 
-    pub fn fmt(
+    fn flatten<'a>(
+        &self,
+        index: &EnvelopesTreeIndex<'a>,
+        envelopes: &mut Vec<Envelope>,
+        parent: ThreadedEnvelope<'a>,
+        weight: u8,
+        pad: String,
+        prefix: String,
+    ) {
+        if let Some(envelope) = self.envelopes.envelopes.map().get(parent.id) {
+            let mut envelope = Envelope::from_backend(&self.config, parent.id.to_owned(), envelope);
+            envelope.thread_prefix = prefix;
+            envelopes.push(envelope);
+        }
+
+        let connectors = self.tree_config.connectors();
+        let children = index.children(parent, weight);
+        let children_count = children.len();
+
+        for (i, child) in children.iter().enumerate() {
+            let is_last = children_count == i + 1;
+            let prefix = format!("{pad}{}", connectors.branch(is_last));
+            let pad = format!("{pad}{}", connectors.pad(is_last));
+            self.flatten(index, envelopes, *child, weight + 1, pad, prefix);
+        }
+    }
+
+    /// Count all the descendants of the given envelope.
+    fn count_descendants<'a>(
+        &self,
+        index: &EnvelopesTreeIndex<'a>,
+        parent: ThreadedEnvelope<'a>,
+        weight: u8,
+    ) -> usize {
+        index
+            .children(parent, weight)
+            .iter()
+            .map(|child| 1 + self.count_descendants(index, *child, weight + 1))
+            .sum()
+    }
+
+    /// Count the unseen and the total envelopes of the given thread,
+    /// including the envelope itself.
+    fn count_unseen<'a>(
+        &self,
+        index: &EnvelopesTreeIndex<'a>,
+        parent: ThreadedEnvelope<'a>,
+        weight: u8,
+    ) -> (usize, usize) {
+        let unseen = self
+            .envelopes
+            .envelopes
+            .map()
+            .get(parent.id)
+            .is_some_and(|envelope| !envelope.flags.contains(&email::flag::Flag::Seen));
+
+        index
+            .children(parent, weight)
+            .iter()
+            .map(|child| self.count_unseen(index, *child, weight + 1))
+            .fold((unseen as usize, 1), |(unseen, total), (u, t)| {
+                (unseen + u, total + t)
+            })
+    }
+
+    fn to_node<'a>(
+        &'a self,
+        index: &EnvelopesTreeIndex<'a>,
+        envelope: ThreadedEnvelope<'a>,
+        weight: u8,
+    ) -> EnvelopesTreeNode<'a> {
+        let children = index
+            .children(envelope, weight)
+            .iter()
+            .map(|child| self.to_node(index, *child, weight + 1))
+            .collect();
+
+        EnvelopesTreeNode {
+            envelope: EnvelopesTreeNodeEnvelope {
+                id: envelope.id,
+                message_id: envelope.message_id,
+                subject: envelope.subject,
+                from: envelope.from,
+                date: envelope.format_date(&self.config),
+            },
+            children,
+        }
+    }
+
+    fn fmt_thread<'a>(
+        &self,
         f: &mut fmt::Formatter,
-        config: &AccountConfig,
-        graph: &DiGraphMap<ThreadedEnvelope<'_>, u8>,
-        parent: ThreadedEnvelope<'_>,
+        tree_config: &ThreadEnvelopesTreeConfig,
+        index: &EnvelopesTreeIndex<'a>,
+        parent: ThreadedEnvelope<'a>,
         pad: String,
         weight: u8,
     ) -> fmt::Result {
-        let edges = graph
-            .all_edges()
-            .filter_map(|(a, b, w)| {
-                if a == parent && *w == weight {
-                    Some(b)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+        let edges = index.children(parent, weight);
 
         if parent.id == "0" {
             f.write_str("root")?;
         } else {
-            write!(f, "{}{}", parent.id.red(), ") ".dark_grey())?;
+            // the line starts with the tree branches, which have the
+            // same width as the pad
+            let mut line_width = pad.width();
+
+            let connector_color = tree_config.connector_color();
+
+            let id = parent.id.with(tree_config.id_color());
+            write!(f, "{id}{}", ") ".with(connector_color))?;
+            line_width += parent.id.width() + 2;
 
             if !parent.subject.is_empty() {
-                write!(f, "{} ", parent.subject.green())?;
+                let subject = parent.subject.with(tree_config.subject_color());
+                write!(f, "{subject} ")?;
+                line_width += parent.subject.width() + 1;
             }
 
             if !parent.from.is_empty() {
-                let left = "<".dark_grey();
-                let right = ">".dark_grey();
-                write!(f, "{left}{}{right}", parent.from.blue())?;
+                let left = "<".with(connector_color);
+                let right = ">".with(connector_color);
+                let from = parent.from.with(tree_config.sender_color());
+                write!(f, "{left}{from}{right}")?;
+                line_width += parent.from.width() + 2;
+            }
+
+            // thread roots are the children of the virtual root
+            if weight == 1 && tree_config.show_counts() {
+                let (unseen, total) = self.count_unseen(index, parent, weight);
+                let counts = format!(" ({unseen} new / {total})");
+                line_width += counts.width();
+                write!(f, "{}", counts.with(connector_color))?;
             }
 
-            let date = parent.format_date(config);
-            let cursor_date_begin_col = terminal::size().unwrap().0 - date.len() as u16;
+            let date = parent.format_date(&self.config);
+            let dots_width = self
+                .width()
+                .saturating_sub(date.width())
+                .saturating_sub(line_width + 2);
 
-            let dots =
-                "·".repeat((cursor_date_begin_col - cursor::position().unwrap().0 - 2) as usize);
-            write!(f, " {} {}", dots.dark_grey(), date.dark_yellow())?;
+            let dots = tree_config.connectors().dot().repeat(dots_width);
+            let dots = dots.with(connector_color);
+            write!(f, " {dots} {}", date.with(tree_config.date_color()))?;
         }
 
         writeln!(f)?;
 
+        let connectors = tree_config.connectors();
+
+        // children of the parent are located at depth weight + 1
+        let is_collapsed = self
+            .max_depth
+            .is_some_and(|max_depth| weight as usize >= max_depth);
+
+        if is_collapsed && !edges.is_empty() {
+            let count = self.count_descendants(index, parent, weight);
+            let more = format!("… {count} more").with(tree_config.connector_color());
+            writeln!(f, "{pad}{}{more}", connectors.branch(true))?;
+            return Ok(());
+        }
+
         let edges_count = edges.len();
-        for (i, b) in edges.into_iter().enumerate() {
+        for (i, b) in edges.iter().enumerate() {
             let is_last = edges_count == i + 1;
-            let (x, y) = if is_last {
-                (' ', '└')
-            } else {
-                ('│', '├')
-            };
-
-            write!(f, "{pad}{y}─ ")?;
+            write!(f, "{pad}{}", connectors.branch(is_last))?;
 
-            let pad = format!("{pad}{x}  ");
-            Self::fmt(f, config, graph, b, pad, weight + 1)?;
+            let pad = format!("{pad}{}", connectors.pad(is_last));
+            self.fmt_thread(f, tree_config, index, *b, pad, weight + 1)?;
         }
 
         Ok(())
@@ -1505,10 +3495,15 @@ impl EnvelopesTree {
 
 impl fmt::Display for EnvelopesTree {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        EnvelopesTree::fmt(
+        color::init();
+
+        let tree_config = self.tree_config.inherit(&self.theme);
+        let index = EnvelopesTreeIndex::new(&self.envelopes, self.order);
+
+        self.fmt_thread(
             f,
-            &self.config,
-            self.envelopes.0.graph(),
+            &tree_config,
+            &index,
             ThreadedEnvelope {
                 id: "0",
                 message_id: "0",
@@ -1522,15 +3517,132 @@ impl fmt::Display for EnvelopesTree {
     }
 }
 
+/// Children of the envelopes of the threads graph, sorted by the
+/// configured order.
+///
+/// The index is built once per render, so that walking the tree
+/// does not scan all the edges of the graph for every envelope.
+struct EnvelopesTreeIndex<'a> {
+    children: HashMap<(&'a str, u8), Vec<ThreadedEnvelope<'a>>>,
+}
+
+impl<'a> EnvelopesTreeIndex<'a> {
+    fn new(envelopes: &'a ThreadedEnvelopes, order: ThreadsOrder) -> Self {
+        let mut children: HashMap<_, Vec<_>> = HashMap::new();
+
+        for (a, b, w) in envelopes.envelopes.graph().all_edges() {
+            children.entry((a.id, *w)).or_default().push(b);
+        }
+
+        match order {
+            ThreadsOrder::OldestFirst => {
+                for siblings in children.values_mut() {
+                    siblings.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(b.id)));
+                }
+            }
+            ThreadsOrder::NewestFirst => {
+                for siblings in children.values_mut() {
+                    siblings.sort_by(|a, b| b.date.cmp(&a.date).then(a.id.cmp(b.id)));
+                }
+            }
+            ThreadsOrder::LastActivity => {
+                let mut activities = HashMap::new();
+
+                for (&(_, weight), siblings) in &children {
+                    for child in siblings {
+                        Self::last_activity(&children, &mut activities, *child, weight + 1);
+                    }
+                }
+
+                for (&(_, weight), siblings) in children.iter_mut() {
+                    siblings.sort_by_key(|child| {
+                        let activity = activities[&(child.id, weight + 1)];
+                        (std::cmp::Reverse(activity), child.id)
+                    });
+                }
+            }
+        }
+
+        Self { children }
+    }
+
+    /// Return the children of the given envelope, located at the
+    /// given depth of the tree.
+    fn children(&self, parent: ThreadedEnvelope<'a>, weight: u8) -> &[ThreadedEnvelope<'a>] {
+        self.children
+            .get(&(parent.id, weight))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return the date of the most recent envelope of the given
+    /// thread, including the envelope itself.
+    ///
+    /// Dates are memoized, so that each envelope is visited once.
+    fn last_activity(
+        children: &HashMap<(&'a str, u8), Vec<ThreadedEnvelope<'a>>>,
+        activities: &mut HashMap<(&'a str, u8), DateTime<FixedOffset>>,
+        parent: ThreadedEnvelope<'a>,
+        weight: u8,
+    ) -> DateTime<FixedOffset> {
+        if let Some(activity) = activities.get(&(parent.id, weight)) {
+            return *activity;
+        }
+
+        let activity = children
+            .get(&(parent.id, weight))
+            .into_iter()
+            .flatten()
+            .map(|child| Self::last_activity(children, activities, *child, weight + 1))
+            .fold(parent.date, Ord::max);
+
+        activities.insert((parent.id, weight), activity);
+        activity
+    }
+}
+
+/// Serialize threads as nested envelopes:
+///
+/// ```json
+/// [{"envelope": {"id": "1", …}, "children": [{"envelope": …, "children": []}]}]
+/// ```
+///
+/// The underlying graph remains available through [`Deref`].
 impl Serialize for EnvelopesTree {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.envelopes.0.serialize(serializer)
+        let root = ThreadedEnvelope {
+            id: "0",
+            message_id: "0",
+            from: "",
+            subject: "",
+            date: Default::default(),
+        };
+
+        let index = EnvelopesTreeIndex::new(&self.envelopes, self.order);
+        self.to_node(&index, root, 0).children.serialize(serializer)
     }
 }
 
+/// Represents a node of the serialized threads tree.
+#[derive(Clone, Debug, Serialize)]
+pub struct EnvelopesTreeNode<'a> {
+    pub envelope: EnvelopesTreeNodeEnvelope<'a>,
+    pub children: Vec<EnvelopesTreeNode<'a>>,
+}
+
+/// Represents the envelope of a serialized threads tree node.
+#[derive(Clone, Debug, Serialize)]
+pub struct EnvelopesTreeNodeEnvelope<'a> {
+    pub id: &'a str,
+    pub message_id: &'a str,
+    pub subject: &'a str,
+    pub from: &'a str,
+    pub date: String,
+}
+
 impl Deref for EnvelopesTree {
     type Target = ThreadedEnvelopes;
 
@@ -1550,6 +3662,19 @@ pub enum Flag {
     Custom(String),
 }
 
+impl From<&str> for Flag {
+    fn from(flag: &str) -> Self {
+        match flag.trim() {
+            flag if flag.eq_ignore_ascii_case("seen") => Flag::Seen,
+            flag if flag.eq_ignore_ascii_case("answered") => Flag::Answered,
+            flag if flag.eq_ignore_ascii_case("flagged") => Flag::Flagged,
+            flag if flag.eq_ignore_ascii_case("deleted") => Flag::Deleted,
+            flag if flag.eq_ignore_ascii_case("draft") => Flag::Draft,
+            flag => Flag::Custom(flag.to_owned()),
+        }
+    }
+}
+
 impl From<&email::flag::Flag> for Flag {
     fn from(flag: &email::flag::Flag) -> Self {
         use email::flag::Flag::*;
@@ -1580,3 +3705,262 @@ impl From<email::flag::Flags> for Flags {
         Flags(flags.iter().map(Flag::from).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(id: &str, sender: &str) -> Envelope {
+        Envelope {
+            id: id.to_owned(),
+            from: Mailbox {
+                name: None,
+                addr: sender.to_owned(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn group_envelopes_by_sender() {
+        let envelopes = [
+            envelope("1", "alice@localhost"),
+            envelope("2", "bob@localhost"),
+            envelope("3", "alice@localhost"),
+        ];
+
+        let groups: Vec<_> = group_envelopes(&envelopes, &EnvelopesGroupBy::Sender)
+            .into_iter()
+            .map(|(label, envelopes)| {
+                let ids: Vec<_> = envelopes.iter().map(|e| e.id.as_str()).collect();
+                (label, ids)
+            })
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![
+                (String::from("alice@localhost"), vec!["1", "3"]),
+                (String::from("bob@localhost"), vec!["2"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn color_borders_of_table() {
+        let border = |s: &str| s.with(Color::Red).to_string();
+
+        let table = [
+            "┌────┬───────┐",
+            "│ ID │ \x1b[1mSUBJ\x1b[0m  │",
+            "╞════╪═══════╡",
+            "│ 1  │ a │ b │",
+            "└────┴───────┘",
+        ]
+        .join("\n");
+
+        let expected = [
+            border("┌────┬───────┐"),
+            format!(
+                "{} ID {} \x1b[1mSUBJ\x1b[0m  {}",
+                border("│"),
+                border("│"),
+                border("│")
+            ),
+            border("╞════╪═══════╡"),
+            format!("{} 1  {} a │ b {}", border("│"), border("│"), border("│")),
+            border("└────┴───────┘"),
+        ]
+        .join("\n");
+
+        assert_eq!(color_borders(&table, Color::Red), expected);
+    }
+
+    #[test]
+    fn dump_applies_defaults_to_accounts() {
+        use crate::terminal::config::{ConfigFormat, TomlConfig};
+
+        let mut config = HimalayaTomlConfig {
+            defaults: Some(HimalayaTomlDefaultsConfig {
+                signature: Some(String::from("Regards")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let account = HimalayaTomlAccountConfig {
+            email: String::from("alice@localhost"),
+            ..Default::default()
+        };
+
+        config.accounts.insert(String::from("alice"), account);
+
+        let dump: toml::Value = config.dump(ConfigFormat::Toml).unwrap().parse().unwrap();
+
+        assert_eq!(dump.get("defaults"), None);
+        assert_eq!(
+            dump["accounts"]["alice"]["signature"].as_str(),
+            Some("Regards")
+        );
+    }
+
+    /// Build threads from the given envelopes `(id, subject, hour,
+    /// seen)` and edges `(parent, child, weight)`, where `0` is the
+    /// virtual root.
+    fn threads(
+        envelopes: &[(&str, &str, u32, bool)],
+        edges: &'static [(&'static str, &'static str, u8)],
+    ) -> ThreadedEnvelopes {
+        let envelopes = envelopes
+            .iter()
+            .map(|(id, subject, hour, seen)| {
+                let date = format!("2024-01-01T{hour:02}:00:00+00:00");
+                let flags = if *seen {
+                    email::flag::Flags::from_iter([email::flag::Flag::Seen])
+                } else {
+                    Default::default()
+                };
+
+                let envelope = email::envelope::Envelope {
+                    id: id.to_string(),
+                    message_id: format!("<{id}@localhost>"),
+                    subject: subject.to_string(),
+                    date: DateTime::parse_from_rfc3339(&date).unwrap(),
+                    flags,
+                    ..Default::default()
+                };
+
+                (id.to_string(), envelope)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let envelopes = email::envelope::ThreadedEnvelopes::build(envelopes, move |envelopes| {
+            let mut graph = DiGraphMap::<ThreadedEnvelope, u8>::new();
+
+            for (a, b, w) in edges {
+                let a = match envelopes.get(*a) {
+                    Some(a) => a.as_threaded(),
+                    None => ThreadedEnvelope {
+                        id: "0",
+                        message_id: "0",
+                        subject: "",
+                        from: "",
+                        date: Default::default(),
+                    },
+                };
+
+                graph.add_edge(a, envelopes[*b].as_threaded(), *w);
+            }
+
+            graph
+        });
+
+        ThreadedEnvelopes {
+            envelopes,
+            cached: false,
+        }
+    }
+
+    /// Render the given tree at a fixed width, without colors.
+    fn render(tree: EnvelopesTree) -> Vec<String> {
+        let rendered = tree.with_some_width(Some(60)).to_string();
+        let mut output = String::new();
+        let mut chars = rendered.chars();
+
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                // skip SGR sequences, like `\x1b[31m`
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                output.push(c);
+            }
+        }
+
+        output.lines().map(ToOwned::to_owned).collect()
+    }
+
+    fn tree(envelopes: ThreadedEnvelopes) -> EnvelopesTree {
+        EnvelopesTree::new(Arc::new(AccountConfig::default()), envelopes, None)
+    }
+
+    #[test]
+    fn display_tree_aligns_dates_of_wide_subjects() {
+        let envelopes = threads(
+            &[
+                ("1", "Hello", 10, true),
+                ("2", "日本語のメール", 11, true),
+                ("3", "Party 🎉🎉", 12, true),
+            ],
+            &[("0", "1", 0), ("1", "2", 1), ("1", "3", 1)],
+        );
+
+        let lines = render(tree(envelopes));
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "root");
+        assert!(lines[1].starts_with("└─ 1) Hello "));
+        assert!(lines[2].starts_with("   ├─ 2) 日本語のメール "));
+        assert!(lines[3].starts_with("   └─ 3) Party 🎉🎉 "));
+
+        for line in &lines[1..] {
+            assert_eq!(line.width(), 60, "{line:?} is not aligned");
+        }
+    }
+
+    #[test]
+    fn display_tree_collapses_deep_replies() {
+        let envelopes = threads(
+            &[
+                ("1", "Hello", 10, true),
+                ("2", "Re: Hello", 11, true),
+                ("3", "Re: Re: Hello", 12, true),
+                ("4", "Re: Re: Re: Hello", 13, true),
+            ],
+            &[("0", "1", 0), ("1", "2", 1), ("2", "3", 2), ("3", "4", 3)],
+        );
+
+        let lines = render(tree(envelopes).with_some_max_depth(Some(1)));
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("└─ 1) Hello "));
+        assert_eq!(lines[2], "   └─ … 3 more");
+    }
+
+    #[test]
+    fn display_tree_counts_unseen_envelopes_of_threads() {
+        let envelopes = threads(
+            &[
+                ("1", "Hello", 10, true),
+                ("2", "Re: Hello", 11, false),
+                ("3", "Re: Hello", 12, true),
+                ("4", "Bye", 13, false),
+            ],
+            &[("0", "1", 0), ("1", "2", 1), ("1", "3", 1), ("0", "4", 0)],
+        );
+
+        let tree_config = ThreadEnvelopesTreeConfig {
+            show_counts: Some(true),
+            ..Default::default()
+        };
+
+        let tree = EnvelopesTree::new(
+            Arc::new(AccountConfig::default()),
+            envelopes,
+            Some(tree_config),
+        );
+
+        let lines = render(tree);
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].starts_with("├─ 1) Hello "));
+        assert!(lines[1].contains(" (1 new / 3) ·"));
+        assert!(lines[2].starts_with("│  ├─ 2) Re: Hello "));
+        assert!(lines[3].starts_with("│  └─ 3) Re: Hello "));
+        assert!(lines[4].starts_with("└─ 4) Bye "));
+        assert!(lines[4].contains(" (1 new / 1) ·"));
+
+        for line in &lines[1..] {
+            assert_eq!(line.width(), 60, "{line:?} is not aligned");
+        }
+    }
+}
@@ -12,13 +12,17 @@ use mml::MmlCompilerBuilder;
 use process::Command;
 use tracing::debug;
 
-use crate::terminal::cli::printer::Printer;
+use crate::terminal::{cli::printer::Printer, print};
 
 use super::{
     backend::Backend,
     choice::{self, PostEditChoice, PreEditChoice},
 };
 
+fn is_prompt_cancelled(err: &color_eyre::Report) -> bool {
+    matches!(err.downcast_ref(), Some(crate::Error::PromptCancelled))
+}
+
 pub async fn open_with_tpl(tpl: Template) -> Result<Template> {
     let path = local_draft_path();
 
@@ -48,7 +52,7 @@ pub async fn open_with_local_draft() -> Result<Template> {
 }
 
 #[allow(unused)]
-pub async fn edit_tpl_with_editor<P: Printer>(
+pub async fn edit_tpl_with_editor<P: Printer + ?Sized>(
     config: Arc<AccountConfig>,
     printer: &mut P,
     backend: &Backend,
@@ -69,8 +73,9 @@ pub async fn edit_tpl_with_editor<P: Printer>(
                     }
                     PreEditChoice::Quit => return Ok(()),
                 },
+                Err(err) if is_prompt_cancelled(&err) => return Ok(()),
                 Err(err) => {
-                    println!("{}", err);
+                    print::error(err.to_string());
                     continue;
                 }
             }
@@ -82,7 +87,7 @@ pub async fn edit_tpl_with_editor<P: Printer>(
     loop {
         match choice::post_edit() {
             Ok(PostEditChoice::Send) => {
-                printer.log("Sending message…\n")?;
+                printer.log(&"Sending message…\n")?;
 
                 #[allow(unused_mut)]
                 let mut compiler = MmlCompilerBuilder::new();
@@ -95,7 +100,7 @@ pub async fn edit_tpl_with_editor<P: Printer>(
                 backend.send_message_then_save_copy(&email).await?;
 
                 remove_local_draft()?;
-                printer.out("Message successfully sent!\n")?;
+                printer.out(&"Message successfully sent!\n")?;
                 break;
             }
             Ok(PostEditChoice::Edit) => {
@@ -103,7 +108,7 @@ pub async fn edit_tpl_with_editor<P: Printer>(
                 continue;
             }
             Ok(PostEditChoice::LocalDraft) => {
-                printer.out("Message successfully saved locally!\n")?;
+                printer.out(&"Message successfully saved locally!\n")?;
                 break;
             }
             Ok(PostEditChoice::RemoteDraft) => {
@@ -123,15 +128,19 @@ pub async fn edit_tpl_with_editor<P: Printer>(
                     )
                     .await?;
                 remove_local_draft()?;
-                printer.out("Message successfully saved to drafts!\n")?;
+                printer.out(&"Message successfully saved to drafts!\n")?;
                 break;
             }
             Ok(PostEditChoice::Discard) => {
                 remove_local_draft()?;
                 break;
             }
+            Err(err) if is_prompt_cancelled(&err) => {
+                printer.out(&"Message kept as local draft.\n")?;
+                break;
+            }
             Err(err) => {
-                printer.out(format!("{err}\n"));
+                printer.out(&format!("{err}\n"));
                 continue;
             }
         }
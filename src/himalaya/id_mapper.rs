@@ -1,27 +1,170 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
+use async_trait::async_trait;
 use color_eyre::{eyre::eyre, eyre::Context, Result};
 use dirs::data_dir;
 use email::account::config::AccountConfig;
-use tracing::debug;
+#[cfg(feature = "sled")]
+use sled::transaction::TransactionError;
+#[cfg(feature = "sled")]
+use tokio::task::spawn_blocking;
+use tracing::{debug, warn};
 
-#[derive(Debug, Default)]
+/// Maximum distance between the alias generator and the greatest
+/// restored alias, see [`IdMapper::restore_aliases`].
+///
+/// The generator of the database can only be advanced one alias at a
+/// time, so restoring far greater aliases would take too long.
+pub const MAX_RESTORED_ALIAS_GAP: u64 = 100_000;
+
+#[derive(Clone, Debug, Default)]
 pub enum IdMapper {
     #[default]
     Dummy,
+    /// Aliases kept in memory, lost when the mapper is dropped.
+    ///
+    /// Useful for one-shot invocations, like scripts and tests, where
+    /// aliases only need to be stable for the lifetime of the process.
+    Memory(Arc<Mutex<MemoryIdMapper>>),
     #[cfg(feature = "sled")]
     Mapper(sled::Db),
 }
 
+/// Aliases of the in-memory mapper, see [`IdMapper::Memory`].
+#[derive(Debug, Default)]
+pub struct MemoryIdMapper {
+    aliases: HashMap<String, String>,
+    last_alias: u64,
+}
+
+impl MemoryIdMapper {
+    fn create_alias(&mut self, id: &str) -> String {
+        self.last_alias += 1;
+        let alias = self.last_alias.to_string();
+        self.aliases.insert(id.to_owned(), alias.clone());
+        alias
+    }
+}
+
+/// Check that the given restored alias is not too far beyond the
+/// given generated one, see [`MAX_RESTORED_ALIAS_GAP`].
+fn check_restored_alias_gap(generated_alias: u64, restored_alias: u64) -> Result<()> {
+    if restored_alias.saturating_sub(generated_alias) > MAX_RESTORED_ALIAS_GAP {
+        return Err(eyre!(
+            "cannot restore alias {restored_alias}: too far beyond the next alias {generated_alias}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Index of the folders of an account having a database in a given
+/// directory.
+///
+/// Databases are named after a digest of the account name and the
+/// folder, so they cannot be listed from the account name alone. The
+/// index records the folder of every opened database, so that all
+/// the databases of an account can be removed or moved, see
+/// [`IdMapper::remove`]. Databases opened before the index existed
+/// can only be found from their folder.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoldersIndex(PathBuf);
+
+impl FoldersIndex {
+    pub fn new(db_dir: &Path, account_name: &str) -> Self {
+        let digest = md5::compute(account_name);
+        Self(db_dir.join(format!("{digest:x}.folders")))
+    }
+
+    /// Read the indexed folders.
+    pub fn folders(&self) -> Result<BTreeSet<String>> {
+        match fs::read_to_string(&self.0) {
+            Ok(content) => Ok(content.lines().map(ToOwned::to_owned).collect()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeSet::new()),
+            Err(err) => {
+                Err(err).with_context(|| format!("cannot read folders index at {:?}", self.0))
+            }
+        }
+    }
+
+    /// Read the indexed folders, together with the given ones.
+    pub fn folders_with<F: AsRef<str>>(
+        &self,
+        folders: impl IntoIterator<Item = F>,
+    ) -> Result<BTreeSet<String>> {
+        let mut indexed_folders = self.folders()?;
+        indexed_folders.extend(folders.into_iter().map(|f| f.as_ref().to_owned()));
+        Ok(indexed_folders)
+    }
+
+    /// Add the given folder to the index, unless already indexed.
+    ///
+    /// Folders are stored one per line, so folders containing line
+    /// breaks are not indexed.
+    pub fn insert(&self, folder: &str) -> Result<()> {
+        if folder.contains(['\n', '\r']) || self.folders()?.contains(folder) {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.0.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create folders index directory at {dir:?}"))?;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.0)
+            .with_context(|| format!("cannot open folders index at {:?}", self.0))?;
+
+        writeln!(file, "{folder}")
+            .with_context(|| format!("cannot write folders index at {:?}", self.0))
+    }
+
+    /// Remove the index.
+    pub fn remove(&self) -> Result<()> {
+        match fs::remove_file(&self.0) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => {
+                Err(err).with_context(|| format!("cannot remove folders index at {:?}", self.0))
+            }
+        }
+    }
+}
+
 impl IdMapper {
+    pub fn new_memory() -> Self {
+        Self::Memory(Default::default())
+    }
+
+    fn lock_memory(
+        mapper: &Mutex<MemoryIdMapper>,
+    ) -> Result<std::sync::MutexGuard<'_, MemoryIdMapper>> {
+        mapper
+            .lock()
+            .map_err(|_| eyre!("cannot lock in-memory id mapper"))
+    }
+
+    /// Open the database of the given account folder, in the given
+    /// directory or in the default one, see [`IdMapper::db_path`].
+    ///
+    /// The folder is recorded in the index of the account, see
+    /// [`FoldersIndex`].
     #[cfg(feature = "sled")]
-    pub fn new(account_config: &AccountConfig, folder: &str) -> Result<Self> {
-        let digest = md5::compute(account_config.name.clone() + folder);
-        let db_path = data_dir()
-            .ok_or(eyre!("cannot get XDG data directory"))?
-            .join("himalaya")
-            .join(".id-mappers")
-            .join(format!("{digest:x}"));
+    pub fn new(
+        db_dir: Option<&Path>,
+        account_config: &AccountConfig,
+        folder: &str,
+    ) -> Result<Self> {
+        let db_dir = Self::db_dir(db_dir)?;
+        let db_path = Self::db_path(Some(&db_dir), &account_config.name, folder)?;
 
         let conn = sled::Config::new()
             .path(&db_path)
@@ -29,9 +172,202 @@ impl IdMapper {
             .open()
             .with_context(|| format!("cannot open id mapper database at {db_path:?}"))?;
 
+        let index = FoldersIndex::new(&db_dir, &account_config.name);
+        if let Err(err) = index.insert(folder) {
+            warn!("cannot index id mapper database of folder {folder}: {err:#}");
+        }
+
         Ok(Self::Mapper(conn))
     }
 
+    /// Get the directory of the databases.
+    ///
+    /// Databases live in the given directory, see the `id-map-dir`
+    /// account option, or in the XDG data directory by default.
+    #[cfg(feature = "sled")]
+    pub fn db_dir(db_dir: Option<&Path>) -> Result<PathBuf> {
+        match db_dir {
+            Some(db_dir) => Ok(db_dir.to_owned()),
+            None => Ok(data_dir()
+                .ok_or(eyre!("cannot get XDG data directory"))?
+                .join("himalaya")
+                .join(".id-mappers")),
+        }
+    }
+
+    /// Get the path of the database of the given account folder, see
+    /// [`IdMapper::db_dir`].
+    #[cfg(feature = "sled")]
+    pub fn db_path(db_dir: Option<&Path>, account_name: &str, folder: &str) -> Result<PathBuf> {
+        let digest = md5::compute(account_name.to_owned() + folder);
+        Ok(Self::db_dir(db_dir)?.join(format!("{digest:x}")))
+    }
+
+    /// Remove the databases of the given account folders and of the
+    /// folders of the account index, see [`FoldersIndex`].
+    ///
+    /// Returns the number of removed databases.
+    #[cfg(feature = "sled")]
+    pub fn remove<F: AsRef<str>>(
+        db_dir: Option<&Path>,
+        account_name: &str,
+        folders: impl IntoIterator<Item = F>,
+    ) -> Result<usize> {
+        let db_dir = Self::db_dir(db_dir)?;
+        let index = FoldersIndex::new(&db_dir, account_name);
+        let mut removed = 0;
+
+        for folder in index.folders_with(folders)? {
+            let db_path = Self::db_path(Some(&db_dir), account_name, &folder)?;
+
+            if db_path.exists() {
+                debug!("removing id mapper database at {db_path:?}…");
+                fs::remove_dir_all(&db_path)
+                    .with_context(|| format!("cannot remove id mapper database at {db_path:?}"))?;
+                removed += 1;
+            }
+        }
+
+        index.remove()?;
+        Ok(removed)
+    }
+
+    /// Move the databases of the given account folders and of the
+    /// folders of the account index to the new account name, see
+    /// [`IdMapper::remove`].
+    ///
+    /// Returns the number of moved databases.
+    #[cfg(feature = "sled")]
+    pub fn rename<F: AsRef<str>>(
+        db_dir: Option<&Path>,
+        account_name: &str,
+        new_account_name: &str,
+        folders: impl IntoIterator<Item = F>,
+    ) -> Result<usize> {
+        let db_dir = Self::db_dir(db_dir)?;
+        let index = FoldersIndex::new(&db_dir, account_name);
+        let new_index = FoldersIndex::new(&db_dir, new_account_name);
+        let mut moved = 0;
+
+        for folder in index.folders_with(folders)? {
+            let db_path = Self::db_path(Some(&db_dir), account_name, &folder)?;
+
+            if db_path.exists() {
+                let new_db_path = Self::db_path(Some(&db_dir), new_account_name, &folder)?;
+                debug!("moving id mapper database from {db_path:?} to {new_db_path:?}…");
+                fs::rename(&db_path, &new_db_path)
+                    .with_context(|| format!("cannot move id mapper database at {db_path:?}"))?;
+                new_index.insert(&folder)?;
+                moved += 1;
+            }
+        }
+
+        index.remove()?;
+        Ok(moved)
+    }
+
+    /// Get all the aliases, by id.
+    pub fn aliases(&self) -> Result<BTreeMap<String, String>> {
+        match self {
+            Self::Dummy => Ok(BTreeMap::new()),
+            Self::Memory(mapper) => {
+                let aliases = Self::lock_memory(mapper)?
+                    .aliases
+                    .iter()
+                    .map(|(id, alias)| (id.clone(), alias.clone()))
+                    .collect();
+
+                Ok(aliases)
+            }
+            #[cfg(feature = "sled")]
+            Self::Mapper(conn) => conn
+                .iter()
+                .map(|entry| {
+                    let (id, alias) = entry.context("cannot get aliases")?;
+                    let id = String::from_utf8_lossy(id.as_ref()).to_string();
+                    let alias = String::from_utf8_lossy(alias.as_ref()).to_string();
+                    Ok((id, alias))
+                })
+                .collect(),
+        }
+    }
+
+    /// Restore the given aliases, by id, see [`IdMapper::aliases`].
+    ///
+    /// Aliases can only be restored into an empty mapper. Aliases are
+    /// numbers, and the alias generator is moved past the greatest
+    /// restored one, so that new aliases do not collide with restored
+    /// ones. Aliases more than [`MAX_RESTORED_ALIAS_GAP`] beyond the
+    /// generator are rejected. Returns the number of restored
+    /// aliases.
+    pub fn restore_aliases(&self, aliases: BTreeMap<String, String>) -> Result<usize> {
+        let mut restored_aliases = HashSet::with_capacity(aliases.len());
+
+        for alias in aliases.values() {
+            let restored_alias = alias
+                .parse::<u64>()
+                .with_context(|| format!("cannot restore invalid alias {alias}"))?;
+
+            if !restored_aliases.insert(restored_alias) {
+                return Err(eyre!("cannot restore duplicate alias {alias}"));
+            }
+        }
+
+        let last_alias = restored_aliases.into_iter().max();
+
+        match self {
+            Self::Dummy => Ok(0),
+            Self::Memory(mapper) => {
+                let mut mapper = Self::lock_memory(mapper)?;
+
+                if !mapper.aliases.is_empty() {
+                    return Err(eyre!("cannot restore aliases into a non-empty id mapper"));
+                }
+
+                if let Some(last_alias) = last_alias {
+                    check_restored_alias_gap(mapper.last_alias, last_alias)?;
+                    mapper.last_alias = mapper.last_alias.max(last_alias);
+                }
+
+                let count = aliases.len();
+                mapper.aliases.extend(aliases);
+                Ok(count)
+            }
+            #[cfg(feature = "sled")]
+            Self::Mapper(conn) => {
+                if !conn.is_empty() {
+                    return Err(eyre!("cannot restore aliases into a non-empty id mapper"));
+                }
+
+                // the id generator cannot be set, so it is advanced
+                // until it is past the restored aliases
+                if let Some(last_alias) = last_alias {
+                    let mut next_alias = conn
+                        .generate_id()
+                        .context("cannot advance alias generator")?;
+
+                    check_restored_alias_gap(next_alias, last_alias)?;
+
+                    while next_alias < last_alias {
+                        next_alias = conn
+                            .generate_id()
+                            .context("cannot advance alias generator")?;
+                    }
+                }
+
+                let count = aliases.len();
+
+                for (id, alias) in aliases {
+                    debug!("restoring alias {alias} for id {id}");
+                    conn.insert(&id, alias.as_bytes())
+                        .with_context(|| format!("cannot insert alias {alias} for id {id}"))?;
+                }
+
+                Ok(count)
+            }
+        }
+    }
+
     pub fn create_alias<I>(&self, id: I) -> Result<String>
     where
         I: AsRef<str>,
@@ -39,6 +375,7 @@ impl IdMapper {
         let id = id.as_ref();
         match self {
             Self::Dummy => Ok(id.to_owned()),
+            Self::Memory(mapper) => Ok(Self::lock_memory(mapper)?.create_alias(id)),
             #[cfg(feature = "sled")]
             Self::Mapper(conn) => {
                 debug!("creating alias for id {id}…");
@@ -64,6 +401,14 @@ impl IdMapper {
         let id = id.as_ref();
         match self {
             Self::Dummy => Ok(id.to_owned()),
+            Self::Memory(mapper) => {
+                let mut mapper = Self::lock_memory(mapper)?;
+
+                match mapper.aliases.get(id) {
+                    Some(alias) => Ok(alias.clone()),
+                    None => Ok(mapper.create_alias(id)),
+                }
+            }
             #[cfg(feature = "sled")]
             Self::Mapper(conn) => {
                 debug!("getting alias for id {id}…");
@@ -89,6 +434,63 @@ impl IdMapper {
         }
     }
 
+    /// Get the aliases of the given ids, in the same order, creating
+    /// the missing ones.
+    ///
+    /// Unlike [`IdMapper::get_or_create_alias`], all the aliases are
+    /// resolved within a single database transaction.
+    pub fn get_or_create_aliases<I>(&self, ids: &[I]) -> Result<Vec<String>>
+    where
+        I: AsRef<str>,
+    {
+        match self {
+            Self::Dummy => Ok(ids.iter().map(|id| id.as_ref().to_owned()).collect()),
+            Self::Memory(mapper) => {
+                let mut mapper = Self::lock_memory(mapper)?;
+
+                let aliases = ids
+                    .iter()
+                    .map(|id| match mapper.aliases.get(id.as_ref()) {
+                        Some(alias) => alias.clone(),
+                        None => mapper.create_alias(id.as_ref()),
+                    })
+                    .collect();
+
+                Ok(aliases)
+            }
+            #[cfg(feature = "sled")]
+            Self::Mapper(conn) => {
+                debug!("getting or creating aliases for {} ids…", ids.len());
+
+                let aliases: Result<Vec<String>, TransactionError> = conn.transaction(|tx| {
+                    let mut aliases = Vec::with_capacity(ids.len());
+
+                    for id in ids {
+                        let id = id.as_ref();
+
+                        let alias = match tx.get(id)? {
+                            Some(alias) => String::from_utf8_lossy(alias.as_ref()).to_string(),
+                            None => {
+                                let alias = tx.generate_id()?.to_string();
+                                debug!("created alias {alias} for id {id}");
+                                tx.insert(id, alias.as_bytes())?;
+                                alias
+                            }
+                        };
+
+                        aliases.push(alias);
+                    }
+
+                    Ok(aliases)
+                });
+
+                // transactions are never aborted, so only storage
+                // errors can occur
+                aliases.map_err(|err| eyre!("cannot get or create aliases: {err:?}"))
+            }
+        }
+    }
+
     pub fn get_id<A>(&self, alias: A) -> Result<String>
     where
         A: ToString,
@@ -97,6 +499,12 @@ impl IdMapper {
 
         match self {
             Self::Dummy => Ok(alias.to_string()),
+            Self::Memory(mapper) => Self::lock_memory(mapper)?
+                .aliases
+                .iter()
+                .find(|(_, entry_alias)| **entry_alias == alias)
+                .map(|(entry_id, _)| entry_id.clone())
+                .ok_or_else(|| eyre!("cannot get id from alias {alias}")),
             #[cfg(feature = "sled")]
             Self::Mapper(conn) => {
                 debug!("getting id from alias {alias}…");
@@ -125,6 +533,17 @@ impl IdMapper {
 
         match self {
             Self::Dummy => Ok(aliases),
+            Self::Memory(mapper) => {
+                let aliases: HashSet<&str> = aliases.iter().map(|alias| alias.as_str()).collect();
+                let ids = Self::lock_memory(mapper)?
+                    .aliases
+                    .iter()
+                    .filter(|(_, alias)| aliases.contains(alias.as_str()))
+                    .map(|(id, _)| id.clone())
+                    .collect();
+
+                Ok(ids)
+            }
             #[cfg(feature = "sled")]
             Self::Mapper(conn) => {
                 let aliases: HashSet<&str> = aliases.iter().map(|alias| alias.as_str()).collect();
@@ -147,3 +566,128 @@ impl IdMapper {
         }
     }
 }
+
+/// Asynchronous operations of the id mapper.
+///
+/// Database operations are blocking, so they run on the blocking
+/// threads of the runtime instead of stalling the executor, which
+/// matters for large folders.
+#[async_trait]
+pub trait AsyncIdMapper {
+    /// See [`IdMapper::create_alias`].
+    async fn create_alias_async(&self, id: String) -> Result<String>;
+
+    /// See [`IdMapper::get_or_create_aliases`].
+    async fn get_or_create_aliases_async(&self, ids: Vec<String>) -> Result<Vec<String>>;
+
+    /// See [`IdMapper::get_ids`].
+    async fn get_ids_async<A: ToString + Sync>(&self, aliases: &[A]) -> Result<Vec<String>>;
+}
+
+#[async_trait]
+impl AsyncIdMapper for IdMapper {
+    async fn create_alias_async(&self, id: String) -> Result<String> {
+        self.blocking(move |id_mapper| id_mapper.create_alias(id))
+            .await
+    }
+
+    async fn get_or_create_aliases_async(&self, ids: Vec<String>) -> Result<Vec<String>> {
+        self.blocking(move |id_mapper| id_mapper.get_or_create_aliases(&ids))
+            .await
+    }
+
+    async fn get_ids_async<A: ToString + Sync>(&self, aliases: &[A]) -> Result<Vec<String>> {
+        let aliases: Vec<String> = aliases.iter().map(ToString::to_string).collect();
+        self.blocking(move |id_mapper| id_mapper.get_ids(aliases))
+            .await
+    }
+}
+
+impl IdMapper {
+    /// Run the given operation on the blocking threads of the
+    /// runtime, unless it does not reach the database.
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&IdMapper) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        match self {
+            #[cfg(feature = "sled")]
+            Self::Mapper(_) => {
+                let id_mapper = self.clone();
+                spawn_blocking(move || f(&id_mapper))
+                    .await
+                    .context("cannot join id mapper task")?
+            }
+            _ => f(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{FoldersIndex, IdMapper, MAX_RESTORED_ALIAS_GAP};
+
+    fn aliases(entries: &[(&str, u64)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(id, alias)| (id.to_string(), alias.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn restore_aliases_moves_generator_past_restored_aliases() {
+        let id_mapper = IdMapper::new_memory();
+
+        let count = id_mapper
+            .restore_aliases(aliases(&[("a", 3), ("b", 7)]))
+            .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(id_mapper.get_or_create_alias("b").unwrap(), "7");
+        assert_eq!(id_mapper.get_or_create_alias("c").unwrap(), "8");
+    }
+
+    #[test]
+    fn restore_aliases_rejects_invalid_aliases() {
+        let id_mapper = IdMapper::new_memory();
+
+        assert!(id_mapper
+            .restore_aliases(aliases(&[("a", 1), ("b", 1)]))
+            .is_err());
+        assert!(id_mapper
+            .restore_aliases(aliases(&[("a", MAX_RESTORED_ALIAS_GAP + 1)]))
+            .is_err());
+
+        id_mapper.create_alias("a").unwrap();
+
+        assert!(id_mapper.restore_aliases(aliases(&[("b", 2)])).is_err());
+    }
+
+    #[test]
+    fn folders_index() {
+        let dir =
+            std::env::temp_dir().join(format!("pimalaya-tui-folders-index-{}", std::process::id()));
+
+        let index = FoldersIndex::new(&dir, "account");
+        assert_ne!(index, FoldersIndex::new(&dir, "other"));
+
+        index.insert("INBOX").unwrap();
+        index.insert("Lists/rust").unwrap();
+        index.insert("INBOX").unwrap();
+        index.insert("line\nbreak").unwrap();
+
+        let folders = BTreeSet::from([String::from("INBOX"), String::from("Lists/rust")]);
+        assert_eq!(index.folders().unwrap(), folders);
+
+        let folders = index.folders_with(["Sent", "INBOX"]).unwrap();
+        assert_eq!(folders.len(), 3);
+
+        index.remove().unwrap();
+        assert!(index.folders().unwrap().is_empty());
+        index.remove().unwrap();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
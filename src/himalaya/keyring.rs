@@ -0,0 +1,273 @@
+#[cfg(feature = "oauth2")]
+use email::account::config::oauth2::OAuth2Config;
+#[cfg(feature = "imap")]
+use email::imap::config::ImapAuthConfig;
+#[cfg(feature = "smtp")]
+use email::smtp::config::SmtpAuthConfig;
+use secret::Secret;
+
+#[cfg(feature = "imap")]
+use super::config::Backend;
+use super::config::HimalayaTomlAccountConfig;
+#[cfg(feature = "smtp")]
+use super::config::SendingBackend;
+use crate::{
+    terminal::{print, prompt},
+    Result,
+};
+
+/// Keyring entry referenced by an account configuration, see
+/// [`audit`].
+#[derive(Clone, Debug)]
+pub struct KeyringSecretReport {
+    /// Human-readable name of the secret, like `IMAP password`.
+    pub label: &'static str,
+    pub secret: Secret,
+    /// Whether the entry exists in the keyring.
+    pub found: bool,
+}
+
+/// Check that every keyring entry referenced by the IMAP and SMTP
+/// authentication configs of the given account actually exists.
+///
+/// Empty secrets are first replaced by keyring entries, the same
+/// way the account config is built from the TOML config. Secrets
+/// that are not stored in the keyring are not reported.
+pub async fn audit(
+    account_name: &str,
+    account_config: &mut HimalayaTomlAccountConfig,
+) -> Result<Vec<KeyringSecretReport>> {
+    let mut reports = Vec::new();
+
+    for (label, secret) in secrets(account_name, account_config)? {
+        reports.push(check(label, secret).await?);
+    }
+
+    Ok(reports)
+}
+
+/// Audit the keyring entries of the given account, then offer to
+/// enter the missing ones.
+///
+/// Returns the reports of the entries that are still missing.
+pub async fn repair(
+    account_name: &str,
+    account_config: &mut HimalayaTomlAccountConfig,
+) -> Result<Vec<KeyringSecretReport>> {
+    let mut missing = Vec::new();
+
+    for report in audit(account_name, account_config).await? {
+        if report.found {
+            continue;
+        }
+
+        let label = report.label;
+        print::warn(format!(
+            "Cannot find {label} in your system's global keyring."
+        ));
+
+        if !prompt::bool(format!("Would you like to enter your {label}?"), true, None)? {
+            missing.push(report);
+            continue;
+        }
+
+        let raw = prompt::secret(format!("{label}:"), None)?;
+        report.secret.set_if_keyring(raw).await?;
+        print::success(format!("{label} successfully saved in the keyring"));
+    }
+
+    Ok(missing)
+}
+
+/// Delete the keyring entries referenced by the given account,
+/// including the ones of empty secrets, see [`audit`].
+pub async fn delete(
+    account_name: &str,
+    account_config: &mut HimalayaTomlAccountConfig,
+) -> Result<()> {
+    for (_, secret) in secrets(account_name, account_config)? {
+        secret.delete_if_keyring().await?;
+    }
+
+    Ok(())
+}
+
+/// Keyring entry to move, see [`rename`].
+#[derive(Clone, Debug)]
+pub struct KeyringEntryMove {
+    pub from: Secret,
+    pub to: Secret,
+}
+
+/// Rename the keyring entries referenced by the given account after
+/// the new account name.
+///
+/// Only entries prefixed by the account name are renamed, which is
+/// how the wizard names them (`<account>-imap-passwd`), and how
+/// empty secrets are named, see [`audit`]. Other entries are kept as
+/// they are.
+///
+/// Only the account configuration is updated: the returned entries
+/// need to be moved with [`move_entries`] once the configuration is
+/// saved.
+pub fn rename(
+    account_config: &mut HimalayaTomlAccountConfig,
+    account_name: &str,
+    new_account_name: &str,
+) -> Result<Vec<KeyringEntryMove>> {
+    let mut moves = Vec::new();
+
+    for (_, secret) in secrets(account_name, account_config)? {
+        let Some(entry) = keyring_entry_name(secret) else {
+            continue;
+        };
+
+        let Some(suffix) = entry
+            .strip_prefix(account_name)
+            .filter(|suffix| suffix.starts_with('-'))
+        else {
+            continue;
+        };
+
+        let new_secret = Secret::try_new_keyring_entry(format!("{new_account_name}{suffix}"))?;
+        let from = std::mem::replace(secret, new_secret.clone());
+
+        moves.push(KeyringEntryMove {
+            from,
+            to: new_secret,
+        });
+    }
+
+    Ok(moves)
+}
+
+/// Move the values of the given keyring entries, see [`rename`].
+///
+/// Entries without value are skipped.
+pub async fn move_entries(moves: Vec<KeyringEntryMove>) -> Result<()> {
+    for KeyringEntryMove { from, to } in moves {
+        if let Some(value) = from.find().await? {
+            to.set_if_keyring(value).await?;
+            from.delete_if_keyring().await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the keyring secrets of the given account.
+///
+/// Empty secrets are first replaced by keyring entries named after
+/// the account, the same way the account config is built from the
+/// TOML config.
+fn secrets<'a>(
+    account_name: &str,
+    account_config: &'a mut HimalayaTomlAccountConfig,
+) -> Result<Vec<(&'static str, &'a mut Secret)>> {
+    let mut secrets = Vec::new();
+
+    #[cfg(feature = "imap")]
+    if let Some(Backend::Imap(imap_config)) = account_config.backend.as_mut() {
+        imap_config.auth.replace_empty_secrets(account_name)?;
+        secrets.extend(imap_secrets(&mut imap_config.auth));
+    }
+
+    // fields are borrowed separately, so that IMAP and SMTP secrets
+    // can be held at the same time
+    #[cfg(feature = "smtp")]
+    if let Some(SendingBackend::Smtp(smtp_config)) = account_config
+        .message
+        .as_mut()
+        .and_then(|msg| msg.send.as_mut())
+        .and_then(|send| send.backend.as_mut())
+    {
+        smtp_config.auth.replace_empty_secrets(account_name)?;
+        secrets.extend(smtp_secrets(&mut smtp_config.auth));
+    }
+
+    Ok(secrets)
+}
+
+/// Get the name of the keyring entry of the given secret, from its
+/// serialized form `{ keyring = "name" }`.
+fn keyring_entry_name(secret: &Secret) -> Option<String> {
+    match toml::Value::try_from(secret).ok()? {
+        toml::Value::Table(mut table) => match table.remove("keyring")? {
+            toml::Value::String(name) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+async fn check(label: &'static str, secret: &Secret) -> Result<KeyringSecretReport> {
+    let found = secret.find().await?.is_some();
+
+    Ok(KeyringSecretReport {
+        label,
+        secret: secret.clone(),
+        found,
+    })
+}
+
+fn is_keyring_entry(secret: &Secret) -> bool {
+    matches!(secret, Secret::KeyringEntry(_))
+}
+
+#[cfg(feature = "imap")]
+fn imap_secrets(auth: &mut ImapAuthConfig) -> Vec<(&'static str, &mut Secret)> {
+    match auth {
+        ImapAuthConfig::Password(passwd) => {
+            keyring_secrets([("IMAP password", Some(&mut passwd.0))])
+        }
+        #[cfg(feature = "oauth2")]
+        ImapAuthConfig::OAuth2(config) => oauth2_secrets(
+            config,
+            [
+                "IMAP OAuth 2.0 client secret",
+                "IMAP OAuth 2.0 access token",
+                "IMAP OAuth 2.0 refresh token",
+            ],
+        ),
+    }
+}
+
+#[cfg(feature = "smtp")]
+fn smtp_secrets(auth: &mut SmtpAuthConfig) -> Vec<(&'static str, &mut Secret)> {
+    match auth {
+        SmtpAuthConfig::Password(passwd) => {
+            keyring_secrets([("SMTP password", Some(&mut passwd.0))])
+        }
+        #[cfg(feature = "oauth2")]
+        SmtpAuthConfig::OAuth2(config) => oauth2_secrets(
+            config,
+            [
+                "SMTP OAuth 2.0 client secret",
+                "SMTP OAuth 2.0 access token",
+                "SMTP OAuth 2.0 refresh token",
+            ],
+        ),
+    }
+}
+
+#[cfg(feature = "oauth2")]
+fn oauth2_secrets<'a>(
+    config: &'a mut OAuth2Config,
+    [client_secret, access_token, refresh_token]: [&'static str; 3],
+) -> Vec<(&'static str, &'a mut Secret)> {
+    keyring_secrets([
+        (client_secret, config.client_secret.as_mut()),
+        (access_token, Some(&mut config.access_token)),
+        (refresh_token, Some(&mut config.refresh_token)),
+    ])
+}
+
+fn keyring_secrets<'a, const N: usize>(
+    secrets: [(&'static str, Option<&'a mut Secret>); N],
+) -> Vec<(&'static str, &'a mut Secret)> {
+    secrets
+        .into_iter()
+        .filter_map(|(label, secret)| Some((label, secret?)))
+        .filter(|(_, secret)| is_keyring_entry(secret))
+        .collect()
+}
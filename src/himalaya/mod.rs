@@ -1,8 +1,12 @@
 pub mod backend;
+#[cfg(feature = "sled")]
+pub mod cache;
 pub mod choice;
 pub mod config;
 #[cfg(feature = "cli")]
 pub mod editor;
 pub mod id_mapper;
+#[cfg(all(feature = "keyring", any(feature = "imap", feature = "smtp")))]
+pub mod keyring;
 #[cfg(feature = "wizard")]
 pub mod wizard;
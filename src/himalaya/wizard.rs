@@ -1,12 +1,21 @@
 use std::{
     fmt,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use super::config::*;
+use comfy_table::presets;
+use crossterm::style::Color;
+use email::{
+    account::config::AccountConfig, autoconfig::config::AutoConfig, folder::list::ListFolders,
+};
+
+use super::{backend::BackendBuilder, config::*};
+#[cfg(feature = "sled")]
+use super::{cache::EnvelopesCache, id_mapper::IdMapper};
 use crate::{
     terminal::{config::TomlConfig, print, prompt, wizard},
-    Result,
+    Error, Result,
 };
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -81,142 +90,1005 @@ const SEND_MESSAGE_BACKEND_KINDS: &[SendingBackendKind] = &[
     SendingBackendKind::None,
 ];
 
-pub async fn edit(
+/// Run the wizard without a TTY, every prompt being answered by the
+/// given answers or by its default value.
+///
+/// Returns [`Error::MissingPromptAnswerError`] if a prompt without
+/// default value is not answered.
+pub async fn run_headless(
     path: impl AsRef<Path>,
+    config: HimalayaTomlConfig,
+    account_name: Option<&str>,
+    account_config: HimalayaTomlAccountConfig,
+    answers: wizard::WizardAnswers,
+) -> Result<HimalayaTomlConfig> {
+    prompt::set_answer_provider(answers);
+    prompt::set_headless(true);
+
+    let config = edit(path, config, account_name, account_config).await;
+
+    prompt::set_headless(false);
+    prompt::unset_answer_provider();
+
+    config
+}
+
+/// Run the wizard for a new configuration.
+///
+/// Once an account is configured, the user is offered to configure
+/// another one. The configuration is written once all accounts are
+/// configured.
+pub async fn run(path: impl AsRef<Path>, config: HimalayaTomlConfig) -> Result<HimalayaTomlConfig> {
+    prompt::set_back_navigation(true);
+    let res = add_accounts_and_write(path.as_ref(), config).await;
+    prompt::set_back_navigation(false);
+
+    match res {
+        // going back from the first question leaves the wizard
+        Err(Error::PromptBack) => Err(Error::PromptCancelled),
+        res => res,
+    }
+}
+
+/// Ask questions about as many accounts as the user wants, then
+/// write the updated configuration.
+///
+/// Going back from the first question of an additional account
+/// drops it, going back from the configuration preview asks the
+/// questions of the last account again.
+async fn add_accounts_and_write(
+    path: &Path,
     mut config: HimalayaTomlConfig,
+) -> Result<HimalayaTomlConfig> {
+    let mut added_account_names: Vec<String> = Vec::new();
+    let mut editing: Option<(String, HimalayaTomlAccountConfig)> = None;
+
+    loop {
+        let (account_name, account_config) = match editing.take() {
+            Some((name, account_config)) => (Some(name), account_config),
+            None => (None, Default::default()),
+        };
+
+        match &account_name {
+            Some(name) => print::section(format!("Configuring your account {name}")),
+            None if config.accounts.is_empty() => {
+                print::section("Configuring your default account")
+            }
+            None => print::section("Configuring another account"),
+        };
+
+        match edit_account(&config, account_name.as_deref(), account_config).await {
+            Ok((name, account_config)) => {
+                insert_account(&mut config, name.clone(), account_config);
+                added_account_names.push(name);
+            }
+            Err(Error::PromptBack) if !added_account_names.is_empty() => (),
+            Err(err) => return Err(err),
+        }
+
+        if prompt::bool("Add another account?", false, None)? {
+            continue;
+        }
+
+        match config.write(path) {
+            Err(Error::PromptBack) => {
+                let name = added_account_names.pop();
+                let account_config = name.as_ref().and_then(|name| config.accounts.remove(name));
+                editing = name.zip(account_config);
+            }
+            res => return res.map(|()| config),
+        }
+    }
+}
+
+pub async fn edit(
+    path: impl AsRef<Path>,
+    config: HimalayaTomlConfig,
     account_name: Option<&str>,
-    mut account_config: HimalayaTomlAccountConfig,
+    account_config: HimalayaTomlAccountConfig,
 ) -> Result<HimalayaTomlConfig> {
     match account_name.as_ref() {
         Some(name) => print::section(format!("Configuring your account {name}")),
         None => print::section("Configuring your default account"),
     };
 
-    let default_email = Some(account_config.email.as_str()).filter(|email| !email.is_empty());
-    let email = prompt::email("Email address:", default_email)?;
+    prompt::set_back_navigation(true);
+    let res = edit_and_write(path.as_ref(), &config, account_name, account_config).await;
+    prompt::set_back_navigation(false);
+
+    match res {
+        // going back from the first question leaves the wizard
+        Err(Error::PromptBack) => Err(Error::PromptCancelled),
+        res => res,
+    }
+}
+
+/// Ask questions about the given account, then write the updated
+/// configuration.
+///
+/// Going back from the configuration preview asks the questions
+/// again, using previous answers as defaults.
+async fn edit_and_write(
+    path: &Path,
+    config: &HimalayaTomlConfig,
+    account_name: Option<&str>,
+    mut account_config: HimalayaTomlAccountConfig,
+) -> Result<HimalayaTomlConfig> {
+    loop {
+        let (name, edited_account_config) =
+            edit_account(config, account_name, account_config).await?;
 
-    account_config.email = email.to_string();
+        let mut config = config.clone();
+        insert_account(&mut config, name, edited_account_config.clone());
 
-    let default =
-        account_name.is_none() || prompt::bool("Should this account be the default one?", false)?;
+        match config.write(path) {
+            Err(Error::PromptBack) => account_config = edited_account_config,
+            res => return res.map(|()| config),
+        }
+    }
+}
 
-    if default {
+/// Insert the given account into the configuration, making sure it
+/// is the only default account if it is the default one.
+fn insert_account(
+    config: &mut HimalayaTomlConfig,
+    account_name: String,
+    account_config: HimalayaTomlAccountConfig,
+) {
+    if account_config.default == Some(true) {
         config
             .accounts
             .iter_mut()
             .for_each(|(_, config)| config.default = None)
     }
 
-    account_config.default = Some(default);
+    config.accounts.insert(account_name, account_config);
+}
 
-    let autoconfig_email = account_config.email.to_owned();
-    let autoconfig =
-        tokio::spawn(async move { email::autoconfig::from_addr(&autoconfig_email).await.ok() });
+/// Remove the given account from the configuration.
+///
+/// The user is asked whether keyring entries and id mapper databases
+/// of the account should be deleted as well. They are only deleted
+/// once the configuration is saved.
+pub async fn delete(
+    path: impl AsRef<Path>,
+    mut config: HimalayaTomlConfig,
+    account_name: &str,
+) -> Result<HimalayaTomlConfig> {
+    let Some((account_name, _)) = config.find_account(account_name)? else {
+        return Err(Error::GetAccountConfigError(account_name.to_owned()));
+    };
+
+    let account_name = account_name.to_owned();
+    print::section(format!("Deleting your account {account_name}"));
+
+    if !prompt::bool(format!("Delete the account {account_name}?"), false, None)? {
+        return Ok(config);
+    }
 
-    let default_account_name = match account_name {
-        Some(name) => name,
-        None => email
-            .domain()
-            .split_once('.')
-            .map(|domain| domain.0)
-            .unwrap_or(email.domain()),
+    #[allow(unused_mut, unused_variables)]
+    let Some(mut account_config) = config.accounts.remove(&account_name) else {
+        return Ok(config);
     };
 
-    let account_name = prompt::text("Account name:", Some(default_account_name))?;
+    #[cfg(all(feature = "keyring", any(feature = "imap", feature = "smtp")))]
+    let delete_keyring_entries = prompt::bool("Delete its keyring entries?", true, None)?;
 
-    let default_display_name = account_config
-        .display_name
-        .as_deref()
-        .or(Some(email.local_part()));
+    #[cfg(feature = "sled")]
+    let delete_databases = prompt::bool("Delete its id mapper databases?", true, None)?;
 
-    account_config.display_name = Some(prompt::text("Full display name:", default_display_name)?);
+    config.write(path.as_ref())?;
 
-    let default_downloads_dir = Some(PathBuf::from("~/Downloads"));
-    let default_downloads_dir = account_config
-        .downloads_dir
-        .as_deref()
-        .or(default_downloads_dir.as_deref());
+    // folders are listed before keyring entries are deleted, since
+    // the backend may need them
+    #[cfg(feature = "sled")]
+    let folders = if delete_databases {
+        database_folders(&account_name, &account_config).await
+    } else {
+        Vec::new()
+    };
 
-    account_config.downloads_dir =
-        Some(prompt::path("Downloads directory:", default_downloads_dir)?);
+    #[cfg(all(feature = "keyring", any(feature = "imap", feature = "smtp")))]
+    if delete_keyring_entries {
+        super::keyring::delete(&account_name, &mut account_config).await?;
+    }
 
-    let autoconfig = autoconfig.await?;
-    let autoconfig = autoconfig.as_ref();
+    #[cfg(feature = "sled")]
+    if delete_databases {
+        match IdMapper::remove(
+            account_config.id_map_dir.as_deref(),
+            &account_name,
+            &folders,
+        ) {
+            Ok(n) => print::info(format!("Removed {}", plural(n, "id mapper database"))),
+            Err(err) => print::warn(format!("{err:#}")),
+        }
 
-    if let Some(config) = autoconfig {
-        if config.is_gmail() {
-            println!();
-            print::warn("Warning: Google passwords cannot be used directly, see:");
-            print::warn("https://github.com/pimalaya/himalaya?tab=readme-ov-file#configuration");
-            println!();
+        match EnvelopesCache::remove(&account_name, &folders) {
+            Ok(n) => print::info(format!("Removed {}", plural(n, "envelopes cache"))),
+            Err(err) => print::warn(format!("{err:#}")),
         }
     }
 
-    let backend = prompt::item("Default backend:", &*DEFAULT_BACKEND_KINDS, None)?;
+    print::success(format!("Account {account_name} successfully deleted"));
+
+    Ok(config)
+}
+
+/// Rename the given account.
+///
+/// Keyring entries named after the account and id mapper databases
+/// are moved to the new name, once the configuration is saved.
+pub async fn rename(
+    path: impl AsRef<Path>,
+    mut config: HimalayaTomlConfig,
+    account_name: &str,
+    new_account_name: Option<&str>,
+) -> Result<HimalayaTomlConfig> {
+    let Some((account_name, _)) = config.find_account(account_name)? else {
+        return Err(Error::GetAccountConfigError(account_name.to_owned()));
+    };
+
+    let account_name = account_name.to_owned();
+    print::section(format!("Renaming your account {account_name}"));
+
+    let new_account_name = match new_account_name {
+        Some(name) => name.to_owned(),
+        None => prompt::text("New account name:", None, &[], None)?,
+    };
+
+    if new_account_name == account_name {
+        return Ok(config);
+    }
+
+    if config.accounts.contains_key(&new_account_name) {
+        return Err(Error::RenameAccountConflictError(new_account_name));
+    }
+
+    #[allow(unused_mut)]
+    let Some(mut account_config) = config.accounts.remove(&account_name) else {
+        return Ok(config);
+    };
+
+    // folders are listed while the backend can still find its
+    // keyring entries
+    #[cfg(feature = "sled")]
+    let folders = database_folders(&account_name, &account_config).await;
+
+    #[cfg(all(feature = "keyring", any(feature = "imap", feature = "smtp")))]
+    let keyring_moves =
+        super::keyring::rename(&mut account_config, &account_name, &new_account_name)?;
+
+    #[cfg(feature = "sled")]
+    let id_map_dir = account_config.id_map_dir.clone();
 
-    match backend {
-        BackendKind::None => {
-            account_config.backend = Some(Backend::None);
+    config
+        .accounts
+        .insert(new_account_name.clone(), account_config);
+    config.write(path.as_ref())?;
+
+    #[cfg(all(feature = "keyring", any(feature = "imap", feature = "smtp")))]
+    super::keyring::move_entries(keyring_moves).await?;
+
+    #[cfg(feature = "sled")]
+    {
+        match IdMapper::rename(
+            id_map_dir.as_deref(),
+            &account_name,
+            &new_account_name,
+            &folders,
+        ) {
+            Ok(n) => print::info(format!("Moved {}", plural(n, "id mapper database"))),
+            Err(err) => print::warn(format!("{err:#}")),
         }
-        #[cfg(feature = "imap")]
-        BackendKind::Imap => {
-            let config = wizard::imap::start(&account_name, &email, autoconfig).await?;
-            account_config.backend = Some(Backend::Imap(config));
+
+        // caches are rebuilt by the next listings
+        if let Err(err) = EnvelopesCache::remove(&account_name, &folders) {
+            print::warn(format!("{err:#}"));
         }
-        #[cfg(feature = "maildir")]
-        BackendKind::Maildir => {
-            let config = wizard::maildir::start(&account_name)?;
-            account_config.backend = Some(Backend::Maildir(config));
+    }
+
+    print::success(format!(
+        "Account {account_name} successfully renamed to {new_account_name}"
+    ));
+
+    Ok(config)
+}
+
+/// Table presets offered by [`edit_tables`], as `(name, preset)`
+/// pairs.
+const TABLE_PRESETS: [(&str, &str); 6] = [
+    ("ASCII Markdown", presets::ASCII_MARKDOWN),
+    ("ASCII full", presets::ASCII_FULL_CONDENSED),
+    ("ASCII borders only", presets::ASCII_BORDERS_ONLY_CONDENSED),
+    ("UTF-8 full", presets::UTF8_FULL_CONDENSED),
+    ("UTF-8 borders only", presets::UTF8_BORDERS_ONLY),
+    ("No borders", presets::NOTHING),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct TablePreset(&'static str, &'static str);
+
+impl fmt::Display for TablePreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Customize columns, glyphs and colors of the envelopes and folders
+/// tables.
+///
+/// Tables of the given account are customized, or tables shared by
+/// all accounts when no account is given.
+pub fn edit_tables(
+    path: impl AsRef<Path>,
+    mut config: HimalayaTomlConfig,
+    account_name: Option<&str>,
+) -> Result<HimalayaTomlConfig> {
+    let (envelope_config, folder_config) = match account_name {
+        Some(name) => {
+            let Some((name, _)) = config.find_account(name)? else {
+                return Err(Error::GetAccountConfigError(name.to_owned()));
+            };
+
+            print::section(format!("Customizing tables of your account {name}"));
+
+            let name = name.to_owned();
+            let account_config = config.accounts.get_mut(&name).unwrap();
+            (&mut account_config.envelope, &mut account_config.folder)
         }
-        #[cfg(feature = "notmuch")]
-        BackendKind::Notmuch => {
-            let config = wizard::notmuch::start()?;
-            account_config.backend = Some(Backend::Notmuch(config));
+        None => {
+            print::section("Customizing tables of all your accounts");
+
+            let defaults = config.defaults.get_or_insert_with(Default::default);
+            (&mut defaults.envelope, &mut defaults.folder)
         }
+    };
+
+    if prompt::bool("Customize the envelopes table?", true, None)? {
+        let table = envelope_config
+            .get_or_insert_with(Default::default)
+            .list
+            .get_or_insert_with(Default::default)
+            .table
+            .get_or_insert_with(Default::default);
+
+        edit_envelopes_table(table)?;
     }
 
-    let backend = prompt::item(
-        "Backend for sending messages:",
-        &*SEND_MESSAGE_BACKEND_KINDS,
+    if prompt::bool("Customize the folders table?", true, None)? {
+        let table = folder_config
+            .get_or_insert_with(Default::default)
+            .list
+            .get_or_insert_with(Default::default)
+            .table
+            .get_or_insert_with(Default::default);
+
+        edit_folders_table(table)?;
+    }
+
+    config.write(path.as_ref())?;
+    Ok(config)
+}
+
+fn edit_envelopes_table(table: &mut ListEnvelopesTableConfig) -> Result<()> {
+    table.preset = edit_table_preset("Envelopes table style:", table.preset.as_deref())?;
+
+    let default_columns = table
+        .columns
+        .clone()
+        .unwrap_or_else(|| EnvelopesTableColumn::DEFAULT.to_vec());
+    let columns = prompt::items(
+        "Envelopes table columns:",
+        EnvelopesTableColumn::ALL,
+        &default_columns,
         None,
     )?;
+    table.columns =
+        Some(columns).filter(|columns| *columns != default_columns || table.columns.is_some());
+
+    let glyphs = table
+        .flag_glyphs()
+        .into_iter()
+        .map(|glyph| -> Result<FlagGlyph> {
+            let prompt = format!("Glyph of {} envelopes:", glyph.flag);
+            let glyph_char = prompt::text(prompt.as_str(), Some(glyph.glyph.as_str()), &[], None)?;
+            Ok(FlagGlyph::new(glyph.flag, glyph_char))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    table.flag_glyphs = Some(glyphs);
+
+    table.id_color = edit_table_color("Envelope ids color:", table.id_color, Color::Red)?;
+    table.flags_color = edit_table_color("Envelope flags color:", table.flags_color, Color::Reset)?;
+    table.subject_color = edit_table_color(
+        "Envelope subjects color:",
+        table.subject_color,
+        Color::Green,
+    )?;
+    table.sender_color =
+        edit_table_color("Envelope senders color:", table.sender_color, Color::Blue)?;
+    table.date_color =
+        edit_table_color("Envelope dates color:", table.date_color, Color::DarkYellow)?;
+
+    Ok(())
+}
+
+fn edit_folders_table(table: &mut ListFoldersTableConfig) -> Result<()> {
+    table.preset = edit_table_preset("Folders table style:", table.preset.as_deref())?;
+
+    let default_columns = table
+        .columns
+        .clone()
+        .unwrap_or_else(|| FoldersTableColumn::ALL.to_vec());
+    let columns = prompt::items(
+        "Folders table columns:",
+        FoldersTableColumn::ALL,
+        &default_columns,
+        None,
+    )?;
+    table.columns =
+        Some(columns).filter(|columns| *columns != default_columns || table.columns.is_some());
+
+    table.name_color = edit_table_color("Folder names color:", table.name_color, Color::Blue)?;
+    table.desc_color =
+        edit_table_color("Folder descriptions color:", table.desc_color, Color::Green)?;
+
+    Ok(())
+}
+
+/// Ask for a table preset, keeping the given one when it is not
+/// offered by [`TABLE_PRESETS`].
+fn edit_table_preset(prompt: &str, preset: Option<&str>) -> Result<Option<String>> {
+    let choices = TABLE_PRESETS.map(|(name, preset)| TablePreset(name, preset));
+    let known = choices
+        .iter()
+        .copied()
+        .find(|known| Some(known.1) == preset);
+
+    if preset.is_some() && known.is_none() {
+        return Ok(preset.map(ToOwned::to_owned));
+    }
+
+    let choice = prompt::item(prompt, choices, known.or(choices.first().copied()), None)?;
+
+    // the Markdown preset is the default one
+    if preset.is_none() && choice.1 == presets::ASCII_MARKDOWN {
+        return Ok(None);
+    }
+
+    Ok(Some(choice.1.to_owned()))
+}
+
+/// Ask for a table color, only keeping it when it differs from the
+/// built-in default or when it was already set.
+fn edit_table_color(prompt: &str, color: Option<Color>, default: Color) -> Result<Option<Color>> {
+    let answer = prompt::color(prompt, Some(color.unwrap_or(default)), None)?;
+    Ok(Some(answer).filter(|answer| color.is_some() || *answer != default))
+}
+
+/// Folders databases may have been created for, in addition to the
+/// indexed ones, see [`FoldersIndex`]: the default folders, the
+/// folder aliases and the folders of the backend of the given
+/// account.
+///
+/// Databases opened before folders were indexed can only be found
+/// this way. The backend is only listed when reachable, otherwise
+/// the user is warned that some databases may be left behind.
+///
+/// [`FoldersIndex`]: super::id_mapper::FoldersIndex
+#[cfg(feature = "sled")]
+async fn database_folders(
+    account_name: &str,
+    account_config: &HimalayaTomlAccountConfig,
+) -> Vec<String> {
+    use email::folder::{DRAFTS, INBOX, SENT, TRASH};
+
+    let mut folders: Vec<String> = [INBOX, SENT, DRAFTS, TRASH]
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect();
+
+    let aliases = account_config
+        .folder
+        .as_ref()
+        .and_then(|folder| folder.aliases.as_ref());
+
+    for (alias, folder) in aliases.into_iter().flatten() {
+        folders.push(alias.to_owned());
+        folders.push(folder.to_owned());
+    }
+
+    if !matches!(account_config.backend, None | Some(Backend::None)) {
+        let spinner = print::spinner("Listing folders…");
+        let backend_folders = list_folders(account_name, account_config).await;
+        drop(spinner);
+
+        match backend_folders {
+            Ok(backend_folders) => folders.extend(backend_folders),
+            Err(err) => {
+                print::warn(format!("Cannot list folders: {err}"));
+                print::warn("Databases of folders opened by older versions may be left behind.");
+            }
+        }
+    }
+
+    folders.sort();
+    folders.dedup();
+    folders
+}
+
+/// Ask questions about the given account, going back to the
+/// previous question on Esc.
+async fn edit_account(
+    config: &HimalayaTomlConfig,
+    account_name: Option<&str>,
+    mut account_config: HimalayaTomlAccountConfig,
+) -> Result<(String, HimalayaTomlAccountConfig)> {
+    let mut email = None;
+    let mut preset = None;
+    let mut autoconfig_task = None;
+    let mut autoconfig = None;
+    let mut name = account_name.map(ToOwned::to_owned);
+    let mut backend_kind = None;
+    let mut sending_backend_kind = None;
+
+    let mut steps = wizard::Steps::default();
+
+    while steps.current() < 12 {
+        let res = match steps.current() {
+            0 => (|| {
+                let default_email =
+                    Some(account_config.email.as_str()).filter(|email| !email.is_empty());
+                let addr = prompt::email("Email address:", default_email, None)?;
+
+                account_config.email = addr.to_string();
+                preset = wizard::preset::find(addr.domain());
+
+                if let Some(preset) = preset {
+                    print::info(format!(
+                        "Using {} settings for {}",
+                        preset.name,
+                        addr.domain()
+                    ));
+
+                    if !preset.folder_aliases.is_empty() {
+                        let folder = account_config.folder.get_or_insert_with(Default::default);
+                        let aliases = folder.aliases.get_or_insert_with(Default::default);
+
+                        for (alias, name) in preset.folder_aliases {
+                            aliases
+                                .entry(alias.to_string())
+                                .or_insert_with(|| name.to_string());
+                        }
+                    }
+                }
+
+                let autoconfig_email = account_config.email.to_owned();
+                autoconfig_task = Some(tokio::spawn(async move {
+                    email::autoconfig::from_addr(&autoconfig_email).await.ok()
+                }));
+                autoconfig = None;
+
+                email = Some(addr);
+                Ok(())
+            })(),
+            // the first account of a configuration is the default one
+            1 if config.accounts.is_empty() => {
+                account_config.default = Some(true);
+                steps.skip()
+            }
+            1 => (|| {
+                let default = account_config.default.unwrap_or_default();
+                let default =
+                    prompt::bool("Should this account be the default one?", default, None)?;
+                account_config.default = Some(default);
+                Ok(())
+            })(),
+            2 => (|| {
+                let Some(email) = &email else {
+                    return steps.skip();
+                };
+
+                let default_account_name = match &name {
+                    Some(name) => name.as_str(),
+                    None => email
+                        .domain()
+                        .split_once('.')
+                        .map(|domain| domain.0)
+                        .unwrap_or(email.domain()),
+                };
+
+                name = Some(prompt::autocomplete(
+                    "Account name:",
+                    Some(default_account_name),
+                    config.accounts.keys(),
+                    None,
+                )?);
+
+                Ok(())
+            })(),
+            3 => (|| {
+                let default_display_name = account_config
+                    .display_name
+                    .as_deref()
+                    .or(email.as_ref().map(|email| email.local_part()));
+
+                account_config.display_name = Some(prompt::text(
+                    "Full display name:",
+                    default_display_name,
+                    &[],
+                    None,
+                )?);
+
+                Ok(())
+            })(),
+            4 => (|| {
+                let default_downloads_dir = Some(PathBuf::from("~/Downloads"));
+                let default_downloads_dir = account_config
+                    .downloads_dir
+                    .as_deref()
+                    .or(default_downloads_dir.as_deref());
+
+                account_config.downloads_dir = Some(prompt::path(
+                    "Downloads directory:",
+                    default_downloads_dir,
+                    None,
+                )?);
+
+                Ok(())
+            })(),
+            5 => {
+                async {
+                    if let Some(task) = autoconfig_task.take() {
+                        autoconfig = task.await?;
+                        print_quirks(preset, autoconfig.as_ref());
+                    }
+
+                    #[cfg(feature = "imap")]
+                    let default_backend = preset
+                        .and_then(|preset| preset.imap)
+                        .map(|_| BackendKind::Imap);
+                    #[cfg(not(feature = "imap"))]
+                    let default_backend = None;
+
+                    backend_kind = Some(prompt::item(
+                        "Default backend:",
+                        DEFAULT_BACKEND_KINDS.iter().cloned(),
+                        backend_kind.clone().or(default_backend),
+                        None,
+                    )?);
+
+                    Ok(())
+                }
+                .await
+            }
+            6 => {
+                async {
+                    #[allow(unused_variables)]
+                    let (Some(kind), Some(account_name), Some(email)) =
+                        (&backend_kind, &name, &email)
+                    else {
+                        return steps.skip();
+                    };
+
+                    account_config.backend = Some(match kind {
+                        BackendKind::None => Backend::None,
+                        #[cfg(feature = "imap")]
+                        BackendKind::Imap => Backend::Imap(
+                            wizard::imap::start(account_name, email, autoconfig.as_ref(), preset)
+                                .await?,
+                        ),
+                        #[cfg(feature = "maildir")]
+                        BackendKind::Maildir => {
+                            Backend::Maildir(wizard::maildir::start(account_name)?)
+                        }
+                        #[cfg(feature = "notmuch")]
+                        BackendKind::Notmuch => Backend::Notmuch(wizard::notmuch::start()?),
+                    });
+
+                    // nothing is asked when there is no backend
+                    if *kind == BackendKind::None {
+                        steps.skip()
+                    } else {
+                        Ok(())
+                    }
+                }
+                .await
+            }
+            7 => (|| {
+                #[cfg(feature = "smtp")]
+                let default_backend = preset
+                    .and_then(|preset| preset.smtp)
+                    .map(|_| SendingBackendKind::Smtp);
+                #[cfg(not(feature = "smtp"))]
+                let default_backend = None;
+
+                sending_backend_kind = Some(prompt::item(
+                    "Backend for sending messages:",
+                    SEND_MESSAGE_BACKEND_KINDS.iter().cloned(),
+                    sending_backend_kind.clone().or(default_backend),
+                    None,
+                )?);
+
+                Ok(())
+            })(),
+            8 => {
+                async {
+                    #[allow(unused_variables)]
+                    let (Some(kind), Some(account_name), Some(email)) =
+                        (&sending_backend_kind, &name, &email)
+                    else {
+                        return steps.skip();
+                    };
+
+                    let backend = match kind {
+                        SendingBackendKind::None => SendingBackend::None,
+                        #[cfg(feature = "smtp")]
+                        SendingBackendKind::Smtp => SendingBackend::Smtp(
+                            wizard::smtp::start(account_name, email, autoconfig.as_ref(), preset)
+                                .await?,
+                        ),
+                        #[cfg(feature = "sendmail")]
+                        SendingBackendKind::Sendmail => {
+                            SendingBackend::Sendmail(wizard::sendmail::start()?)
+                        }
+                    };
+
+                    account_config.message = Some(MessageConfig {
+                        send: Some(SendMessageConfig {
+                            backend: Some(backend),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    });
+
+                    if *kind == SendingBackendKind::None {
+                        steps.skip()
+                    } else {
+                        Ok(())
+                    }
+                }
+                .await
+            }
+            #[cfg(feature = "pgp")]
+            9 => {
+                async {
+                    let (Some(account_name), Some(email)) = (&name, &email) else {
+                        return steps.skip();
+                    };
+
+                    account_config.pgp =
+                        wizard::pgp::start(account_name, email, account_config.pgp.as_ref())
+                            .await?;
+
+                    Ok(())
+                }
+                .await
+            }
+            #[cfg(not(feature = "pgp"))]
+            9 => steps.skip(),
+            10 => {
+                async {
+                    // folders cannot be listed without backend
+                    if matches!(account_config.backend, None | Some(Backend::None)) {
+                        return steps.skip();
+                    }
+
+                    let account_name = name.as_deref().unwrap_or_default();
+                    edit_folder_aliases(account_name, &mut account_config).await
+                }
+                .await
+            }
+            _ => {
+                async {
+                    let account_name = name.as_deref().unwrap_or_default();
+
+                    if test(account_name, &account_config).await? {
+                        Ok(())
+                    } else {
+                        Err(Error::PromptCancelled)
+                    }
+                }
+                .await
+            }
+        };
+
+        steps.advance(res)?;
+    }
+
+    Ok((name.unwrap_or_default(), account_config))
+}
 
-    match backend {
-        SendingBackendKind::None => {
-            account_config.message = Some(MessageConfig {
-                send: Some(SendMessageConfig {
-                    backend: Some(SendingBackend::None),
-                    ..Default::default()
-                }),
-                ..Default::default()
-            });
-        }
-        #[cfg(feature = "smtp")]
-        SendingBackendKind::Smtp => {
-            let config = wizard::smtp::start(&account_name, &email, autoconfig).await?;
-            account_config.message = Some(MessageConfig {
-                send: Some(SendMessageConfig {
-                    backend: Some(SendingBackend::Smtp(config)),
-                    ..Default::default()
-                }),
-                ..Default::default()
-            });
-        }
-        #[cfg(feature = "sendmail")]
-        SendingBackendKind::Sendmail => {
-            let config = wizard::sendmail::start()?;
-            account_config.message = Some(MessageConfig {
-                send: Some(SendMessageConfig {
-                    backend: Some(SendingBackend::Sendmail(config)),
-                    ..Default::default()
-                }),
-                ..Default::default()
-            });
+fn print_quirks(preset: Option<&wizard::preset::Preset>, autoconfig: Option<&AutoConfig>) {
+    if let Some(preset) = preset.filter(|preset| !preset.quirks.is_empty()) {
+        eprintln!();
+        for quirk in preset.quirks {
+            print::warn(quirk);
+        }
+        eprintln!();
+    } else if let Some(config) = autoconfig {
+        if config.is_gmail() {
+            eprintln!();
+            print::warn("Warning: Google passwords cannot be used directly, see:");
+            print::warn("https://github.com/pimalaya/himalaya?tab=readme-ov-file#configuration");
+            eprintln!();
+        }
+    }
+}
+
+/// Folder aliases offered by [`edit_folder_aliases`].
+const FOLDER_ALIASES: [&str; 4] = ["sent", "drafts", "trash", "archive"];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum FolderAliasChoice {
+    Folder(String),
+    None,
+}
+
+impl fmt::Display for FolderAliasChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Folder(folder) => write!(f, "{folder}"),
+            Self::None => write!(f, "No alias"),
+        }
+    }
+}
+
+/// Offer to configure aliases of special folders.
+///
+/// Folders of the account backend are listed so that the user can
+/// pick the actual folder names.
+async fn edit_folder_aliases(
+    account_name: &str,
+    account_config: &mut HimalayaTomlAccountConfig,
+) -> Result<()> {
+    if !prompt::bool("Configure folder aliases?", false, None)? {
+        return Ok(());
+    }
+
+    let spinner = print::spinner("Listing folders…");
+    let folders = list_folders(account_name, account_config).await;
+    drop(spinner);
+
+    let folders = match folders {
+        Ok(folders) => folders,
+        Err(err) => {
+            print::warn(format!("Cannot list folders: {err}"));
+            Vec::new()
         }
     };
 
-    config.accounts.insert(account_name, account_config);
-    config.write(path.as_ref())?;
+    let folder_config = account_config.folder.get_or_insert_with(Default::default);
+    let aliases = folder_config.aliases.get_or_insert_with(Default::default);
 
-    Ok(config)
+    for alias in FOLDER_ALIASES {
+        let prompt = format!("Folder aliased as {alias}:");
+
+        // guess the folder from its name when no alias is set yet
+        let default_folder = aliases.get(alias).cloned().or_else(|| {
+            folders
+                .iter()
+                .find(|folder| folder.to_lowercase().contains(alias))
+                .cloned()
+        });
+
+        let folder = if folders.is_empty() {
+            prompt::some_text(
+                prompt.as_str(),
+                default_folder.as_deref(),
+                &[],
+                Some("Leave empty to not alias this folder"),
+            )?
+        } else {
+            let choices = folders
+                .iter()
+                .cloned()
+                .map(FolderAliasChoice::Folder)
+                .chain([FolderAliasChoice::None]);
+
+            let default_choice = default_folder
+                .map(FolderAliasChoice::Folder)
+                .unwrap_or(FolderAliasChoice::None);
+
+            match prompt::item(prompt, choices, Some(default_choice), None)? {
+                FolderAliasChoice::Folder(folder) => Some(folder),
+                FolderAliasChoice::None => None,
+            }
+        };
+
+        match folder {
+            Some(folder) => aliases.insert(alias.to_owned(), folder),
+            None => aliases.remove(alias),
+        };
+    }
+
+    if aliases.is_empty() {
+        folder_config.aliases = None;
+    }
+
+    Ok(())
+}
+
+/// List the folder names of the default backend of the given
+/// account.
+async fn list_folders(
+    account_name: &str,
+    toml_account_config: &HimalayaTomlAccountConfig,
+) -> color_eyre::Result<Vec<String>> {
+    let (toml_account_config, account_config) = account_configs(account_name, toml_account_config);
+
+    let backend = BackendBuilder::new(toml_account_config, account_config, |b| b)
+        .build()
+        .await?;
+
+    let mut folders: Vec<String> = backend
+        .list_folders()
+        .await?
+        .into_iter()
+        .map(|folder| folder.name)
+        .collect();
+
+    folders.sort();
+    Ok(folders)
+}
+
+fn account_configs(
+    account_name: &str,
+    toml_account_config: &HimalayaTomlAccountConfig,
+) -> (Arc<HimalayaTomlAccountConfig>, Arc<AccountConfig>) {
+    let toml_account_config = Arc::new(toml_account_config.clone());
+
+    let mut account_config = AccountConfig::from((*toml_account_config).clone());
+    account_config.name = account_name.to_owned();
+
+    (toml_account_config, Arc::new(account_config))
+}
+
+/// Offer to test the given account configuration before saving it.
+///
+/// The test builds the backends of the account, which connects and
+/// authenticates to remote servers, then lists folders of the
+/// default backend.
+///
+/// Returns `false` if the test failed and the configuration should
+/// not be saved.
+async fn test(account_name: &str, toml_account_config: &HimalayaTomlAccountConfig) -> Result<bool> {
+    if !prompt::bool("Test this configuration now?", true, None)? {
+        return Ok(true);
+    }
+
+    let (toml_account_config, account_config) = account_configs(account_name, toml_account_config);
+
+    let has_backend = !matches!(toml_account_config.backend, None | Some(Backend::None));
+
+    let spinner = print::spinner("Testing the configuration…");
+    let res = async {
+        let backend = BackendBuilder::new(toml_account_config.clone(), account_config, |b| b)
+            .build()
+            .await?;
+
+        if has_backend {
+            backend.list_folders().await?;
+        }
+
+        color_eyre::Result::<()>::Ok(())
+    }
+    .await;
+    drop(spinner);
+
+    match res {
+        Ok(()) => {
+            print::success("The configuration works!");
+            Ok(true)
+        }
+        Err(err) => {
+            let reason = err
+                .chain()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": ");
+            print::error(format!("The configuration does not work: {reason}"));
+            prompt::bool("Save this configuration anyway?", false, None)
+        }
+    }
 }
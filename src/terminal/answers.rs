@@ -0,0 +1,99 @@
+use std::{collections::HashMap, env};
+
+/// Source of answers used to satisfy prompts without a TTY.
+///
+/// Answers are looked up by key, see [`answer_key`].
+pub trait AnswerProvider: Send + Sync {
+    fn get(&self, key: &str) -> Option<String>;
+}
+
+/// Build the answer key of the given prompt message.
+///
+/// The key is the lowercased message where every sequence of
+/// non-alphanumeric characters is replaced by a single dash, for
+/// example `IMAP OAuth 2.0 client id:` gives `imap-oauth-2-0-client-id`.
+pub fn answer_key(prompt: &str) -> String {
+    let mut key = String::with_capacity(prompt.len());
+
+    for c in prompt.chars() {
+        if c.is_alphanumeric() {
+            key.extend(c.to_lowercase());
+        } else if !key.is_empty() && !key.ends_with('-') {
+            key.push('-');
+        }
+    }
+
+    key.trim_end_matches('-').to_owned()
+}
+
+/// Answers backed by a plain map.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MapAnswers(pub HashMap<String, String>);
+
+impl AnswerProvider for MapAnswers {
+    fn get(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+}
+
+impl FromIterator<(String, String)> for MapAnswers {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        Self(HashMap::from_iter(iter))
+    }
+}
+
+#[cfg(feature = "config")]
+impl MapAnswers {
+    /// Read answers from a TOML file.
+    ///
+    /// The file is expected to be a flat table where keys are
+    /// answer keys. Non-string values are converted to their string
+    /// representation.
+    pub fn from_toml_path(path: impl AsRef<std::path::Path>) -> crate::Result<Self> {
+        use crate::Error;
+
+        let path = path.as_ref();
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| Error::ReadAnswersFileError(err, path.to_owned()))?;
+
+        let table: toml::Table = toml::from_str(&content)
+            .map_err(|err| Error::ParseAnswersFileError(err, path.to_owned()))?;
+
+        let answers = table
+            .into_iter()
+            .map(|(key, val)| match val {
+                toml::Value::String(val) => (key, val),
+                val => (key, val.to_string()),
+            })
+            .collect();
+
+        Ok(answers)
+    }
+}
+
+/// Answers backed by environment variables.
+///
+/// The variable name is the prefix followed by the uppercased answer
+/// key, dashes being replaced by underscores. For example, with the
+/// prefix `HIMALAYA_ANSWER_`, the prompt `IMAP hostname:` is answered
+/// by `HIMALAYA_ANSWER_IMAP_HOSTNAME`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EnvAnswers {
+    prefix: String,
+}
+
+impl EnvAnswers {
+    pub fn new(prefix: impl ToString) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+        }
+    }
+}
+
+impl AnswerProvider for EnvAnswers {
+    fn get(&self, key: &str) -> Option<String> {
+        let key = key.to_uppercase().replace('-', "_");
+        env::var(format!("{}{key}", self.prefix)).ok()
+    }
+}
@@ -0,0 +1,82 @@
+use inquire::{
+    autocompletion::{Autocomplete, Replacement},
+    CustomUserError,
+};
+
+/// Autocompletion that fuzzy-matches the user input against a fixed
+/// list of suggestions.
+///
+/// Suggestions are matched when all characters of the input appear
+/// in order (case-insensitive), then sorted by relevance.
+#[derive(Clone, Debug, Default)]
+pub struct FuzzyAutocomplete {
+    suggestions: Vec<String>,
+}
+
+impl FuzzyAutocomplete {
+    pub fn new(suggestions: impl IntoIterator<Item = impl ToString>) -> Self {
+        Self {
+            suggestions: suggestions.into_iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl Autocomplete for FuzzyAutocomplete {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        let mut matches: Vec<_> = self
+            .suggestions
+            .iter()
+            .filter_map(|suggestion| Some((fuzzy_score(input, suggestion)?, suggestion)))
+            .collect();
+
+        // highest scores first, then alphabetically
+        matches.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then(a.cmp(b)));
+
+        Ok(matches.into_iter().map(|(_, s)| s.clone()).collect())
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        match highlighted_suggestion {
+            Some(suggestion) => Ok(Some(suggestion)),
+            None => Ok(self.get_suggestions(input)?.into_iter().next()),
+        }
+    }
+}
+
+/// Compute a fuzzy matching score of the given input against the
+/// given candidate.
+///
+/// Returns [`None`] if the candidate does not match. Consecutive
+/// matching characters and matches at the beginning of the candidate
+/// are rewarded.
+pub fn fuzzy_score(input: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut streak = 0;
+    let mut candidate_chars = candidate.chars().flat_map(char::to_lowercase).enumerate();
+
+    for c in input.chars().flat_map(char::to_lowercase) {
+        loop {
+            let (i, candidate_char) = candidate_chars.next()?;
+
+            if c == candidate_char {
+                streak += 1;
+                score += streak * 2;
+
+                if i == 0 {
+                    score += 4;
+                }
+
+                break;
+            }
+
+            streak = 0;
+            score -= 1;
+        }
+    }
+
+    Some(score)
+}
@@ -1,6 +1,8 @@
 use std::{
     fmt,
-    io::{stderr, stdout, Stderr, Stdout, Write},
+    fs::File,
+    io::{stderr, stdout, BufWriter, IsTerminal, Stderr, Write},
+    path::Path,
     str::FromStr,
 };
 
@@ -11,12 +13,15 @@ use color_eyre::{
 };
 use serde::Serialize;
 
+use crate::terminal::wrap::WrapWriter;
+
 /// Represents the available output formats.
 #[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, ValueEnum)]
 pub enum OutputFmt {
     #[default]
     Plain,
     Json,
+    Yaml,
 }
 
 impl FromStr for OutputFmt {
@@ -26,6 +31,7 @@ impl FromStr for OutputFmt {
         match fmt {
             fmt if fmt.eq_ignore_ascii_case("json") => Ok(Self::Json),
             fmt if fmt.eq_ignore_ascii_case("plain") => Ok(Self::Plain),
+            fmt if fmt.eq_ignore_ascii_case("yaml") => Ok(Self::Yaml),
             unknown => bail!("cannot parse output format {unknown}"),
         }
     }
@@ -36,6 +42,7 @@ impl fmt::Display for OutputFmt {
         let fmt = match *self {
             OutputFmt::Json => "JSON",
             OutputFmt::Plain => "Plain",
+            OutputFmt::Yaml => "YAML",
         };
 
         write!(f, "{}", fmt)
@@ -54,36 +61,140 @@ impl<T: Serialize> OutputJson<T> {
     }
 }
 
+/// Defines a struct-wrapper to provide a JSON error output.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct OutputErrorJson {
+    error: OutputError,
+}
+
+impl OutputErrorJson {
+    pub fn new(err: &Error) -> Self {
+        Self {
+            error: OutputError::new(err),
+        }
+    }
+}
+
+/// Serializable representation of an error.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct OutputError {
+    /// The name of the crate error variant, or `unknown`.
+    code: String,
+    message: String,
+    /// Messages of the underlying causes, outermost first.
+    chain: Vec<String>,
+}
+
+impl OutputError {
+    pub fn new(err: &Error) -> Self {
+        let code = err
+            .chain()
+            .find_map(|err| err.downcast_ref::<crate::Error>())
+            .map(crate::Error::code)
+            .unwrap_or("unknown")
+            .to_owned();
+
+        Self {
+            code,
+            message: err.to_string(),
+            chain: err.chain().skip(1).map(ToString::to_string).collect(),
+        }
+    }
+}
+
 pub trait PrintTable {
     fn print(&self, writer: &mut dyn Write, table_max_width: Option<u16>) -> Result<()>;
 }
 
+/// Data that can be printed, either displayed or serialized
+/// depending on the output format.
+///
+/// This trait is implemented for every type implementing both
+/// [`fmt::Display`] and [`serde::Serialize`]. It is object-safe,
+/// which allows [`Printer`] to be used as a trait object.
+pub trait PrintData: fmt::Display + erased_serde::Serialize {}
+
+impl<T: fmt::Display + serde::Serialize + ?Sized> PrintData for T {}
+
+erased_serde::serialize_trait_object!(PrintData);
+
 pub trait Printer {
-    fn out<T: fmt::Display + serde::Serialize>(&mut self, data: T) -> Result<()>;
+    fn out(&mut self, data: &dyn PrintData) -> Result<()>;
 
-    fn log<T: fmt::Display + serde::Serialize>(&mut self, data: T) -> Result<()> {
+    fn log(&mut self, data: &dyn PrintData) -> Result<()> {
         self.out(data)
     }
 
+    /// Report the given error.
+    ///
+    /// Machine-readable printers should serialize the error, see
+    /// [`OutputErrorJson`].
+    fn error(&mut self, err: &Error) -> Result<()> {
+        eprintln!("{err:?}");
+        Ok(())
+    }
+
     fn is_json(&self) -> bool {
         false
     }
+
+    /// Return `true` if logs are suppressed, so that only data is
+    /// printed.
+    fn is_quiet(&self) -> bool {
+        false
+    }
 }
 
+/// Printer writing data to stdout and logs to stderr.
+///
+/// Data can be redirected to another writer, see
+/// [`StdoutPrinter::with_writer`] and [`StdoutPrinter::with_path`].
+///
+/// Data is written as it is, since it may already be laid out (like
+/// tables). Logs are wrapped at the terminal width when stderr is a
+/// terminal.
 pub struct StdoutPrinter {
-    stdout: Stdout,
+    stdout: Box<dyn Write + Send>,
     stderr: Stderr,
     output: OutputFmt,
+    quiet: bool,
+    is_terminal: bool,
 }
 
 impl StdoutPrinter {
     pub fn new(output: OutputFmt) -> Self {
+        let stderr = stderr();
+        let is_terminal = stderr.is_terminal();
+
         Self {
-            stdout: stdout(),
-            stderr: stderr(),
+            stdout: Box::new(stdout()),
+            stderr,
             output,
+            quiet: false,
+            is_terminal,
         }
     }
+
+    /// Write data to the given writer instead of stdout.
+    pub fn with_writer(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.stdout = Box::new(writer);
+        self
+    }
+
+    /// Write data to the file at the given path, creating or
+    /// truncating it.
+    pub fn with_path(self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("cannot create output file at {}", path.display()))?;
+        Ok(self.with_writer(BufWriter::new(file)))
+    }
+
+    /// Suppress logs, only data sent to [`Printer::out`] is printed.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
 }
 
 impl Default for StdoutPrinter {
@@ -93,29 +204,171 @@ impl Default for StdoutPrinter {
 }
 
 impl Printer for StdoutPrinter {
-    fn out<T: fmt::Display + serde::Serialize>(&mut self, data: T) -> Result<()> {
+    fn out(&mut self, data: &dyn PrintData) -> Result<()> {
         match self.output {
             OutputFmt::Plain => {
                 writeln!(self.stdout, "{data}")?;
             }
             OutputFmt::Json => {
-                serde_json::to_writer(&mut self.stdout, &data)
+                serde_json::to_writer(&mut self.stdout, data)
                     .context("cannot write json to writer")?;
             }
+            OutputFmt::Yaml => {
+                serde_yaml::to_writer(&mut self.stdout, data)
+                    .context("cannot write yaml to writer")?;
+            }
+        };
+
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    fn log(&mut self, data: &dyn PrintData) -> Result<()> {
+        if self.quiet {
+            return Ok(());
+        }
+
+        match self.output {
+            OutputFmt::Plain if self.is_terminal => {
+                let mut stderr = WrapWriter::new(&mut self.stderr);
+                write!(stderr, "{data}")?;
+                stderr.flush()?;
+            }
+            OutputFmt::Plain => {
+                write!(&mut self.stderr, "{data}")?;
+            }
+            OutputFmt::Json | OutputFmt::Yaml => (),
+        }
+
+        Ok(())
+    }
+
+    fn error(&mut self, err: &Error) -> Result<()> {
+        match self.output {
+            OutputFmt::Plain => {
+                writeln!(self.stderr, "{err:?}")?;
+            }
+            OutputFmt::Json => {
+                serde_json::to_writer(&mut self.stdout, &OutputErrorJson::new(err))
+                    .context("cannot write json error to writer")?;
+            }
+            OutputFmt::Yaml => {
+                serde_yaml::to_writer(&mut self.stdout, &OutputErrorJson::new(err))
+                    .context("cannot write yaml error to writer")?;
+            }
+        };
+
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    fn is_json(&self) -> bool {
+        self.output == OutputFmt::Json
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+}
+
+/// Printer capturing data, logs and errors in memory.
+///
+/// Data is captured in its displayed or serialized form depending on
+/// the output format, which makes this printer suitable for tests.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VecPrinter {
+    pub out: Vec<String>,
+    pub logs: Vec<String>,
+    pub errors: Vec<String>,
+    output: OutputFmt,
+    quiet: bool,
+}
+
+impl VecPrinter {
+    pub fn new(output: OutputFmt) -> Self {
+        Self {
+            output,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    fn format(&self, data: &dyn PrintData) -> Result<String> {
+        let data = match self.output {
+            OutputFmt::Plain => data.to_string(),
+            OutputFmt::Json => {
+                serde_json::to_string(data).context("cannot serialize data to json")?
+            }
+            OutputFmt::Yaml => {
+                serde_yaml::to_string(data).context("cannot serialize data to yaml")?
+            }
         };
 
+        Ok(data)
+    }
+}
+
+impl Printer for VecPrinter {
+    fn out(&mut self, data: &dyn PrintData) -> Result<()> {
+        let data = self.format(data)?;
+        self.out.push(data);
         Ok(())
     }
 
-    fn log<T: fmt::Display + serde::Serialize>(&mut self, data: T) -> Result<()> {
-        if let OutputFmt::Plain = self.output {
-            write!(&mut self.stderr, "{data}")?;
+    fn log(&mut self, data: &dyn PrintData) -> Result<()> {
+        if !self.quiet && self.output == OutputFmt::Plain {
+            self.logs.push(data.to_string());
         }
 
         Ok(())
     }
 
+    fn error(&mut self, err: &Error) -> Result<()> {
+        let err = match self.output {
+            OutputFmt::Plain => err.to_string(),
+            OutputFmt::Json => serde_json::to_string(&OutputErrorJson::new(err))
+                .context("cannot serialize error to json")?,
+            OutputFmt::Yaml => serde_yaml::to_string(&OutputErrorJson::new(err))
+                .context("cannot serialize error to yaml")?,
+        };
+
+        self.errors.push(err);
+        Ok(())
+    }
+
     fn is_json(&self) -> bool {
         self.output == OutputFmt::Json
     }
+
+    fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::{eyre, Context};
+
+    use super::OutputError;
+
+    #[test]
+    fn output_error_code_is_the_crate_error_variant() {
+        let err = Err::<(), _>(crate::Error::PromptMaxAttemptsReached(3))
+            .context("cannot configure account")
+            .unwrap_err();
+        let output = OutputError::new(&err);
+
+        assert_eq!(output.code, "PromptMaxAttemptsReached");
+        assert_eq!(output.message, "cannot configure account");
+        assert_eq!(
+            output.chain,
+            vec!["cannot prompt: too many invalid answers (3 attempts)"]
+        );
+
+        assert_eq!(OutputError::new(&eyre!("other")).code, "unknown");
+    }
 }
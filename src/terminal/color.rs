@@ -0,0 +1,252 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crossterm::style::{Color, Colored};
+
+const UNSET: u8 = 0;
+const DISABLED: u8 = 1;
+const ENABLED: u8 = 2;
+
+static OVERRIDE: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Initialize the color switch for content written to stdout, see
+/// [`init_for`].
+pub fn init() {
+    init_for(io::stdout().is_terminal());
+}
+
+/// Initialize the color switch for content written to a stream,
+/// given whether this stream is a terminal.
+///
+/// Colors are disabled when `NO_COLOR` is set to a non-empty value,
+/// forced when `CLICOLOR_FORCE` is set to a non-empty value other
+/// than `0`, otherwise enabled only when the stream is a terminal.
+/// Since styled content relies on a global switch, it should be
+/// called before each write to a different stream.
+pub fn init_for(is_terminal: bool) {
+    // styled content from crossterm relies on its own switch
+    Colored::set_ansi_color_disabled(!is_enabled_for(is_terminal));
+}
+
+/// Return `true` if colors should be emitted to stdout.
+///
+/// Printing helpers, tables and trees consult this switch, so that
+/// pipelines and accessibility setups do not get ANSI codes.
+pub fn is_enabled() -> bool {
+    is_enabled_for(io::stdout().is_terminal())
+}
+
+/// Return `true` if colors should be emitted to a stream, given
+/// whether this stream is a terminal.
+pub fn is_enabled_for(is_terminal: bool) -> bool {
+    match OVERRIDE.load(Ordering::Relaxed) {
+        ENABLED => true,
+        DISABLED => false,
+        _ => enabled_from_env(is_terminal),
+    }
+}
+
+/// Enable or disable colors for all streams, overriding the
+/// environment.
+pub fn set_enabled(enabled: bool) {
+    OVERRIDE.store(if enabled { ENABLED } else { DISABLED }, Ordering::Relaxed);
+    Colored::set_ansi_color_disabled(!enabled);
+}
+
+fn enabled_from_env(is_terminal: bool) -> bool {
+    let is_set = |key| env::var(key).is_ok_and(|val| !val.is_empty());
+
+    if is_set("NO_COLOR") {
+        return false;
+    }
+
+    if is_set("CLICOLOR_FORCE") && env::var("CLICOLOR_FORCE").as_deref() != Ok("0") {
+        return true;
+    }
+
+    is_terminal
+}
+
+/// Parse a color from a user input.
+///
+/// Accepts color names in any case (`dark_grey`, `Dark-Grey`, `dark
+/// grey`), ANSI values (`0` to `255`) and hexadecimal RGB values
+/// (`#rrggbb`).
+pub fn parse(input: &str) -> Result<Color, String> {
+    let input = input.trim();
+
+    if let Some(hex) = input.strip_prefix('#') {
+        return parse_hex(hex).ok_or_else(|| format!("{input} is not a valid #rrggbb color"));
+    }
+
+    if let Ok(ansi) = input.parse::<u8>() {
+        return Ok(Color::AnsiValue(ansi));
+    }
+
+    let name = input.to_lowercase().replace(['-', ' '], "_");
+
+    match name.replace("gray", "grey").as_str() {
+        "reset" => Ok(Color::Reset),
+        name => Color::try_from(name).map_err(|_| format!("{input} is not a valid color")),
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+
+    Some(Color::Rgb { r, g, b })
+}
+
+/// Format the given color so that it can be parsed back by
+/// [`parse`].
+pub fn to_string(color: &Color) -> String {
+    match color {
+        Color::Reset => String::from("reset"),
+        Color::Black => String::from("black"),
+        Color::DarkGrey => String::from("dark_grey"),
+        Color::Red => String::from("red"),
+        Color::DarkRed => String::from("dark_red"),
+        Color::Green => String::from("green"),
+        Color::DarkGreen => String::from("dark_green"),
+        Color::Yellow => String::from("yellow"),
+        Color::DarkYellow => String::from("dark_yellow"),
+        Color::Blue => String::from("blue"),
+        Color::DarkBlue => String::from("dark_blue"),
+        Color::Magenta => String::from("magenta"),
+        Color::DarkMagenta => String::from("dark_magenta"),
+        Color::Cyan => String::from("cyan"),
+        Color::DarkCyan => String::from("dark_cyan"),
+        Color::White => String::from("white"),
+        Color::Grey => String::from("grey"),
+        Color::Rgb { r, g, b } => format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::AnsiValue(n) => n.to_string(),
+    }
+}
+
+/// Convert the given color to an inquire color.
+///
+/// Returns [`None`] for [`Color::Reset`], which has no inquire
+/// counterpart. Light variants are used for non-dark colors, since
+/// inquire maps its own non-dark colors to the dark ones.
+pub fn to_inquire_color(color: Color) -> Option<inquire::ui::Color> {
+    use inquire::ui;
+
+    match color {
+        Color::Reset => None,
+        Color::Black => Some(ui::Color::Black),
+        Color::DarkGrey => Some(ui::Color::DarkGrey),
+        Color::Red => Some(ui::Color::LightRed),
+        Color::DarkRed => Some(ui::Color::DarkRed),
+        Color::Green => Some(ui::Color::LightGreen),
+        Color::DarkGreen => Some(ui::Color::DarkGreen),
+        Color::Yellow => Some(ui::Color::LightYellow),
+        Color::DarkYellow => Some(ui::Color::DarkYellow),
+        Color::Blue => Some(ui::Color::LightBlue),
+        Color::DarkBlue => Some(ui::Color::DarkBlue),
+        Color::Magenta => Some(ui::Color::LightMagenta),
+        Color::DarkMagenta => Some(ui::Color::DarkMagenta),
+        Color::Cyan => Some(ui::Color::LightCyan),
+        Color::DarkCyan => Some(ui::Color::DarkCyan),
+        Color::White => Some(ui::Color::White),
+        Color::Grey => Some(ui::Color::Grey),
+        Color::Rgb { r, g, b } => Some(ui::Color::Rgb { r, g, b }),
+        Color::AnsiValue(n) => Some(ui::Color::AnsiValue(n)),
+    }
+}
+
+/// Convert the given color to a comfy-table color.
+#[cfg(feature = "himalaya")]
+pub fn to_comfy_table_color(color: Color) -> comfy_table::Color {
+    match color {
+        Color::Reset => comfy_table::Color::Reset,
+        Color::Black => comfy_table::Color::Black,
+        Color::DarkGrey => comfy_table::Color::DarkGrey,
+        Color::Red => comfy_table::Color::Red,
+        Color::DarkRed => comfy_table::Color::DarkRed,
+        Color::Green => comfy_table::Color::Green,
+        Color::DarkGreen => comfy_table::Color::DarkGreen,
+        Color::Yellow => comfy_table::Color::Yellow,
+        Color::DarkYellow => comfy_table::Color::DarkYellow,
+        Color::Blue => comfy_table::Color::Blue,
+        Color::DarkBlue => comfy_table::Color::DarkBlue,
+        Color::Magenta => comfy_table::Color::Magenta,
+        Color::DarkMagenta => comfy_table::Color::DarkMagenta,
+        Color::Cyan => comfy_table::Color::Cyan,
+        Color::DarkCyan => comfy_table::Color::DarkCyan,
+        Color::White => comfy_table::Color::White,
+        Color::Grey => comfy_table::Color::Grey,
+        Color::Rgb { r, g, b } => comfy_table::Color::Rgb { r, g, b },
+        Color::AnsiValue(n) => comfy_table::Color::AnsiValue(n),
+    }
+}
+
+/// (De)serialize optional colors using [`parse`] and [`to_string`].
+///
+/// Colors can be given as names in any case, ANSI values (as string
+/// or integer) or hexadecimal RGB values:
+///
+/// ```toml
+/// subject-color = "#ff8800"
+/// sender-color = 123
+/// date-color = "Dark-Yellow"
+/// ```
+///
+/// Use it with `#[serde(default, with = "color::serde_option")]`.
+#[cfg(feature = "himalaya")]
+pub mod serde_option {
+    use std::fmt;
+
+    use crossterm::style::Color;
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        color: &Option<Color>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match color {
+            Some(color) => serializer.serialize_some(&super::to_string(color)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Color>, D::Error> {
+        deserializer.deserialize_any(ColorVisitor).map(Some)
+    }
+
+    struct ColorVisitor;
+
+    impl de::Visitor<'_> for ColorVisitor {
+        type Value = Color;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a color name, an ANSI value or a #rrggbb value")
+        }
+
+        fn visit_str<E: de::Error>(self, color: &str) -> Result<Self::Value, E> {
+            super::parse(color).map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, n: u64) -> Result<Self::Value, E> {
+            u8::try_from(n)
+                .map(Color::AnsiValue)
+                .map_err(|_| E::custom(format!("{n} is not a valid ANSI color")))
+        }
+
+        fn visit_i64<E: de::Error>(self, n: i64) -> Result<Self::Value, E> {
+            u8::try_from(n)
+                .map(Color::AnsiValue)
+                .map_err(|_| E::custom(format!("{n} is not a valid ANSI color")))
+        }
+    }
+}
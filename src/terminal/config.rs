@@ -1,4 +1,7 @@
-use std::{fs, path::PathBuf};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 use async_trait::async_trait;
 use dirs::{config_dir, home_dir};
@@ -25,54 +28,68 @@ pub trait TomlConfig: for<'de> Deserialize<'de> {
 
     /// Read and parse the TOML configuration at the given paths
     ///
-    /// Returns an error if a configuration file cannot be read or if
-    /// a content cannot be parsed.
+    /// Despite its name, configuration files can also be written in
+    /// YAML or JSON, see [`parse_config_file`].
+    ///
+    /// Configurations are merged in order, each one followed by the
+    /// files it includes (see [`read_toml_file`]). Then `${ENV_VAR}`
+    /// occurrences in string values are replaced by the value of the
+    /// environment variable, see [`interpolate_env_vars`]. Finally,
+    /// encrypted values are decrypted, see [`decrypt_values`].
+    ///
+    /// Returns an error if a configuration file cannot be read, if
+    /// a content cannot be parsed or if an environment variable is
+    /// missing.
     fn from_paths(paths: &[PathBuf]) -> Result<Self> {
-        match paths.len() {
-            0 => {
-                return Err(Error::ReadTomlConfigFileFromEmptyPaths);
-            }
-            1 => {
-                let path = &paths[0];
+        let Some(path) = paths.first() else {
+            return Err(Error::ReadTomlConfigFileFromEmptyPaths);
+        };
 
-                let ref content = fs::read_to_string(path)
-                    .map_err(|err| Error::ReadTomlConfigFile(err, path.clone()))?;
+        let mut merged_content = read_toml_file(path, &mut Vec::new())?;
+        // keep the content of each file, in order to report parse
+        // errors against the file defining the faulty field
+        let mut contents = vec![(path, merged_content.clone())];
 
-                toml::from_str(content).map_err(|err| Error::ParseTomlConfigFile(err, path.clone()))
+        for path in &paths[1..] {
+            if !path.is_file() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(?path, "skipping invalid subconfig file");
+                continue;
             }
-            _ => {
-                let path = &paths[0];
 
-                let mut merged_content = fs::read_to_string(path)
-                    .map_err(|err| Error::ReadTomlConfigFile(err, path.clone()))?
-                    .parse::<Value>()
-                    .map_err(|err| Error::ParseTomlConfigFile(err, path.clone()))?;
+            let content = read_toml_file(path, &mut Vec::new())?;
+            merged_content =
+                merge(merged_content, content.clone()).map_err(Error::MergeTomlConfigFiles)?;
+            contents.push((path, content));
+        }
 
-                for path in &paths[1..] {
-                    let content = fs::read_to_string(path);
+        interpolate_env_vars(&mut merged_content)?;
 
-                    #[cfg(feature = "tracing")]
-                    if let Err(err) = &content {
-                        tracing::debug!(?path, ?err, "skipping invalid subconfig file");
-                    }
+        let decrypt_cmd = match merged_content
+            .as_table_mut()
+            .and_then(|t| t.remove("decrypt-cmd"))
+        {
+            Some(Value::String(cmd)) => cmd,
+            _ => String::from(DEFAULT_DECRYPT_CMD),
+        };
 
-                    let Ok(content) = content else {
-                        continue;
-                    };
+        decrypt_values(&mut merged_content, &decrypt_cmd)?;
 
-                    let content = content
-                        .parse()
-                        .map_err(|err| Error::ParseTomlConfigFile(err, path.clone()))?;
+        merged_content.clone().try_into().map_err(|err| {
+            let hint = suggest_unknown_field(&err, &merged_content);
 
-                    merged_content =
-                        merge(merged_content, content).map_err(Error::MergeTomlConfigFiles)?;
-                }
+            if let [(path, _)] = contents.as_slice() {
+                return Error::ParseTomlConfigFile(err, path.to_path_buf(), hint);
+            }
 
-                merged_content
-                    .try_into()
-                    .map_err(|err| Error::ParseTomlConfigFile(err, path.clone()))
+            match find_unknown_field_file(&err, &merged_content, &contents) {
+                Some(path) => Error::ParseTomlConfigFile(err, path.clone(), hint),
+                None => {
+                    let paths = contents.into_iter().map(|(path, _)| path.clone()).collect();
+                    Error::ParseMergedTomlConfigFiles(err, paths, hint)
+                }
             }
-        }
+        })
     }
 
     /// Read and parse the TOML configuration at the optional given
@@ -84,11 +101,17 @@ pub trait TomlConfig: for<'de> Deserialize<'de> {
     /// If the given path does not exist, then create it using the
     /// wizard.
     ///
-    /// If no path is given, then either read and parse the TOML
-    /// configuration at the first valid default path, otherwise
-    /// create it using the wizard.  wizard.
+    /// If no path is given, then paths are taken from the
+    /// environment, see [`TomlConfig::env_paths`].
+    ///
+    /// If no path is found in the environment either, then either
+    /// read and parse the TOML configuration at the first valid
+    /// default path, otherwise create it using the wizard.
     #[cfg(feature = "wizard")]
     async fn from_paths_or_default(paths: &[PathBuf]) -> Result<Self> {
+        let env_paths = Self::env_paths();
+        let paths = if paths.is_empty() { &env_paths } else { paths };
+
         match paths.len() {
             0 => Self::from_default_paths().await,
             _ if paths[0].exists() => Self::from_paths(paths),
@@ -105,6 +128,9 @@ pub trait TomlConfig: for<'de> Deserialize<'de> {
 
     #[cfg(not(feature = "wizard"))]
     fn from_paths_or_default(paths: &[PathBuf]) -> Result<Self> {
+        let env_paths = Self::env_paths();
+        let paths = if paths.is_empty() { &env_paths } else { paths };
+
         match paths.len() {
             0 => Self::from_default_paths(),
             _ if paths[0].exists() => Self::from_paths(paths),
@@ -112,6 +138,26 @@ pub trait TomlConfig: for<'de> Deserialize<'de> {
         }
     }
 
+    /// Get the name of the environment variable overriding the
+    /// configuration paths, like `HIMALAYA_CONFIG`.
+    fn env_var_name() -> String {
+        let project = Self::project_name().to_ascii_uppercase().replace('-', "_");
+        format!("{project}_CONFIG")
+    }
+
+    /// Get the configuration paths from the environment variable
+    /// named after the project, see [`TomlConfig::env_var_name`].
+    ///
+    /// Multiple paths can be given, separated like in `PATH`.
+    fn env_paths() -> Vec<PathBuf> {
+        match env::var_os(Self::env_var_name()) {
+            Some(paths) => env::split_paths(&paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     /// Read and parse the TOML configuration from default paths.
     #[cfg(feature = "wizard")]
     async fn from_default_paths() -> Result<Self> {
@@ -147,24 +193,41 @@ pub trait TomlConfig: for<'de> Deserialize<'de> {
         Ok(dir.join(Self::project_name()).join("config.toml"))
     }
 
-    /// Get the first default configuration path that points to a
-    /// valid file
-    ///
-    /// Tries paths in this order:
+    /// Get the default configuration paths, in order of precedence
     ///
-    /// - `$XDG_CONFIG_DIR/<project>/config.toml`
+    /// - `$XDG_CONFIG_HOME/<project>/config.toml`, when defined
+    /// - `<config dir>/<project>/config.toml`, where the config dir
+    ///   is `$XDG_CONFIG_HOME` or `$HOME/.config` on Linux,
+    ///   `$HOME/Library/Application Support` on macOS and
+    ///   `%APPDATA%` on Windows
     /// - `$HOME/.config/<project>/config.toml`
+    /// - `%APPDATA%\<project>\config.toml` on Windows
+    /// - `$HOME/Library/Application Support/<project>/config.toml` on
+    ///   macOS
     /// - `$HOME/.<project>rc`
-    fn first_valid_default_path() -> Option<PathBuf> {
-        let project = Self::project_name();
+    ///
+    /// For every config directory, `config.yaml`, `config.yml` and
+    /// `config.json` follow `config.toml`. Duplicates are removed.
+    fn default_paths() -> Vec<PathBuf> {
+        let dirs = ConfigDirs {
+            xdg_config_home: env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+            config_dir: config_dir(),
+            home: home_dir(),
+            app_data: env::var_os("APPDATA")
+                .filter(|_| cfg!(windows))
+                .map(PathBuf::from),
+            macos: cfg!(target_os = "macos"),
+        };
 
-        Self::default_path()
-            .ok()
-            .filter(|p| p.exists())
-            .or_else(|| home_dir().map(|p| p.join(".config").join(project).join("config.toml")))
-            .filter(|p| p.exists())
-            .or_else(|| home_dir().map(|p| p.join(format!(".{project}rc"))))
-            .filter(|p| p.exists())
+        dirs.config_paths(Self::project_name())
+    }
+
+    /// Get the first default configuration path that points to a
+    /// valid file, see [`TomlConfig::default_paths`].
+    fn first_valid_default_path() -> Option<PathBuf> {
+        Self::default_paths().into_iter().find(|p| p.exists())
     }
 
     #[cfg(feature = "wizard")]
@@ -179,40 +242,184 @@ pub trait TomlConfig: for<'de> Deserialize<'de> {
         }
     }
 
+    /// Number of backups kept by [`TomlConfig::write`], see
+    /// [`backup_file`].
+    ///
+    /// Setting it to 0 disables backups.
+    #[cfg(feature = "wizard")]
+    fn backup_retention() -> usize {
+        DEFAULT_BACKUP_RETENTION
+    }
+
     #[cfg(feature = "wizard")]
     fn write(&self, path: &std::path::Path) -> Result<()>
     where
         Self: serde::Serialize,
     {
-        use crate::terminal::prompt;
+        use crate::terminal::{print, prompt};
 
-        let path = prompt::path("Where to save the configuration?", Some(path))?;
-        println!("Writing configuration at {}…", path.display());
+        let path = prompt::path("Where to save the configuration?", Some(path), None)?;
+        print::info(format!("Writing configuration at {}…", path.display()));
 
-        let mut doc: toml_edit::DocumentMut = toml::to_string(&self)
-            .map_err(Error::SerializeTomlConfigError)?
-            .parse()
-            .map_err(Error::ParseSerializedTomlConfigError)?;
+        let value = Value::try_from(self).map_err(Error::SerializeTomlConfigError)?;
+        let existing = fs::read_to_string(&path)
+            .ok()
+            .map(|content| (content, read_loaded_toml_file(&path)));
+        let existing = existing
+            .as_ref()
+            .map(|(content, loaded)| (content.as_str(), loaded));
+        let doc = build_document(value, existing, Self::set_table_dotted)?;
 
-        doc.iter_mut().for_each(|(_, item)| {
-            if let Some(table) = item.as_table_mut() {
-                table.iter_mut().for_each(|(_, item)| {
-                    if let Some(table) = item.as_table_mut() {
-                        Self::set_table_dotted(table);
-                    }
-                })
+        // secrets typed in the wizard are saved, but not shown
+        let mut preview = doc.clone();
+        redact_table_secrets(preview.as_table_mut(), false);
+        let doc = doc.to_string();
+
+        print::section("Configuration preview");
+        print::toml(preview.to_string());
+
+        // wizards supporting back navigation can change answers
+        if prompt::back_navigation() {
+            let save = prompt::confirm(
+                "Save this configuration?",
+                ("Save", "Back"),
+                true,
+                Some("Choose Back to change your answers"),
+            )?;
+
+            if !save {
+                return Err(Error::PromptBack);
             }
-        });
+        } else if !prompt::bool("Save this configuration?", true, None)? {
+            return Err(Error::PromptCancelled);
+        }
+
+        if let Some(backup) = backup_file(&path, Self::backup_retention())? {
+            print::info(format!(
+                "Existing configuration backed up at {}",
+                backup.display()
+            ));
+        }
 
         fs::create_dir_all(path.parent().unwrap_or(&path))
             .map_err(|err| Error::CreateTomlConfigParentDirectoryError(err, path.clone()))?;
-        fs::write(&path, doc.to_string())
-            .map_err(|err| Error::WriteTomlConfigError(err, path.clone()))?;
+        fs::write(&path, doc).map_err(|err| Error::WriteTomlConfigError(err, path.clone()))?;
 
-        println!("Done! Exiting the wizard…");
+        print::success("Done! Exiting the wizard…");
         Ok(())
     }
 
+    /// Watch the configuration files at the given paths, and call
+    /// the given callback with the result of
+    /// [`TomlConfig::from_paths`] each time one of them changes.
+    ///
+    /// Parent directories are watched instead of the files
+    /// themselves, so that files replaced by editors are still
+    /// watched. Files included by the configuration are not watched.
+    /// A single save may trigger the callback multiple times.
+    ///
+    /// Watching stops when the returned watcher is dropped.
+    #[cfg(feature = "config-watch")]
+    fn watch(
+        paths: &[PathBuf],
+        mut callback: impl FnMut(Result<Self>) + Send + 'static,
+    ) -> Result<notify::RecommendedWatcher>
+    where
+        Self: Sized + 'static,
+    {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let watched_paths = paths.to_vec();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(_err) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(?_err, "skipping invalid config watch event");
+                        return;
+                    }
+                };
+
+                if !matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    return;
+                }
+
+                // only watched directories emit events, so comparing
+                // file names is enough
+                let changed = event.paths.iter().any(|path| {
+                    watched_paths
+                        .iter()
+                        .any(|watched| path.file_name() == watched.file_name())
+                });
+
+                if changed {
+                    callback(Self::from_paths(&watched_paths));
+                }
+            })
+            .map_err(Error::WatchTomlConfigError)?;
+
+        for path in paths {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(Error::WatchTomlConfigError)?;
+        }
+
+        Ok(watcher)
+    }
+
+    /// Serialize the configuration as it is effectively used, see
+    /// [`TomlConfig::dump`].
+    ///
+    /// Defaults to the configuration as it is. Configurations
+    /// holding defaults shared by accounts apply them to each
+    /// account instead, so that dumps show the values accounts
+    /// actually get.
+    fn to_effective_value(&self) -> Result<Value>
+    where
+        Self: serde::Serialize,
+    {
+        Value::try_from(self).map_err(Error::SerializeTomlConfigError)
+    }
+
+    /// Serialize the configuration using the given format, with
+    /// secrets redacted.
+    ///
+    /// Used on a configuration loaded with [`TomlConfig::from_paths`],
+    /// it shows the configuration as it is effectively loaded: files
+    /// merged, includes resolved, environment variables expanded and
+    /// account defaults applied, see
+    /// [`TomlConfig::to_effective_value`]. See [`redact_secrets`] for
+    /// what is considered a secret.
+    fn dump(&self, format: ConfigFormat) -> Result<String>
+    where
+        Self: serde::Serialize,
+    {
+        let mut config = self.to_effective_value()?;
+        redact_secrets(&mut config, false);
+
+        match format {
+            ConfigFormat::Toml => {
+                toml::to_string_pretty(&config).map_err(Error::SerializeTomlConfigError)
+            }
+            ConfigFormat::Yaml => {
+                serde_yaml::to_string(&config).map_err(Error::SerializeYamlConfigError)
+            }
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(&config).map_err(Error::SerializeJsonConfigError)
+            }
+        }
+    }
+
     fn to_toml_account_config(
         &self,
         account_name: Option<&str>,
@@ -243,3 +450,1285 @@ pub trait TomlConfig: for<'de> Deserialize<'de> {
         Ok((toml_account_config, account_config))
     }
 }
+
+/// Directories where configuration files are looked for, see
+/// [`TomlConfig::default_paths`].
+#[derive(Clone, Debug, Default)]
+struct ConfigDirs {
+    /// The `$XDG_CONFIG_HOME` directory, when defined.
+    xdg_config_home: Option<PathBuf>,
+    /// The config directory of the platform.
+    config_dir: Option<PathBuf>,
+    /// The home directory.
+    home: Option<PathBuf>,
+    /// The `%APPDATA%` directory, on Windows only.
+    app_data: Option<PathBuf>,
+    /// Whether `~/Library/Application Support` is looked up.
+    macos: bool,
+}
+
+impl ConfigDirs {
+    /// Get the configuration paths of the given project, in order of
+    /// precedence.
+    fn config_paths(&self, project: &str) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+
+        dirs.extend(self.xdg_config_home.clone());
+        dirs.extend(self.config_dir.clone());
+        dirs.extend(self.home.as_ref().map(|home| home.join(".config")));
+        dirs.extend(self.app_data.clone());
+
+        if self.macos {
+            dirs.extend(
+                self.home
+                    .as_ref()
+                    .map(|home| home.join("Library").join("Application Support")),
+            );
+        }
+
+        let mut paths = Vec::new();
+
+        for dir in dirs {
+            for ext in ["toml", "yaml", "yml", "json"] {
+                paths.push(dir.join(project).join("config").with_extension(ext));
+            }
+        }
+
+        paths.extend(
+            self.home
+                .as_ref()
+                .map(|home| home.join(format!(".{project}rc"))),
+        );
+
+        let mut unique_paths = Vec::with_capacity(paths.len());
+        for path in paths {
+            if !unique_paths.contains(&path) {
+                unique_paths.push(path);
+            }
+        }
+
+        unique_paths
+    }
+}
+
+/// Read and parse the TOML file at the given path, then merge the
+/// files it includes into it.
+///
+/// Files are included using the top-level `include` key, which
+/// accepts a path or a list of paths:
+///
+/// ```toml
+/// include = ["accounts/*.toml", "~/.config/theme.toml"]
+/// ```
+///
+/// Relative paths are resolved from the directory of the including
+/// file. The file name may contain `*` and `?` wildcards, matching
+/// files are included in alphabetical order. Included files can
+/// include other files, as long as they do not include themselves.
+///
+/// The given stack contains the paths of the files being included,
+/// in order to detect cycles.
+pub fn read_toml_file(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Value> {
+    let canonical_path = path
+        .canonicalize()
+        .map_err(|err| Error::ReadTomlConfigFile(err, path.to_owned()))?;
+
+    if stack.contains(&canonical_path) {
+        return Err(Error::IncludeTomlConfigCycleError(path.to_owned()));
+    }
+
+    let content =
+        fs::read_to_string(path).map_err(|err| Error::ReadTomlConfigFile(err, path.to_owned()))?;
+    let mut content = parse_config_file(path, &content)?;
+
+    let includes = match content.as_table_mut().and_then(|t| t.remove("include")) {
+        None => return Ok(content),
+        Some(Value::String(include)) => vec![include],
+        Some(Value::Array(includes)) => includes
+            .into_iter()
+            .map(|include| match include {
+                Value::String(include) => Ok(include),
+                _ => Err(Error::ParseTomlConfigIncludeError(path.to_owned())),
+            })
+            .collect::<Result<_>>()?,
+        Some(_) => return Err(Error::ParseTomlConfigIncludeError(path.to_owned())),
+    };
+
+    let dir = canonical_path.parent().unwrap_or(Path::new("/"));
+    stack.push(canonical_path.clone());
+
+    for include in includes {
+        for path in expand_include(dir, &include)? {
+            let included = read_toml_file(&path, stack)?;
+            content = merge(content, included).map_err(Error::MergeTomlConfigFiles)?;
+        }
+    }
+
+    stack.pop();
+    Ok(content)
+}
+
+/// Insert into the given value the keys of the given defaults it
+/// does not contain, recursively.
+pub fn fill_missing(value: &mut Value, defaults: Value) {
+    let (Value::Table(table), Value::Table(defaults)) = (value, defaults) else {
+        return;
+    };
+
+    for (key, default) in defaults {
+        match table.get_mut(&key) {
+            Some(value) => fill_missing(value, default),
+            None => {
+                table.insert(key, default);
+            }
+        }
+    }
+}
+
+/// Read the configuration file at the given path the way
+/// [`TomlConfig::from_paths`] loads it: includes merged, environment
+/// variables interpolated and values decrypted.
+///
+/// Errors are ignored, since the result is only used to detect
+/// unchanged values, see [`build_document`].
+#[cfg(feature = "wizard")]
+fn read_loaded_toml_file(path: &Path) -> Value {
+    let mut value =
+        read_toml_file(path, &mut Vec::new()).unwrap_or(Value::Table(Default::default()));
+    let _ = interpolate_env_vars(&mut value);
+
+    let decrypt_cmd = match value.get("decrypt-cmd") {
+        Some(Value::String(cmd)) => cmd.clone(),
+        _ => String::from(DEFAULT_DECRYPT_CMD),
+    };
+
+    let _ = decrypt_values(&mut value, &decrypt_cmd);
+    value
+}
+
+/// Build the document of the given configuration value.
+///
+/// When given, the existing configuration is made of the content of
+/// the file being replaced and of its loaded value, see
+/// [`read_loaded_toml_file`]. The existing document is then patched
+/// instead of being replaced, so that comments, formatting and
+/// untouched entries are kept:
+///
+/// - encrypted values and `${ENV_VAR}` occurrences are kept as long
+///   as their loaded value did not change, see
+///   [`restore_raw_values`]
+///
+/// - top-level keys unknown to the configuration, like `include` or
+///   `decrypt-cmd`, are kept
+///
+/// - entries coming from included files are not copied, unless they
+///   changed
+///
+/// The given function sets nested tables as dotted, see
+/// [`TomlConfig::set_table_dotted`].
+#[cfg(feature = "wizard")]
+fn build_document(
+    mut value: Value,
+    existing: Option<(&str, &Value)>,
+    set_table_dotted: fn(&mut toml_edit::Table),
+) -> Result<toml_edit::DocumentMut> {
+    let existing = existing.and_then(|(content, loaded)| {
+        let doc = content.parse::<toml_edit::DocumentMut>().ok()?;
+        let raw = content.parse::<Value>().ok()?;
+        Some((doc, raw, loaded))
+    });
+
+    if let Some((_, raw, loaded)) = &existing {
+        restore_raw_values(&mut value, raw, loaded);
+    }
+
+    let mut doc: toml_edit::DocumentMut = toml::to_string(&value)
+        .map_err(Error::SerializeTomlConfigError)?
+        .parse()
+        .map_err(Error::ParseSerializedTomlConfigError)?;
+
+    doc.iter_mut().for_each(|(_, item)| {
+        if let Some(table) = item.as_table_mut() {
+            table.iter_mut().for_each(|(_, item)| {
+                if let Some(table) = item.as_table_mut() {
+                    set_table_dotted(table);
+                }
+            })
+        }
+    });
+
+    let Some((mut existing_doc, raw, loaded)) = existing else {
+        return Ok(doc);
+    };
+
+    let empty = toml::Table::new();
+    let loaded = loaded.as_table().unwrap_or(&empty);
+
+    if let (Value::Table(raw), Value::Table(value)) = (&raw, &value) {
+        patch_table(
+            existing_doc.as_table_mut(),
+            raw,
+            doc.as_table(),
+            value,
+            loaded,
+            true,
+        );
+        doc = existing_doc;
+    }
+
+    Ok(doc)
+}
+
+/// Replace string values of the given new configuration value by
+/// their raw counterpart, when their loaded counterpart did not
+/// change.
+///
+/// Raw values are the ones written in the existing configuration
+/// file, like `encrypted:…` values or `${ENV_VAR}` occurrences, and
+/// loaded values are their decrypted or interpolated version. This
+/// prevents saving secrets in plain text.
+#[cfg(feature = "wizard")]
+fn restore_raw_values(value: &mut Value, raw: &Value, loaded: &Value) {
+    match (value, raw, loaded) {
+        (Value::Table(table), Value::Table(raw), Value::Table(loaded)) => {
+            for (key, value) in table.iter_mut() {
+                if let (Some(raw), Some(loaded)) = (raw.get(key), loaded.get(key)) {
+                    restore_raw_values(value, raw, loaded);
+                }
+            }
+        }
+        (Value::Array(values), Value::Array(raw), Value::Array(loaded))
+            if values.len() == raw.len() && values.len() == loaded.len() =>
+        {
+            for ((value, raw), loaded) in values.iter_mut().zip(raw).zip(loaded) {
+                restore_raw_values(value, raw, loaded);
+            }
+        }
+        (value, Value::String(_), loaded) if *value == *loaded => {
+            *value = raw.clone();
+        }
+        _ => (),
+    }
+}
+
+/// Patch the given existing document table with the given new one.
+///
+/// Keys whose value changed are replaced, and other keys are kept as
+/// they are. New keys are added, unless they come unchanged from an
+/// included file, which the given loaded table tells.
+///
+/// When `top_level` is `true`, sub-tables are patched one level
+/// down, so that only the touched entries of tables like `accounts`
+/// are replaced. Keys missing from the new table are only removed
+/// from these sub-tables: top-level keys are kept, since some of
+/// them are unknown to the configuration, like `include`.
+///
+/// Values are compared using their parsed counterparts, since
+/// documents also hold formatting.
+#[cfg(feature = "wizard")]
+fn patch_table(
+    doc: &mut toml_edit::Table,
+    value: &toml::Table,
+    new_doc: &toml_edit::Table,
+    new_value: &toml::Table,
+    loaded: &toml::Table,
+    top_level: bool,
+) {
+    if !top_level {
+        let removed_keys: Vec<String> = doc
+            .iter()
+            .map(|(key, _)| key.to_owned())
+            .filter(|key| !new_doc.contains_key(key))
+            .collect();
+
+        for key in removed_keys {
+            doc.remove(&key);
+        }
+    }
+
+    for (key, new_item) in new_doc.iter() {
+        let (old, new) = (value.get(key), new_value.get(key));
+
+        if old.is_some() && old == new {
+            continue;
+        }
+
+        if old.is_none() && loaded.get(key) == new {
+            continue;
+        }
+
+        match (doc.get_mut(key), old, new) {
+            (
+                Some(toml_edit::Item::Table(table)),
+                Some(Value::Table(old)),
+                Some(Value::Table(new)),
+            ) if top_level => {
+                if let Some(new_table) = new_item.as_table() {
+                    let empty = toml::Table::new();
+                    let loaded = match loaded.get(key) {
+                        Some(Value::Table(loaded)) => loaded,
+                        _ => &empty,
+                    };
+
+                    patch_table(table, old, new_table, new, loaded, false);
+                }
+            }
+            _ => {
+                doc.insert(key, new_item.clone());
+            }
+        }
+    }
+}
+
+/// Default number of backups kept by [`TomlConfig::write`].
+#[cfg(feature = "wizard")]
+pub const DEFAULT_BACKUP_RETENTION: usize = 5;
+
+/// Copy the file at the given path to `<path>.bak.<timestamp>`,
+/// where the timestamp is the number of seconds since the Unix
+/// epoch.
+///
+/// Only the given number of most recent backups are kept, older ones
+/// are removed. Returns the path of the backup, or `None` if the file
+/// does not exist or if the retention is 0.
+#[cfg(feature = "wizard")]
+pub fn backup_file(path: &Path, retention: usize) -> Result<Option<PathBuf>> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    if retention == 0 || !path.is_file() {
+        return Ok(None);
+    }
+
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+        return Ok(None);
+    };
+
+    let prefix = format!("{name}.bak.");
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let backup = path.with_file_name(format!("{prefix}{timestamp}"));
+
+    fs::copy(path, &backup).map_err(|err| Error::BackupTomlConfigError(err, backup.clone()))?;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut backups: Vec<(u64, PathBuf)> = fs::read_dir(dir)
+        .map_err(|err| Error::BackupTomlConfigError(err, backup.clone()))?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let timestamp = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix(&prefix)?
+                .parse()
+                .ok()?;
+            Some((timestamp, path))
+        })
+        .collect();
+
+    backups.sort();
+
+    let outdated = backups.len().saturating_sub(retention);
+    for (_, path) in backups.drain(..outdated) {
+        if let Err(_err) = fs::remove_file(&path) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(?path, ?_err, "cannot remove outdated config backup");
+        }
+    }
+
+    Ok(Some(backup))
+}
+
+/// Represents the supported configuration formats.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Yaml,
+    Json,
+}
+
+/// Replacement of redacted secrets.
+pub const REDACTED: &str = "<redacted>";
+
+/// Redact secrets of the given configuration value.
+///
+/// A secret is any string located under a key containing `passwd`,
+/// `password`, `secret` or `token`, or under a `raw` key like the
+/// ones holding passwords typed in wizards (`backend.auth.raw`).
+/// Commands (`cmd`) and keyring entries (`keyring`) are kept, since
+/// they only tell where secrets live.
+pub fn redact_secrets(value: &mut Value, is_secret: bool) {
+    match value {
+        Value::String(string) if is_secret => {
+            *string = REDACTED.to_owned();
+        }
+        Value::Array(values) => {
+            for value in values {
+                redact_secrets(value, is_secret);
+            }
+        }
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                match secret_key(key) {
+                    SecretKey::Location => continue,
+                    SecretKey::Secret => redact_secrets(value, true),
+                    SecretKey::Other => redact_secrets(value, is_secret),
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Redact secrets of the given configuration document table, see
+/// [`redact_secrets`].
+///
+/// Unlike the latter, comments and formatting are kept.
+#[cfg(feature = "wizard")]
+fn redact_table_secrets(table: &mut toml_edit::Table, is_secret: bool) {
+    for (key, item) in table.iter_mut() {
+        let is_secret = match secret_key(key.get()) {
+            SecretKey::Location => continue,
+            SecretKey::Secret => true,
+            SecretKey::Other => is_secret,
+        };
+
+        match item {
+            toml_edit::Item::Value(value) => redact_value_secrets(value, is_secret),
+            toml_edit::Item::Table(table) => redact_table_secrets(table, is_secret),
+            toml_edit::Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    redact_table_secrets(table, is_secret);
+                }
+            }
+            toml_edit::Item::None => (),
+        }
+    }
+}
+
+#[cfg(feature = "wizard")]
+fn redact_value_secrets(value: &mut toml_edit::Value, is_secret: bool) {
+    match value {
+        toml_edit::Value::String(string) if is_secret => {
+            let decor = string.decor().clone();
+            *string = toml_edit::Formatted::new(REDACTED.to_owned());
+            *string.decor_mut() = decor;
+        }
+        toml_edit::Value::Array(values) => {
+            for value in values.iter_mut() {
+                redact_value_secrets(value, is_secret);
+            }
+        }
+        toml_edit::Value::InlineTable(table) => {
+            for (key, value) in table.iter_mut() {
+                let is_secret = match secret_key(key.get()) {
+                    SecretKey::Location => continue,
+                    SecretKey::Secret => true,
+                    SecretKey::Other => is_secret,
+                };
+
+                redact_value_secrets(value, is_secret);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Kind of configuration key, regarding secrets.
+enum SecretKey {
+    /// The key holds a secret.
+    Secret,
+    /// The key tells where a secret lives.
+    Location,
+    /// The key is not related to secrets.
+    Other,
+}
+
+fn secret_key(key: &str) -> SecretKey {
+    const SECRET_KEYS: [&str; 4] = ["passwd", "password", "secret", "token"];
+
+    let key = key.to_ascii_lowercase();
+
+    if key == "cmd" || key == "keyring" {
+        SecretKey::Location
+    } else if key == "raw" || SECRET_KEYS.iter().any(|k| key.contains(k)) {
+        SecretKey::Secret
+    } else {
+        SecretKey::Other
+    }
+}
+
+/// Parse the given configuration content, using the format matching
+/// the extension of the given path.
+///
+/// Files ending with `.yaml` or `.yml` are parsed as YAML, files
+/// ending with `.json` are parsed as JSON, other files are parsed as
+/// TOML. All formats are converted to the same TOML value, so they
+/// share the same structure.
+pub fn parse_config_file(path: &Path, content: &str) -> Result<Value> {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match ext.as_deref() {
+        Some("yaml" | "yml") => serde_yaml::from_str(content)
+            .map_err(|err| Error::ParseYamlConfigFile(err, path.to_owned())),
+        Some("json") => serde_json::from_str(content)
+            .map_err(|err| Error::ParseJsonConfigFile(err, path.to_owned())),
+        _ => content
+            .parse()
+            .map_err(|err| Error::ParseTomlConfigFile(err, path.to_owned(), None)),
+    }
+}
+
+/// Build a hint for the given error if it is caused by an unknown
+/// field: the path of the field in the given configuration, and the
+/// closest valid field name.
+fn suggest_unknown_field(err: &toml::de::Error, config: &Value) -> Option<String> {
+    let mut fields = unknown_fields(err)?;
+    let unknown = fields.next()?;
+
+    let mut hint = match find_key_path(config, unknown) {
+        Some(path) => format!("unknown field `{}`", path.join(".")),
+        None => format!("unknown field `{unknown}`"),
+    };
+
+    let closest = fields
+        .map(|field| (levenshtein(unknown, field), field))
+        .filter(|(distance, field)| *distance <= field.len().max(unknown.len()) / 2)
+        .min();
+
+    if let Some((_, field)) = closest {
+        hint.push_str(&format!(", did you mean `{field}`?"));
+    }
+
+    Some(hint)
+}
+
+/// Return the fields of the given error if it is caused by an
+/// unknown field: the unknown field comes first, then the expected
+/// ones.
+fn unknown_fields(err: &toml::de::Error) -> Option<impl Iterator<Item = &str>> {
+    let rest = err.message().strip_prefix("unknown field ")?;
+    // fields are surrounded by backticks
+    Some(rest.split('`').skip(1).step_by(2))
+}
+
+/// Find the file defining the unknown field of the given error,
+/// among the given merged files.
+///
+/// Files are searched from the last one, since the content of later
+/// files overrides the content of earlier ones.
+fn find_unknown_field_file<'a>(
+    err: &toml::de::Error,
+    config: &Value,
+    contents: &[(&'a PathBuf, Value)],
+) -> Option<&'a PathBuf> {
+    let unknown = unknown_fields(err)?.next()?;
+    let path = find_key_path(config, unknown)?;
+
+    contents
+        .iter()
+        .rev()
+        .find(|(_, content)| has_key_path(content, &path))
+        .map(|(path, _)| *path)
+}
+
+/// Find the path of the first occurrence of the given key in the
+/// given value, one segment per nested table.
+fn find_key_path(value: &Value, key: &str) -> Option<Vec<String>> {
+    let table = value.as_table()?;
+
+    if table.contains_key(key) {
+        return Some(vec![key.to_owned()]);
+    }
+
+    table.iter().find_map(|(name, value)| {
+        let mut path = find_key_path(value, key)?;
+        path.insert(0, name.clone());
+        Some(path)
+    })
+}
+
+/// Return `true` if the given value contains the given key path.
+fn has_key_path(value: &Value, path: &[String]) -> bool {
+    match path.split_first() {
+        None => true,
+        Some((key, path)) => value
+            .as_table()
+            .and_then(|table| table.get(key))
+            .is_some_and(|value| has_key_path(value, path)),
+    }
+}
+
+/// Compute the Levenshtein distance between the given strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Resolve the given include against the given directory, then
+/// expand wildcards of its file name.
+fn expand_include(dir: &Path, include: &str) -> Result<Vec<PathBuf>> {
+    let path = match include.strip_prefix("~/") {
+        Some(rest) => home_dir().unwrap_or_default().join(rest),
+        None => dir.join(include),
+    };
+
+    let Some(pattern) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(vec![path]);
+    };
+
+    if !pattern.contains(['*', '?']) {
+        return Ok(vec![path]);
+    }
+
+    let dir = path.parent().unwrap_or(dir);
+    let entries =
+        fs::read_dir(dir).map_err(|err| Error::ReadTomlConfigFile(err, dir.to_owned()))?;
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| matches_wildcard(pattern, name))
+        })
+        .collect();
+
+    paths.sort();
+    Ok(paths)
+}
+
+/// Match the given name against the given pattern, where `*` matches
+/// any sequence of characters and `?` matches any character.
+fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Prefix of encrypted configuration values.
+pub const ENCRYPTED_PREFIX: &str = "encrypted:";
+
+/// Command used to decrypt values when the configuration does not
+/// define a top-level `decrypt-cmd`.
+pub const DEFAULT_DECRYPT_CMD: &str = "gpg --decrypt --quiet --batch";
+
+/// Command used by wizards to encrypt values, see [`encrypt_value`].
+#[cfg(feature = "wizard")]
+pub const DEFAULT_ENCRYPT_CMD: &str =
+    "gpg --encrypt --armor --quiet --batch --default-recipient-self";
+
+/// Decrypt all string values of the given TOML value starting with
+/// [`ENCRYPTED_PREFIX`].
+///
+/// The ASCII-armored ciphertext following the prefix is piped to the
+/// given shell command, whose output replaces the value:
+///
+/// ```toml
+/// decrypt-cmd = "age --decrypt --identity ~/.config/age/key.txt"
+///
+/// [accounts.example.backend.auth]
+/// type = "password"
+/// raw = """encrypted:-----BEGIN AGE ENCRYPTED FILE-----
+/// …
+/// -----END AGE ENCRYPTED FILE-----"""
+/// ```
+///
+/// Returns an error if the command fails.
+pub fn decrypt_values(value: &mut Value, cmd: &str) -> Result<()> {
+    match value {
+        Value::String(string) => {
+            if let Some(ciphertext) = string.strip_prefix(ENCRYPTED_PREFIX) {
+                *string = decrypt_value(ciphertext, cmd)?;
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                decrypt_values(value, cmd)?;
+            }
+        }
+        Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                decrypt_values(value, cmd)?;
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+fn decrypt_value(ciphertext: &str, cmd: &str) -> Result<String> {
+    pipe_value(
+        ciphertext.trim(),
+        cmd,
+        Error::DecryptTomlConfigValueError,
+        Error::DecryptTomlConfigValueCommandError,
+    )
+}
+
+/// Encrypt the given value by piping it to the given shell command.
+///
+/// The ASCII-armored output can be stored in the configuration,
+/// prefixed by [`ENCRYPTED_PREFIX`], see [`decrypt_values`].
+#[cfg(feature = "wizard")]
+pub fn encrypt_value(plaintext: &str, cmd: &str) -> Result<String> {
+    pipe_value(
+        plaintext,
+        cmd,
+        Error::EncryptTomlConfigValueError,
+        Error::EncryptTomlConfigValueCommandError,
+    )
+}
+
+/// Pipe the given input to the given shell command, then return its
+/// output without trailing newlines.
+fn pipe_value(
+    input: &str,
+    cmd: &str,
+    spawn_err: fn(std::io::Error, String) -> Error,
+    cmd_err: fn(String, String) -> Error,
+) -> Result<String> {
+    use std::{
+        io::{self, Write},
+        process::{Command, Stdio},
+    };
+
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", cmd]);
+        command
+    };
+
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = Command::new("sh");
+        command.args(["-c", cmd]);
+        command
+    };
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| spawn_err(err, cmd.to_owned()))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(input.as_bytes()) {
+            // the command exited without reading its input, its exit
+            // status tells why
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => (),
+            Err(err) => return Err(spawn_err(err, cmd.to_owned())),
+            Ok(()) => (),
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|err| spawn_err(err, cmd.to_owned()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        return Err(cmd_err(cmd.to_owned(), stderr));
+    }
+
+    let output = String::from_utf8_lossy(&output.stdout);
+    Ok(output.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// Replace `${ENV_VAR}` occurrences in all string values of the given
+/// TOML value by the value of the matching environment variable.
+///
+/// Occurrences can be escaped with a double dollar: `$${ENV_VAR}` is
+/// replaced by the literal `${ENV_VAR}`. Occurrences which are not
+/// plain variable names, like `${ENV_VAR:-default}`, are kept as they
+/// are.
+///
+/// Shell commands, under keys named `cmd` or ending with `-cmd` or
+/// `-hook`, are not interpolated: they are expanded by the shell
+/// running them, which may define variables of its own.
+///
+/// Returns an error if an environment variable is not defined.
+pub fn interpolate_env_vars(value: &mut Value) -> Result<()> {
+    match value {
+        Value::String(string) if string.contains('$') => {
+            *string = interpolate_env_vars_in_str(string)?;
+        }
+        Value::Array(values) => {
+            for value in values {
+                interpolate_env_vars(value)?;
+            }
+        }
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                if is_shell_command_key(key) {
+                    continue;
+                }
+
+                interpolate_env_vars(value)?;
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+fn is_shell_command_key(key: &str) -> bool {
+    key == "cmd" || key.ends_with("-cmd") || key.ends_with("-hook")
+}
+
+fn is_env_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn interpolate_env_vars_in_str(input: &str) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            output.push_str("${");
+            rest = escaped;
+            continue;
+        }
+
+        let name = rest
+            .strip_prefix("${")
+            .and_then(|name| name.split_once('}'))
+            .map(|(name, _)| name)
+            .filter(|name| is_env_var_name(name));
+
+        match name {
+            Some(name) => {
+                let value = env::var(name)
+                    .map_err(|_| Error::InterpolateTomlConfigEnvVarError(name.to_owned()))?;
+                output.push_str(&value);
+                rest = &rest[name.len() + 3..];
+            }
+            None => {
+                output.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use toml::Value;
+
+    use super::{find_unknown_field_file, interpolate_env_vars, redact_secrets, REDACTED};
+
+    #[test]
+    fn interpolate_env_vars_replaces_variables() {
+        env::set_var("PIMALAYA_TUI_TEST_USER", "alice");
+
+        let mut value: Value = toml::from_str(
+            r#"
+            login = "${PIMALAYA_TUI_TEST_USER}@localhost"
+            escaped = "$${PIMALAYA_TUI_TEST_USER}"
+            default = "${PIMALAYA_TUI_TEST_USER:-bob}"
+            dollar = "$5 and $"
+            "#,
+        )
+        .unwrap();
+
+        interpolate_env_vars(&mut value).unwrap();
+
+        assert_eq!(value["login"].as_str(), Some("alice@localhost"));
+        assert_eq!(value["escaped"].as_str(), Some("${PIMALAYA_TUI_TEST_USER}"));
+        assert_eq!(
+            value["default"].as_str(),
+            Some("${PIMALAYA_TUI_TEST_USER:-bob}")
+        );
+        assert_eq!(value["dollar"].as_str(), Some("$5 and $"));
+    }
+
+    #[test]
+    fn interpolate_env_vars_skips_shell_commands() {
+        env::remove_var("PIMALAYA_TUI_TEST_UNDEFINED");
+
+        let mut value: Value = toml::from_str(
+            r#"
+            [auth]
+            cmd = "pass show ${PIMALAYA_TUI_TEST_UNDEFINED}"
+            [pgp]
+            encrypt-cmd = "gpg -r ${PIMALAYA_TUI_TEST_UNDEFINED}"
+            "#,
+        )
+        .unwrap();
+
+        interpolate_env_vars(&mut value).unwrap();
+
+        assert_eq!(
+            value["auth"]["cmd"].as_str(),
+            Some("pass show ${PIMALAYA_TUI_TEST_UNDEFINED}")
+        );
+        assert_eq!(
+            value["pgp"]["encrypt-cmd"].as_str(),
+            Some("gpg -r ${PIMALAYA_TUI_TEST_UNDEFINED}")
+        );
+    }
+
+    #[test]
+    fn interpolate_env_vars_fails_on_missing_variable() {
+        env::remove_var("PIMALAYA_TUI_TEST_MISSING");
+
+        let mut value: Value = toml::from_str(r#"login = "${PIMALAYA_TUI_TEST_MISSING}""#).unwrap();
+
+        assert!(interpolate_env_vars(&mut value).is_err());
+    }
+
+    #[test]
+    fn find_unknown_field_file_among_merged_files() {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        #[allow(dead_code)]
+        struct Account {
+            email: Option<String>,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Config {
+            accounts: std::collections::HashMap<String, Account>,
+        }
+
+        let main = std::path::PathBuf::from("config.toml");
+        let sub = std::path::PathBuf::from("work.toml");
+
+        let contents = [
+            (
+                &main,
+                toml::from_str("accounts.home.email = 'a@localhost'").unwrap(),
+            ),
+            (
+                &sub,
+                toml::from_str("accounts.work.emial = 'b@localhost'").unwrap(),
+            ),
+        ];
+
+        let config: Value = toml::from_str(
+            r#"
+            accounts.home.email = "a@localhost"
+            accounts.work.emial = "b@localhost"
+            "#,
+        )
+        .unwrap();
+
+        let err = config.clone().try_into::<Config>().unwrap_err();
+
+        assert_eq!(
+            find_unknown_field_file(&err, &config, &contents),
+            Some(&sub)
+        );
+    }
+
+    #[test]
+    fn redact_secrets_redacts_raw_passwords() {
+        let mut value: Value = toml::from_str(
+            r#"
+            [accounts.example]
+            backend.type = "imap"
+            backend.login = "alice"
+            backend.auth.type = "password"
+            backend.auth.raw = "p4ssw0rd"
+            message.send.backend.type = "smtp"
+            message.send.backend.auth.type = "password"
+            message.send.backend.auth.raw = "p4ssw0rd"
+
+            [accounts.other]
+            backend.auth.type = "password"
+            backend.auth.cmd = "pass show other"
+            message.send.backend.auth.keyring = "other-smtp"
+            "#,
+        )
+        .unwrap();
+
+        redact_secrets(&mut value, false);
+
+        let example = &value["accounts"]["example"];
+        assert_eq!(example["backend"]["login"].as_str(), Some("alice"));
+        assert_eq!(
+            example["backend"]["auth"]["type"].as_str(),
+            Some("password")
+        );
+        assert_eq!(example["backend"]["auth"]["raw"].as_str(), Some(REDACTED));
+        assert_eq!(
+            example["message"]["send"]["backend"]["auth"]["raw"].as_str(),
+            Some(REDACTED)
+        );
+
+        let other = &value["accounts"]["other"];
+        assert_eq!(
+            other["backend"]["auth"]["cmd"].as_str(),
+            Some("pass show other")
+        );
+        assert_eq!(
+            other["message"]["send"]["backend"]["auth"]["keyring"].as_str(),
+            Some("other-smtp")
+        );
+    }
+
+    #[cfg(feature = "wizard")]
+    #[test]
+    fn redact_table_secrets_keeps_formatting() {
+        let mut doc: toml_edit::DocumentMut = r#"
+# main account
+[accounts.example]
+backend.auth.type = "password"
+backend.auth.raw = "p4ssw0rd" # typed in the wizard
+message.send.backend.auth = { type = "password", raw = "p4ssw0rd" }
+pgp.secret-keys = ["key1", "key2"]
+"#
+        .parse()
+        .unwrap();
+
+        super::redact_table_secrets(doc.as_table_mut(), false);
+
+        assert_eq!(
+            doc.to_string(),
+            r#"
+# main account
+[accounts.example]
+backend.auth.type = "password"
+backend.auth.raw = "<redacted>" # typed in the wizard
+message.send.backend.auth = { type = "password", raw = "<redacted>" }
+pgp.secret-keys = ["<redacted>", "<redacted>"]
+"#
+        );
+    }
+
+    #[cfg(all(feature = "wizard", unix))]
+    fn write_temp_files(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = env::temp_dir().join(format!("pimalaya-tui-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (file, content) in files {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+
+        dir
+    }
+
+    #[cfg(all(feature = "wizard", unix))]
+    fn set_table_dotted(table: &mut toml_edit::Table) {
+        table.set_dotted(true);
+    }
+
+    #[cfg(all(feature = "wizard", unix))]
+    #[test]
+    fn build_document_keeps_raw_values() {
+        env::set_var("PIMALAYA_TUI_TEST_EMAIL", "alice@localhost");
+
+        let existing = r#"decrypt-cmd = "tr a-z A-Z"
+
+[accounts.example]
+email = "${PIMALAYA_TUI_TEST_EMAIL}"
+backend.auth.type = "password"
+backend.auth.raw = "encrypted:p4ssw0rd"
+"#;
+
+        let dir = write_temp_files("raw-values", &[("config.toml", existing)]);
+        let loaded = super::read_loaded_toml_file(&dir.join("config.toml"));
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let auth = &loaded["accounts"]["example"]["backend"]["auth"];
+        assert_eq!(auth["raw"].as_str(), Some("P4SSW0RD"));
+
+        // the wizard only changes the display name
+        let mut value = loaded.clone();
+        value.as_table_mut().unwrap().remove("decrypt-cmd");
+        value["accounts"]["example"]
+            .as_table_mut()
+            .unwrap()
+            .insert("display-name".into(), Value::from("Alice"));
+
+        let doc = super::build_document(value, Some((existing, &loaded)), set_table_dotted)
+            .unwrap()
+            .to_string();
+        let doc: Value = toml::from_str(&doc).unwrap();
+
+        let example = &doc["accounts"]["example"];
+        assert_eq!(example["display-name"].as_str(), Some("Alice"));
+        assert_eq!(
+            example["email"].as_str(),
+            Some("${PIMALAYA_TUI_TEST_EMAIL}")
+        );
+        assert_eq!(
+            example["backend"]["auth"]["raw"].as_str(),
+            Some("encrypted:p4ssw0rd")
+        );
+    }
+
+    #[cfg(all(feature = "wizard", unix))]
+    #[test]
+    fn build_document_keeps_top_level_keys_and_includes() {
+        let existing = r#"include = "accounts.toml"
+decrypt-cmd = "tr a-z A-Z"
+
+# main account
+[accounts.example]
+email = "alice@localhost"
+"#;
+
+        let included = r#"[accounts.work]
+email = "alice@work"
+"#;
+
+        let dir = write_temp_files(
+            "includes",
+            &[("config.toml", existing), ("accounts.toml", included)],
+        );
+        let loaded = super::read_loaded_toml_file(&dir.join("config.toml"));
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(
+            loaded["accounts"]["work"]["email"].as_str(),
+            Some("alice@work")
+        );
+
+        // the wizard adds a new account
+        let mut value = loaded.clone();
+        value.as_table_mut().unwrap().remove("decrypt-cmd");
+        value["accounts"].as_table_mut().unwrap().insert(
+            "personal".into(),
+            toml::from_str("email = \"alice@home\"").unwrap(),
+        );
+
+        let doc = super::build_document(value, Some((existing, &loaded)), set_table_dotted)
+            .unwrap()
+            .to_string();
+
+        assert!(doc.contains("# main account"));
+
+        let doc: Value = toml::from_str(&doc).unwrap();
+        assert_eq!(doc["include"].as_str(), Some("accounts.toml"));
+        assert_eq!(doc["decrypt-cmd"].as_str(), Some("tr a-z A-Z"));
+
+        let accounts = doc["accounts"].as_table().unwrap();
+        assert!(accounts.contains_key("example"));
+        assert!(accounts.contains_key("personal"));
+        assert!(!accounts.contains_key("work"));
+    }
+
+    fn config_dir_paths(dir: &str) -> Vec<std::path::PathBuf> {
+        ["toml", "yaml", "yml", "json"]
+            .into_iter()
+            .map(|ext| std::path::Path::new(dir).join(format!("himalaya/config.{ext}")))
+            .collect()
+    }
+
+    #[test]
+    fn config_paths_on_linux() {
+        let dirs = super::ConfigDirs {
+            xdg_config_home: Some("/tmp/xdg".into()),
+            config_dir: Some("/home/alice/.config".into()),
+            home: Some("/home/alice".into()),
+            ..Default::default()
+        };
+
+        let mut expected = config_dir_paths("/tmp/xdg");
+        expected.extend(config_dir_paths("/home/alice/.config"));
+        expected.push("/home/alice/.himalayarc".into());
+
+        assert_eq!(dirs.config_paths("himalaya"), expected);
+    }
+
+    #[test]
+    fn config_paths_on_macos() {
+        let dirs = super::ConfigDirs {
+            xdg_config_home: Some("/tmp/xdg".into()),
+            config_dir: Some("/Users/alice/Library/Application Support".into()),
+            home: Some("/Users/alice".into()),
+            macos: true,
+            ..Default::default()
+        };
+
+        // the XDG layout takes precedence over the config dir, which
+        // is not duplicated at the end
+        let mut expected = config_dir_paths("/tmp/xdg");
+        expected.extend(config_dir_paths("/Users/alice/Library/Application Support"));
+        expected.extend(config_dir_paths("/Users/alice/.config"));
+        expected.push("/Users/alice/.himalayarc".into());
+
+        assert_eq!(dirs.config_paths("himalaya"), expected);
+    }
+
+    #[test]
+    fn config_paths_on_windows() {
+        let dirs = super::ConfigDirs {
+            xdg_config_home: None,
+            config_dir: Some("/Users/alice/AppData/Roaming".into()),
+            home: Some("/Users/alice".into()),
+            app_data: Some("/Users/alice/AppData/Roaming".into()),
+            ..Default::default()
+        };
+
+        let mut expected = config_dir_paths("/Users/alice/AppData/Roaming");
+        expected.extend(config_dir_paths("/Users/alice/.config"));
+        expected.push("/Users/alice/.himalayarc".into());
+
+        assert_eq!(dirs.config_paths("himalaya"), expected);
+    }
+}
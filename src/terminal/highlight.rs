@@ -0,0 +1,62 @@
+use crossterm::style::Stylize;
+
+/// Highlight the given TOML document for the terminal.
+///
+/// Highlighting is line-based: comments, table headers, keys and
+/// single-line values are styled. Lines of multi-line values are
+/// rendered as they are.
+pub fn toml(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for line in input.lines() {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+
+        if trimmed.starts_with('#') {
+            output.push_str(&format!("{indent}{}", trimmed.dark_grey()));
+        } else if trimmed.starts_with('[') {
+            output.push_str(&format!("{indent}{}", trimmed.magenta().bold()));
+        } else if let Some((key, value)) = key_value(trimmed) {
+            output.push_str(&format!(
+                "{indent}{} = {}",
+                key.trim_end().cyan(),
+                style_value(value.trim_start())
+            ));
+        } else {
+            output.push_str(line);
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Split the given line at the first `=` that is not quoted.
+fn key_value(line: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '=') => return Some((&line[..i], &line[i + 1..])),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+fn style_value(value: &str) -> String {
+    if value.starts_with('"') || value.starts_with('\'') {
+        format!("{}", value.green())
+    } else if value == "true"
+        || value == "false"
+        || value.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+')
+    {
+        format!("{}", value.yellow())
+    } else {
+        value.to_owned()
+    }
+}
@@ -0,0 +1,165 @@
+use crossterm::style::Stylize;
+
+/// Render the given markdown text for the terminal.
+///
+/// Only a subset of markdown is supported: ATX headings, bullet and
+/// ordered lists, block quotes, fenced code blocks, and inline
+/// emphasis (`*italic*`, `**bold**`, `` `code` ``). Anything else is
+/// rendered as it is.
+pub fn render(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut in_code_block = false;
+
+    for line in input.lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block {
+            output.push_str(&format!("    {}", line.dark_grey()));
+        } else if let Some((level, title)) = heading(trimmed) {
+            let title = render_inline(title);
+            match level {
+                1 => output.push_str(&format!("{}", title.bold().underlined())),
+                2 => output.push_str(&format!("{}", title.bold())),
+                _ => output.push_str(&format!("{}", title.underlined())),
+            }
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            let quote = render_inline(quote.trim_start());
+            output.push_str(&format!("{} {}", "│".dark_grey(), quote.italic()));
+        } else if let Some(item) = bullet(trimmed) {
+            let indent = &line[..line.len() - trimmed.len()];
+            output.push_str(&format!("{indent}• {}", render_inline(item)));
+        } else if let Some((n, item)) = numbered(trimmed) {
+            let indent = &line[..line.len() - trimmed.len()];
+            output.push_str(&format!("{indent}{n}. {}", render_inline(item)));
+        } else {
+            output.push_str(&render_inline(line));
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    line[level..]
+        .strip_prefix(' ')
+        .map(|title| (level, title.trim_end_matches('#').trim()))
+}
+
+fn bullet(line: &str) -> Option<&str> {
+    line.strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+}
+
+fn numbered(line: &str) -> Option<(&str, &str)> {
+    let (n, item) = line.split_once(". ")?;
+
+    if n.is_empty() || !n.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((n, item))
+}
+
+fn render_inline(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let styled = if let Some(inner) = delimited(rest, "`") {
+            Some((inner, format!("{}", inner.cyan()), 2))
+        } else if let Some(inner) = delimited(rest, "**").or_else(|| delimited(rest, "__")) {
+            Some((inner, format!("{}", render_inline(inner).bold()), 4))
+        } else {
+            delimited(rest, "*")
+                .or_else(|| delimited(rest, "_"))
+                .map(|inner| (inner, format!("{}", render_inline(inner).italic()), 2))
+        };
+
+        match styled {
+            Some((inner, styled, delimiters_len)) => {
+                output.push_str(&styled);
+                rest = &rest[inner.len() + delimiters_len..];
+            }
+            None => {
+                let c = rest.chars().next().unwrap();
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    output
+}
+
+/// Return the text enclosed by the given delimiter at the beginning
+/// of the input, if any.
+fn delimited<'a>(input: &'a str, delimiter: &str) -> Option<&'a str> {
+    let rest = input.strip_prefix(delimiter)?;
+    let end = rest.find(delimiter)?;
+    let inner = &rest[..end];
+
+    if inner.is_empty() || inner.starts_with(' ') || inner.ends_with(' ') {
+        return None;
+    }
+
+    Some(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Stylize;
+
+    use super::render;
+
+    #[test]
+    fn render_blocks() {
+        let input =
+            "# Title\n- item\n  * nested\n12. twelfth\n> quote\n```\nlet a = 1;\n```\nplain";
+
+        let expected = [
+            format!("{}", "Title".bold().underlined()),
+            String::from("• item"),
+            String::from("  • nested"),
+            String::from("12. twelfth"),
+            format!("{} {}", "│".dark_grey(), "quote".italic()),
+            format!("    {}", "let a = 1;".dark_grey()),
+            String::from("plain"),
+        ];
+
+        assert_eq!(render(input), expected.join("\n") + "\n");
+    }
+
+    #[test]
+    fn render_inline_styles() {
+        let expected = format!(
+            "{} {} {} * not italic *",
+            "code".cyan(),
+            "bold".bold(),
+            "italic".italic()
+        );
+
+        assert_eq!(
+            render("`code` **bold** _italic_ * not italic *"),
+            expected + "\n"
+        );
+    }
+
+    #[test]
+    fn render_keeps_unsupported_syntax() {
+        assert_eq!(render("#hashtag\n1.5 times"), "#hashtag\n1.5 times\n");
+    }
+}
@@ -1,7 +1,12 @@
+pub mod answers;
+pub mod autocomplete;
 #[cfg(feature = "cli")]
 pub mod cli;
+pub mod color;
 #[cfg(feature = "config")]
 pub mod config;
+pub mod highlight;
+pub mod markdown;
 pub mod print;
 pub mod prompt;
 #[cfg(feature = "tracing")]
@@ -9,3 +14,4 @@ pub mod tracing;
 pub mod validator;
 #[cfg(feature = "wizard")]
 pub mod wizard;
+pub mod wrap;
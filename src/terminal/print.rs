@@ -1,15 +1,459 @@
-use crossterm::style::Stylize;
+use std::{
+    env, fmt,
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    execute,
+    style::{Print, Stylize},
+    terminal::{Clear, ClearType},
+};
+
+use super::{color, highlight, markdown, wrap};
+
+/// Output stream messages are written to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Target {
+    Stdout,
+    /// Default target of diagnostics, so that they do not pollute
+    /// data written to stdout.
+    #[default]
+    Stderr,
+}
+
+impl Target {
+    fn is_terminal(&self) -> bool {
+        match self {
+            Self::Stdout => io::stdout().is_terminal(),
+            Self::Stderr => io::stderr().is_terminal(),
+        }
+    }
+
+    /// Initialize colors for this target, see [`color::init_for`].
+    fn init_color(&self) {
+        color::init_for(self.is_terminal());
+    }
+
+    fn write(&self, text: impl fmt::Display) {
+        // like println!, but without panicking on closed pipes
+        let _ = match self {
+            Self::Stdout => writeln!(io::stdout(), "{text}"),
+            Self::Stderr => writeln!(io::stderr(), "{text}"),
+        };
+    }
+}
+
+/// Print messages to the given target instead of the default one.
+///
+/// ```rust,ignore
+/// print::to(Target::Stdout).warn("Written to stdout");
+/// ```
+pub fn to(target: Target) -> Messages {
+    Messages { target }
+}
+
+/// Messages printed to a specific [`Target`], see [`to`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Messages {
+    target: Target,
+}
+
+impl Messages {
+    pub fn warn(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = fill(text.as_ref(), 0).dark_yellow().bold();
+        self.target.write(text);
+    }
+
+    pub fn question(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        self.target.write(fill(text.as_ref(), 0).italic());
+    }
+
+    pub fn section(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = fill(text.as_ref(), 0).underlined();
+        self.target.write(format_args!("\n{text}\n"));
+    }
+
+    pub fn success(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = fill(text.as_ref(), 2).green();
+        self.target
+            .write(format_args!("{} {text}", "✓".green().bold()));
+    }
+
+    pub fn error(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = fill(text.as_ref(), 2).red();
+        self.target
+            .write(format_args!("{} {text}", "✗".red().bold()));
+    }
+
+    pub fn info(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = fill(text.as_ref(), 2);
+        self.target
+            .write(format_args!("{} {text}", "•".blue().bold()));
+    }
+
+    pub fn hint(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = fill(text.as_ref(), 2).dark_grey().italic();
+        self.target
+            .write(format_args!("{} {text}", "›".dark_grey()));
+    }
+
+    /// Print the given markdown text, see [`markdown::render`].
+    pub fn markdown(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = fill(&markdown::render(text.as_ref()), 0);
+        self.target.write(text.trim_end_matches('\n'));
+    }
+
+    /// Print the given TOML document, see [`highlight::toml`].
+    pub fn toml(&self, text: impl AsRef<str>) {
+        self.target.init_color();
+        let text = highlight::toml(text.as_ref());
+        self.target.write(text.trim_end_matches('\n'));
+    }
+
+    /// Print a terminal hyperlink.
+    ///
+    /// The link is emitted as an OSC 8 sequence when the terminal
+    /// supports it, otherwise the label is printed followed by the
+    /// raw URL.
+    pub fn link(&self, label: impl AsRef<str>, url: impl AsRef<str>) {
+        self.target.init_color();
+        let (label, url) = (label.as_ref(), url.as_ref());
+
+        if supports_hyperlinks(self.target) {
+            let label = label.underlined();
+            self.target
+                .write(format_args!("\x1b]8;;{url}\x1b\\{label}\x1b]8;;\x1b\\"));
+        } else if label == url {
+            self.target.write(url);
+        } else {
+            self.target.write(format_args!("{label}: {url}"));
+        }
+    }
+}
 
 pub fn warn(text: impl AsRef<str>) {
-    println!("{}", text.as_ref().dark_yellow().bold());
+    to(Target::Stderr).warn(text)
 }
 
 pub fn question(text: impl AsRef<str>) {
-    println!("{}", text.as_ref().italic());
+    to(Target::Stderr).question(text)
 }
 
 pub fn section(text: impl AsRef<str>) {
-    println!();
-    println!("{}", text.as_ref().underlined());
-    println!();
+    to(Target::Stderr).section(text)
+}
+
+pub fn success(text: impl AsRef<str>) {
+    to(Target::Stderr).success(text)
+}
+
+pub fn error(text: impl AsRef<str>) {
+    to(Target::Stderr).error(text)
+}
+
+pub fn info(text: impl AsRef<str>) {
+    to(Target::Stderr).info(text)
+}
+
+pub fn hint(text: impl AsRef<str>) {
+    to(Target::Stderr).hint(text)
+}
+
+/// Print the given markdown text to stdout.
+pub fn markdown(text: impl AsRef<str>) {
+    to(Target::Stdout).markdown(text)
+}
+
+pub fn toml(text: impl AsRef<str>) {
+    to(Target::Stderr).toml(text)
+}
+
+pub fn link(label: impl AsRef<str>, url: impl AsRef<str>) {
+    to(Target::Stderr).link(label, url)
+}
+
+/// Wrap the given text at the terminal width, indenting continuation
+/// lines by the given offset.
+fn fill(text: &str, indent: usize) -> String {
+    let width = wrap::width().saturating_sub(indent);
+    let indent = format!("\n{}", " ".repeat(indent));
+    wrap::fill(text, width).replace('\n', &indent)
+}
+
+/// Guess whether the terminal attached to the given target supports
+/// OSC 8 hyperlinks.
+///
+/// The guess can be overridden by setting `FORCE_HYPERLINK` to `1`
+/// or `0`.
+fn supports_hyperlinks(target: Target) -> bool {
+    if let Ok(force) = env::var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+
+    if !target.is_terminal() {
+        return false;
+    }
+
+    let has = |key| env::var_os(key).is_some();
+
+    if has("WT_SESSION") || has("KONSOLE_VERSION") || has("DOMTERM") {
+        return true;
+    }
+
+    if let Ok(version) = env::var("VTE_VERSION") {
+        return version.parse::<u32>().is_ok_and(|version| version >= 5000);
+    }
+
+    if let Ok(program) = env::var("TERM_PROGRAM") {
+        return matches!(
+            program.as_str(),
+            "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+        );
+    }
+
+    env::var("TERM").is_ok_and(|term| {
+        ["kitty", "alacritty", "foot", "wezterm"]
+            .iter()
+            .any(|name| term.contains(name))
+    })
+}
+
+/// Create a new progress bar with the given label and total.
+pub fn progress(label: impl ToString, total: u64) -> ProgressBar {
+    ProgressBar::new(label, total)
+}
+
+/// Progress bar rendered on stderr.
+///
+/// The bar is only drawn when stderr is a terminal, so that progress
+/// does not pollute redirected outputs.
+#[derive(Debug)]
+pub struct ProgressBar {
+    label: String,
+    total: u64,
+    position: u64,
+    bytes: bool,
+    started_at: Instant,
+    visible: bool,
+}
+
+impl ProgressBar {
+    const WIDTH: u64 = 30;
+
+    pub fn new(label: impl ToString, total: u64) -> Self {
+        let bar = Self {
+            label: label.to_string(),
+            total,
+            position: 0,
+            bytes: false,
+            started_at: Instant::now(),
+            visible: io::stderr().is_terminal(),
+        };
+
+        bar.draw();
+        bar
+    }
+
+    /// Display position and total as byte counts.
+    pub fn with_bytes(mut self) -> Self {
+        self.bytes = true;
+        self.draw();
+        self
+    }
+
+    pub fn set_total(&mut self, total: u64) {
+        self.total = total;
+        self.draw();
+    }
+
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position.min(self.total);
+        self.draw();
+    }
+
+    pub fn inc(&mut self, delta: u64) {
+        self.set_position(self.position.saturating_add(delta));
+    }
+
+    /// Complete the progress bar, then move to the next line.
+    pub fn finish(mut self) {
+        self.position = self.total;
+        self.draw();
+
+        if self.visible {
+            eprintln!();
+        }
+    }
+
+    fn count(&self, n: u64) -> String {
+        if self.bytes {
+            format_bytes(n)
+        } else {
+            n.to_string()
+        }
+    }
+
+    fn eta(&self) -> Option<Duration> {
+        if self.position == 0 || self.position >= self.total {
+            return None;
+        }
+
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        let remaining = (self.total - self.position) as f64;
+        Some(Duration::from_secs_f64(
+            elapsed / self.position as f64 * remaining,
+        ))
+    }
+
+    fn draw(&self) {
+        if !self.visible {
+            return;
+        }
+
+        color::init_for(io::stderr().is_terminal());
+
+        let ratio = if self.total == 0 {
+            1.0
+        } else {
+            self.position as f64 / self.total as f64
+        };
+
+        let filled = (ratio * Self::WIDTH as f64) as usize;
+        let empty = Self::WIDTH as usize - filled;
+
+        let mut line = format!(
+            "{} [{}{}] {}/{} ({:.0}%)",
+            self.label,
+            "#".repeat(filled).green(),
+            "-".repeat(empty).dark_grey(),
+            self.count(self.position),
+            self.count(self.total),
+            ratio * 100.0,
+        );
+
+        if let Some(eta) = self.eta() {
+            line.push_str(&format!(" ETA {}s", eta.as_secs()));
+        }
+
+        let mut stderr = io::stderr();
+        let _ = execute!(
+            stderr,
+            Clear(ClearType::CurrentLine),
+            Print('\r'),
+            Print(line)
+        );
+    }
+}
+
+/// Format the given byte count using binary units.
+fn format_bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = n as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{n} {}", UNITS[0])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Start a spinner with the given label.
+///
+/// The spinner animates on stderr until the returned guard is
+/// dropped, which makes it suitable for wrapping awaited futures:
+///
+/// ```rust,ignore
+/// let spinner = print::spinner("Connecting to IMAP…");
+/// let ctx = ctx_builder.build().await?;
+/// drop(spinner);
+/// ```
+///
+/// The line is cleared whether the future succeeds or fails.
+pub fn spinner(label: impl ToString) -> Spinner {
+    Spinner::new(label)
+}
+
+/// Spinner guard, see [`spinner`].
+#[derive(Debug)]
+pub struct Spinner {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    const INTERVAL: Duration = Duration::from_millis(80);
+
+    pub fn new(label: impl ToString) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        if !io::stderr().is_terminal() {
+            return Self { stop, handle: None };
+        }
+
+        color::init_for(io::stderr().is_terminal());
+
+        let label = label.to_string();
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let mut stderr = io::stderr();
+
+                for frame in Self::FRAMES.iter().cycle() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    let _ = execute!(
+                        stderr,
+                        Clear(ClearType::CurrentLine),
+                        Print('\r'),
+                        Print(frame.cyan()),
+                        Print(' '),
+                        Print(&label),
+                    );
+
+                    thread::sleep(Self::INTERVAL);
+                }
+
+                let _ = execute!(stderr, Clear(ClearType::CurrentLine), Print('\r'));
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
 }
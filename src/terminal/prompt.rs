@@ -1,28 +1,310 @@
-use std::fmt;
 #[cfg(feature = "path")]
 use std::path::{Path, PathBuf};
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        RwLock,
+    },
+    time::{Duration, Instant},
+};
 
-use inquire::{Confirm, Password, PasswordDisplayMode, Select, Text};
+use crossterm::{cursor, event, execute, style::Color, terminal};
+pub use inquire::ui::RenderConfig;
+use inquire::{
+    ui::{StyleSheet, Styled},
+    validator::{StringValidator, Validation},
+    Confirm, InquireError, MultiSelect, Password, PasswordDisplayMode, Select, Text,
+};
 
-use crate::{terminal::validator::*, Error, Result};
+use crate::{
+    terminal::{
+        answers::{answer_key, AnswerProvider},
+        autocomplete::FuzzyAutocomplete,
+        color,
+        validator::*,
+    },
+    Error, Result,
+};
 
-pub fn u16(prompt: impl AsRef<str>, default: Option<u16>) -> Result<u16> {
-    let prompt = Text::new(prompt.as_ref()).with_validator(U16Validator);
+static ANSWER_PROVIDER: RwLock<Option<Box<dyn AnswerProvider>>> = RwLock::new(None);
 
-    let number = if let Some(default) = default {
-        prompt.with_default(&default.to_string()).prompt()
-    } else {
-        prompt.prompt()
-    };
+/// Set the global answer provider.
+///
+/// Once set, every prompt first tries to find its answer from the
+/// provider, and only falls back to the TTY when no answer is found.
+pub fn set_answer_provider(provider: impl AnswerProvider + 'static) {
+    *ANSWER_PROVIDER.write().unwrap() = Some(Box::new(provider));
+}
 
-    match number {
-        Ok(number) => Ok(number.parse().unwrap()),
-        Err(err) => Err(Error::PromptU16Error(err)),
+/// Remove the global answer provider, if any.
+pub fn unset_answer_provider() {
+    *ANSWER_PROVIDER.write().unwrap() = None;
+}
+
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable the headless mode.
+///
+/// In headless mode, prompts never read from the TTY: answers come
+/// from the answer provider, then from the default value of the
+/// prompt. Prompts without answer nor default value fail with
+/// [`Error::MissingPromptAnswerError`].
+pub fn set_headless(headless: bool) {
+    HEADLESS.store(headless, Ordering::SeqCst);
+}
+
+static BACK_NAVIGATION: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable the back navigation.
+///
+/// When enabled, pressing Esc fails prompts with
+/// [`Error::PromptBack`] instead of [`Error::PromptCancelled`], so
+/// that wizards can go back to the previous question. Ctrl-C still
+/// cancels prompts.
+pub fn set_back_navigation(enabled: bool) {
+    BACK_NAVIGATION.store(enabled, Ordering::SeqCst);
+}
+
+/// Whether the back navigation is enabled, see
+/// [`set_back_navigation`].
+pub fn back_navigation() -> bool {
+    BACK_NAVIGATION.load(Ordering::SeqCst)
+}
+
+static MAX_ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Set the maximum number of invalid answers accepted by prompts
+/// with validation.
+///
+/// Once reached, the prompt fails with
+/// [`Error::PromptMaxAttemptsReached`] instead of asking again. When
+/// [`None`], prompts ask again until the answer is valid.
+pub fn set_max_attempts(max_attempts: Option<usize>) {
+    MAX_ATTEMPTS.store(max_attempts.unwrap_or_default(), Ordering::SeqCst);
+}
+
+fn limit_attempts(validators: Vec<Box<dyn StringValidator>>) -> Vec<Box<dyn StringValidator>> {
+    match MAX_ATTEMPTS.load(Ordering::SeqCst) {
+        0 => validators,
+        max => vec![Box::new(AttemptsValidator::new(validators, max))],
+    }
+}
+
+fn map_err(err: InquireError, f: impl FnOnce(InquireError) -> Error) -> Error {
+    match err {
+        InquireError::OperationCanceled if back_navigation() => Error::PromptBack,
+        InquireError::OperationCanceled | InquireError::OperationInterrupted => {
+            Error::PromptCancelled
+        }
+        InquireError::Custom(err) => match err.downcast::<MaxAttemptsReachedError>() {
+            Ok(err) => Error::PromptMaxAttemptsReached(err.0),
+            Err(err) => f(InquireError::Custom(err)),
+        },
+        err => f(err),
+    }
+}
+
+/// Set the render config applied to every prompt.
+///
+/// This should be called once by the binary, before any prompt.
+pub fn set_render_config(config: RenderConfig<'static>) {
+    inquire::set_global_render_config(config)
+}
+
+/// Set the render config applied to every prompt from the given
+/// theme.
+pub fn set_theme(theme: &PromptTheme) {
+    set_render_config(theme.to_render_config())
+}
+
+/// Simplified prompt theme, suitable for user configuration.
+///
+/// Unset fields keep their default style.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PromptTheme {
+    pub prefix: Option<String>,
+    pub prefix_color: Option<Color>,
+    pub prompt_color: Option<Color>,
+    pub answer_color: Option<Color>,
+    pub help_color: Option<Color>,
+    pub error_color: Option<Color>,
+    pub highlight_color: Option<Color>,
+}
+
+impl PromptTheme {
+    pub fn to_render_config(&self) -> RenderConfig<'static> {
+        let mut config = RenderConfig::default_colored();
+
+        if let Some(prefix) = &self.prefix {
+            // the render config lives as long as the program, so does
+            // the prefix
+            let prefix: &'static str = Box::leak(prefix.clone().into_boxed_str());
+            config.prompt_prefix = Styled::new(prefix).with_style_sheet(config.prompt_prefix.style);
+            config.answered_prompt_prefix =
+                Styled::new(prefix).with_style_sheet(config.answered_prompt_prefix.style);
+        }
+
+        if let Some(color) = self.prefix_color.and_then(color::to_inquire_color) {
+            config.prompt_prefix = config.prompt_prefix.with_fg(color);
+            config.answered_prompt_prefix = config.answered_prompt_prefix.with_fg(color);
+        }
+
+        if let Some(color) = self.prompt_color.and_then(color::to_inquire_color) {
+            config.prompt = config.prompt.with_fg(color);
+        }
+
+        if let Some(color) = self.answer_color.and_then(color::to_inquire_color) {
+            config.answer = config.answer.with_fg(color);
+        }
+
+        if let Some(color) = self.help_color.and_then(color::to_inquire_color) {
+            config.help_message = config.help_message.with_fg(color);
+        }
+
+        if let Some(color) = self.error_color.and_then(color::to_inquire_color) {
+            config.error_message.prefix = config.error_message.prefix.with_fg(color);
+            config.error_message.message = StyleSheet::new().with_fg(color);
+        }
+
+        if let Some(color) = self.highlight_color.and_then(color::to_inquire_color) {
+            config.highlighted_option_prefix = config.highlighted_option_prefix.with_fg(color);
+            config.selected_option = Some(StyleSheet::new().with_fg(color));
+        }
+
+        config
+    }
+}
+
+/// Find the answer of the given prompt from the answer provider.
+///
+/// In headless mode, the given default value is used as answer when
+/// the provider has none, see [`set_headless`].
+fn answer(prompt: &str, default: Option<String>) -> Result<Option<(String, String)>> {
+    let key = answer_key(prompt);
+
+    let answer = ANSWER_PROVIDER
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|provider| provider.get(&key));
+
+    match answer {
+        Some(answer) => Ok(Some((key, answer))),
+        None if HEADLESS.load(Ordering::SeqCst) => match default {
+            Some(default) => Ok(Some((key, default))),
+            None => Err(Error::MissingPromptAnswerError(key)),
+        },
+        None => Ok(None),
+    }
+}
+
+fn validate_answer(
+    validators: &[Box<dyn StringValidator>],
+    key: String,
+    answer: String,
+) -> Result<String> {
+    for validator in validators {
+        if !matches!(validator.validate(&answer), Ok(Validation::Valid)) {
+            return Err(Error::InvalidPromptAnswerError(key, answer));
+        }
+    }
+
+    Ok(answer)
+}
+
+/// Run the given prompt with a timeout.
+///
+/// The given message is shown until a key is pressed. If none is
+/// pressed before the given duration, the default value is returned,
+/// or [`Error::PromptTimedOut`] when no default value is given.
+/// Otherwise the message is cleared and the prompt runs as usual,
+/// without timeout. The pressed key only stops the timeout, it is
+/// not passed to the prompt.
+///
+/// Other terminal events, like resizes, focus changes and mouse
+/// events, are ignored. Input is awaited on the calling thread, so
+/// nothing is left waiting for input after a timeout. When the
+/// terminal cannot be read, the timeout is considered reached.
+pub fn timeout<T>(
+    message: impl AsRef<str>,
+    duration: Duration,
+    default: Option<T>,
+    prompt: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let mut stderr = io::stderr();
+    let _ = write!(stderr, "{} ({}s)", message.as_ref(), duration.as_secs());
+    let _ = stderr.flush();
+
+    // raw mode makes key presses readable without waiting for a new
+    // line
+    let _ = terminal::enable_raw_mode();
+    let pressed = wait_for_key_press(duration);
+    let _ = terminal::disable_raw_mode();
+
+    if !pressed {
+        let _ = writeln!(stderr);
+        return default.ok_or(Error::PromptTimedOut(duration));
     }
+
+    let _ = execute!(
+        stderr,
+        cursor::MoveToColumn(0),
+        terminal::Clear(terminal::ClearType::CurrentLine)
+    );
+
+    prompt()
 }
 
-pub fn usize(prompt: impl AsRef<str>, default: Option<usize>) -> Result<usize> {
-    let prompt = Text::new(prompt.as_ref()).with_validator(UsizeValidator);
+/// Wait for a key press during the given duration, discarding other
+/// events, see [`timeout`].
+fn wait_for_key_press(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        match event::poll(remaining) {
+            Ok(true) => (),
+            Ok(false) | Err(_) => return false,
+        }
+
+        match event::read() {
+            Ok(event::Event::Key(key)) if key.kind == event::KeyEventKind::Press => return true,
+            Ok(_) => continue,
+            Err(_) => return false,
+        }
+    }
+}
+
+pub fn number<T>(
+    prompt: impl AsRef<str>,
+    default: Option<T>,
+    min: Option<T>,
+    max: Option<T>,
+    help: Option<&str>,
+) -> Result<T>
+where
+    T: FromStr + PartialOrd + fmt::Display + Clone + 'static,
+{
+    let validator = NumberValidator::new(min, max);
+
+    let default_answer = default.as_ref().map(ToString::to_string);
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), default_answer)? {
+        return validator
+            .parse(&answer)
+            .map_err(|_| Error::InvalidPromptAnswerError(key, answer));
+    }
+
+    let validators = limit_attempts(vec![Box::new(validator.clone())]);
+    let mut prompt = Text::new(prompt.as_ref()).with_validators(&validators);
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
 
     let number = if let Some(default) = default {
         prompt.with_default(&default.to_string()).prompt()
@@ -31,68 +313,271 @@ pub fn usize(prompt: impl AsRef<str>, default: Option<usize>) -> Result<usize> {
     };
 
     match number {
-        Ok(number) => Ok(number.parse().unwrap()),
-        Err(err) => Err(Error::PromptUsizeError(err)),
+        Ok(number) => Ok(validator.parse(&number).unwrap()),
+        Err(err) => Err(map_err(err, Error::PromptNumberError)),
     }
 }
 
-pub fn secret(prompt: impl AsRef<str>) -> Result<String> {
-    Password::new(prompt.as_ref())
+pub fn secret(prompt: impl AsRef<str>, help: Option<&str>) -> Result<String> {
+    if let Some((_, answer)) = answer(prompt.as_ref(), None)? {
+        return Ok(answer);
+    }
+
+    let mut prompt = Password::new(prompt.as_ref())
         .with_display_mode(PasswordDisplayMode::Masked)
-        .without_confirmation()
+        .without_confirmation();
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
         .prompt()
-        .map_err(Error::PromptSecretError)
+        .map_err(|err| map_err(err, Error::PromptSecretError))
 }
 
-pub fn some_secret(prompt: impl AsRef<str>) -> Result<Option<String>> {
-    Password::new(prompt.as_ref())
+pub fn some_secret(prompt: impl AsRef<str>, help: Option<&str>) -> Result<Option<String>> {
+    if let Some((_, answer)) = answer(prompt.as_ref(), Some(String::new()))? {
+        return Ok(Some(answer).filter(|answer| !answer.is_empty()));
+    }
+
+    let mut prompt = Password::new(prompt.as_ref())
         .with_display_mode(PasswordDisplayMode::Masked)
-        .without_confirmation()
+        .without_confirmation();
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
         .prompt_skippable()
-        .map_err(Error::PromptSecretError)
+        .map_err(|err| map_err(err, Error::PromptSecretError))
 }
 
-pub fn password(prompt: impl AsRef<str>) -> Result<String> {
-    Password::new(prompt.as_ref())
+pub fn password(prompt: impl AsRef<str>, help: Option<&str>) -> Result<String> {
+    if let Some((_, answer)) = answer(prompt.as_ref(), None)? {
+        return Ok(answer);
+    }
+
+    let mut prompt = Password::new(prompt.as_ref())
         .with_display_mode(PasswordDisplayMode::Masked)
-        .with_custom_confirmation_message("Confirm password")
+        .with_custom_confirmation_message("Confirm password");
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
         .prompt()
-        .map_err(Error::PromptPasswordError)
+        .map_err(|err| map_err(err, Error::PromptPasswordError))
 }
 
-pub fn text<T: AsRef<str>>(prompt: T, default: Option<T>) -> Result<String> {
-    let mut prompt = Text::new(prompt.as_ref());
+pub fn text<T: AsRef<str>>(
+    prompt: T,
+    default: Option<T>,
+    validators: &[Box<dyn StringValidator>],
+    help: Option<&str>,
+) -> Result<String> {
+    let default_answer = default.as_ref().map(|d| d.as_ref().to_owned());
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), default_answer)? {
+        return validate_answer(validators, key, answer);
+    }
+
+    let validators = limit_attempts(validators.to_vec());
+    let mut prompt = Text::new(prompt.as_ref()).with_validators(&validators);
 
     if let Some(default) = default.as_ref() {
         prompt = prompt.with_default(default.as_ref())
     }
 
-    prompt.prompt().map_err(Error::PromptTextError)
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
+        .prompt()
+        .map_err(|err| map_err(err, Error::PromptTextError))
 }
 
-pub fn some_text<T: AsRef<str>>(prompt: T, default: Option<T>) -> Result<Option<String>> {
-    let mut prompt = Text::new(prompt.as_ref());
+pub fn some_text<T: AsRef<str>>(
+    prompt: T,
+    default: Option<T>,
+    validators: &[Box<dyn StringValidator>],
+    help: Option<&str>,
+) -> Result<Option<String>> {
+    let default_answer = default.as_ref().map(|d| d.as_ref().to_owned());
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), Some(default_answer.unwrap_or_default()))?
+    {
+        if answer.is_empty() {
+            return Ok(None);
+        }
+
+        return validate_answer(validators, key, answer).map(Some);
+    }
+
+    let validators = limit_attempts(validators.to_vec());
+    let mut prompt = Text::new(prompt.as_ref()).with_validators(&validators);
 
     if let Some(default) = default.as_ref() {
         prompt = prompt.with_default(default.as_ref())
     }
 
-    prompt.prompt_skippable().map_err(Error::PromptTextError)
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
+        .prompt_skippable()
+        .map_err(|err| map_err(err, Error::PromptTextError))
 }
 
-pub fn bool(prompt: impl AsRef<str>, default: bool) -> Result<bool> {
-    Confirm::new(prompt.as_ref())
+pub fn autocomplete<T: AsRef<str>>(
+    prompt: T,
+    default: Option<T>,
+    suggestions: impl IntoIterator<Item = impl ToString>,
+    help: Option<&str>,
+) -> Result<String> {
+    let default_answer = default.as_ref().map(|d| d.as_ref().to_owned());
+
+    if let Some((_, answer)) = answer(prompt.as_ref(), default_answer)? {
+        return Ok(answer);
+    }
+
+    let mut prompt =
+        Text::new(prompt.as_ref()).with_autocomplete(FuzzyAutocomplete::new(suggestions));
+
+    if let Some(default) = default.as_ref() {
+        prompt = prompt.with_default(default.as_ref())
+    }
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
+        .prompt()
+        .map_err(|err| map_err(err, Error::PromptAutocompleteError))
+}
+
+pub fn bool(prompt: impl AsRef<str>, default: bool, help: Option<&str>) -> Result<bool> {
+    if let Some((key, answer)) = answer(prompt.as_ref(), Some(default.to_string()))? {
+        return match answer.to_lowercase().as_str() {
+            "true" | "yes" | "y" => Ok(true),
+            "false" | "no" | "n" => Ok(false),
+            _ => Err(Error::InvalidPromptAnswerError(key, answer)),
+        };
+    }
+
+    let mut prompt = Confirm::new(prompt.as_ref()).with_default(default);
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
+        .prompt()
+        .map_err(|err| map_err(err, Error::PromptBoolError))
+}
+
+/// Variant of [`bool`] with custom affirmative and negative labels.
+///
+/// The answer is parsed from the full label or its first letter,
+/// case-insensitively, for example `Overwrite`, `overwrite` or `o`.
+pub fn confirm(
+    prompt: impl AsRef<str>,
+    (yes, no): (&str, &str),
+    default: bool,
+    help: Option<&str>,
+) -> Result<bool> {
+    let parser = |input: &str| -> std::result::Result<bool, ()> {
+        let input = input.trim();
+        let matches = |label: &str| {
+            input.eq_ignore_ascii_case(label)
+                || input.len() == 1
+                    && label
+                        .get(..1)
+                        .is_some_and(|c| input.eq_ignore_ascii_case(c))
+        };
+
+        if matches(yes) {
+            Ok(true)
+        } else if matches(no) {
+            Ok(false)
+        } else {
+            Err(())
+        }
+    };
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), Some(default.to_string()))? {
+        return match answer.to_lowercase().as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => parser(&answer).map_err(|_| Error::InvalidPromptAnswerError(key, answer)),
+        };
+    }
+
+    let formatter = |answer: bool| String::from(if answer { yes } else { no });
+
+    let default_formatter = |default: bool| {
+        if default {
+            format!("{yes}/{}", no.to_lowercase())
+        } else {
+            format!("{}/{no}", yes.to_lowercase())
+        }
+    };
+
+    let error = format!("Type {yes:?} or {no:?}");
+
+    let mut prompt = Confirm::new(prompt.as_ref())
         .with_default(default)
+        .with_parser(&parser)
+        .with_formatter(&formatter)
+        .with_default_value_formatter(&default_formatter)
+        .with_error_message(&error);
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
         .prompt()
-        .map_err(Error::PromptBoolError)
+        .map_err(|err| map_err(err, Error::PromptBoolError))
 }
 
 pub fn item<T: fmt::Display + Eq>(
     prompt: impl AsRef<str>,
     items: impl IntoIterator<Item = T>,
     default: Option<T>,
+    help: Option<&str>,
+) -> Result<T> {
+    let page_size = Some(Select::<T>::DEFAULT_PAGE_SIZE);
+    paged_item(prompt, items, default, page_size, true, help)
+}
+
+/// Variant of [`item`] suited for long lists.
+///
+/// The page size defines how many items are displayed at once. When
+/// [`None`], the page size fits the terminal height. When the filter
+/// is enabled, typing filters items with a fuzzy matching.
+pub fn paged_item<T: fmt::Display + Eq>(
+    prompt: impl AsRef<str>,
+    items: impl IntoIterator<Item = T>,
+    default: Option<T>,
+    page_size: Option<usize>,
+    filter: bool,
+    help: Option<&str>,
 ) -> Result<T> {
     let items: Vec<_> = items.into_iter().collect();
+    let default_answer = default.as_ref().map(ToString::to_string);
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), default_answer)? {
+        return items
+            .into_iter()
+            .find(|item| item.to_string().eq_ignore_ascii_case(&answer))
+            .ok_or(Error::InvalidPromptAnswerError(key, answer));
+    }
 
     let default = if let Some(default) = default.as_ref() {
         items
@@ -103,18 +588,138 @@ pub fn item<T: fmt::Display + Eq>(
         None
     };
 
-    let mut prompt = Select::new(prompt.as_ref(), items);
+    let page_size = page_size.unwrap_or_else(|| {
+        // keep some rows for the prompt, the filter input and the
+        // help message, but always show at least one item
+        match terminal::size() {
+            Ok((_, rows)) if rows > 0 => (rows as usize).saturating_sub(4).max(1),
+            _ => Select::<T>::DEFAULT_PAGE_SIZE,
+        }
+    });
+
+    let mut prompt = Select::new(prompt.as_ref(), items).with_page_size(page_size);
+
+    if !filter {
+        prompt = prompt.without_filtering();
+    }
 
     if let Some(default) = default.as_ref() {
         prompt = prompt.with_starting_cursor(*default);
     }
 
-    prompt.prompt().map_err(Error::PromptItemError)
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
+        .prompt()
+        .map_err(|err| map_err(err, Error::PromptItemError))
+}
+
+/// Variant of [`item`] where several items can be selected.
+///
+/// Selected items are returned in the order of the given items.
+/// Answers are comma-separated, for example `id, subject`.
+pub fn items<T: fmt::Display + Eq>(
+    prompt: impl AsRef<str>,
+    items: impl IntoIterator<Item = T>,
+    defaults: &[T],
+    help: Option<&str>,
+) -> Result<Vec<T>> {
+    let items: Vec<_> = items.into_iter().collect();
+    let default_answer = defaults
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), Some(default_answer))? {
+        let answers: Vec<&str> = answer
+            .split(',')
+            .map(str::trim)
+            .filter(|answer| !answer.is_empty())
+            .collect();
+
+        if let Some(unknown) = answers.iter().find(|answer| {
+            !items
+                .iter()
+                .any(|item| item.to_string().eq_ignore_ascii_case(answer))
+        }) {
+            return Err(Error::InvalidPromptAnswerError(key, unknown.to_string()));
+        }
+
+        return Ok(items
+            .into_iter()
+            .filter(|item| {
+                let item = item.to_string();
+                answers
+                    .iter()
+                    .any(|answer| item.eq_ignore_ascii_case(answer))
+            })
+            .collect());
+    }
+
+    let defaults: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| defaults.contains(item))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut prompt = MultiSelect::new(prompt.as_ref(), items).with_default(&defaults);
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    prompt
+        .prompt()
+        .map_err(|err| map_err(err, Error::PromptItemsError))
+}
+
+pub fn color(prompt: impl AsRef<str>, default: Option<Color>, help: Option<&str>) -> Result<Color> {
+    let default_answer = default.as_ref().map(color::to_string);
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), default_answer)? {
+        return color::parse(&answer).map_err(|_| Error::InvalidPromptAnswerError(key, answer));
+    }
+
+    let validators = limit_attempts(vec![Box::new(ColorValidator)]);
+    let mut prompt = Text::new(prompt.as_ref()).with_validators(&validators);
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    let color = if let Some(default) = default {
+        prompt.with_default(&color::to_string(&default)).prompt()
+    } else {
+        prompt.prompt()
+    };
+
+    match color {
+        Ok(color) => Ok(color::parse(&color).unwrap()),
+        Err(err) => Err(map_err(err, Error::PromptColorError)),
+    }
 }
 
 #[cfg(feature = "path")]
-pub fn path(prompt: impl AsRef<str>, default: Option<impl AsRef<Path>>) -> Result<PathBuf> {
-    let prompt = Text::new(prompt.as_ref());
+pub fn path(
+    prompt: impl AsRef<str>,
+    default: Option<impl AsRef<Path>>,
+    help: Option<&str>,
+) -> Result<PathBuf> {
+    let default_answer = default.as_ref().map(|d| d.as_ref().display().to_string());
+
+    if let Some((_, answer)) = answer(prompt.as_ref(), default_answer)? {
+        return Ok(shellexpand_utils::expand::path(PathBuf::from(answer)));
+    }
+
+    let mut prompt = Text::new(prompt.as_ref());
+
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
 
     let text = if let Some(default) = default.as_ref() {
         let default = default.as_ref().display().to_string();
@@ -123,20 +728,39 @@ pub fn path(prompt: impl AsRef<str>, default: Option<impl AsRef<Path>>) -> Resul
         prompt.prompt()
     };
 
-    let path = PathBuf::from(text.map_err(Error::PromptPathError)?);
+    let path = PathBuf::from(text.map_err(|err| map_err(err, Error::PromptPathError))?);
 
     Ok(shellexpand_utils::expand::path(path))
 }
 
 #[cfg(feature = "email")]
-pub fn email<T: AsRef<str>>(prompt: T, default: Option<T>) -> Result<email_address::EmailAddress> {
-    let mut prompt = Text::new(prompt.as_ref()).with_validator(EmailValidator);
+pub fn email<T: AsRef<str>>(
+    prompt: T,
+    default: Option<T>,
+    help: Option<&str>,
+) -> Result<email_address::EmailAddress> {
+    let default_answer = default.as_ref().map(|d| d.as_ref().to_owned());
+
+    if let Some((key, answer)) = answer(prompt.as_ref(), default_answer)? {
+        return answer
+            .parse()
+            .map_err(|_| Error::InvalidPromptAnswerError(key, answer));
+    }
+
+    let validators = limit_attempts(vec![Box::new(EmailValidator)]);
+    let mut prompt = Text::new(prompt.as_ref()).with_validators(&validators);
 
     if let Some(default) = default.as_ref() {
         prompt = prompt.with_default(default.as_ref());
     }
 
-    let email = prompt.prompt().map_err(Error::PromptEmailError)?;
+    if let Some(help) = help {
+        prompt = prompt.with_help_message(help);
+    }
+
+    let email = prompt
+        .prompt()
+        .map_err(|err| map_err(err, Error::PromptEmailError))?;
 
     Ok(<email_address::EmailAddress as std::str::FromStr>::from_str(&email).unwrap())
 }
@@ -1,28 +1,122 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
 use inquire::{
     validator::{StringValidator, Validation},
     CustomUserError,
 };
 
+use thiserror::Error;
+
+use crate::terminal::color;
+
+/// Error returned by [`AttemptsValidator`] when the maximum number
+/// of attempts is reached.
+#[derive(Debug, Error)]
+#[error("too many invalid answers ({0} attempts)")]
+pub struct MaxAttemptsReachedError(pub usize);
+
+/// Validator wrapper that limits the number of invalid answers.
+///
+/// When the maximum number of attempts is reached, the validation
+/// fails with [`MaxAttemptsReachedError`], which aborts the prompt.
+#[derive(Clone)]
+pub struct AttemptsValidator {
+    validators: Vec<Box<dyn StringValidator>>,
+    max_attempts: usize,
+    attempts: Arc<AtomicUsize>,
+}
+
+impl AttemptsValidator {
+    pub fn new(
+        validators: impl IntoIterator<Item = Box<dyn StringValidator>>,
+        max_attempts: usize,
+    ) -> Self {
+        Self {
+            validators: validators.into_iter().collect(),
+            max_attempts,
+            attempts: Default::default(),
+        }
+    }
+}
+
+impl StringValidator for AttemptsValidator {
+    fn validate(&self, input: &str) -> Result<Validation, CustomUserError> {
+        for validator in &self.validators {
+            if let invalid @ Validation::Invalid(_) = validator.validate(input)? {
+                let attempts = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+
+                if attempts >= self.max_attempts {
+                    return Err(Box::new(MaxAttemptsReachedError(attempts)));
+                }
+
+                return Ok(invalid);
+            }
+        }
+
+        Ok(Validation::Valid)
+    }
+}
+
+/// Validator of numbers, with optional inclusive bounds.
 #[derive(Clone, Debug, Default)]
-pub struct U16Validator;
+pub struct NumberValidator<T> {
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl<T: FromStr + PartialOrd + fmt::Display> NumberValidator<T> {
+    pub fn new(min: Option<T>, max: Option<T>) -> Self {
+        Self { min, max }
+    }
+
+    /// Parse the given input, then check that it fits in bounds.
+    ///
+    /// Returns the reason as error if the input is not valid.
+    pub fn parse(&self, input: &str) -> Result<T, String> {
+        let number: T = match input.trim().parse() {
+            Ok(number) => number,
+            Err(_) => return Err(format!("{input} is not a valid number")),
+        };
+
+        if let Some(min) = self.min.as_ref().filter(|min| &number < *min) {
+            return Err(format!("must be greater than or equal to {min}"));
+        }
+
+        if let Some(max) = self.max.as_ref().filter(|max| &number > *max) {
+            return Err(format!("must be less than or equal to {max}"));
+        }
+
+        Ok(number)
+    }
+}
 
-impl StringValidator for U16Validator {
+impl<T> StringValidator for NumberValidator<T>
+where
+    T: FromStr + PartialOrd + fmt::Display + Clone,
+{
     fn validate(&self, input: &str) -> Result<Validation, CustomUserError> {
-        match input.parse::<u16>() {
+        match self.parse(input) {
             Ok(_) => Ok(Validation::Valid),
-            Err(err) => Err(Box::new(err)),
+            Err(reason) => Ok(Validation::Invalid(reason.into())),
         }
     }
 }
 
 #[derive(Clone, Debug, Default)]
-pub struct UsizeValidator;
+pub struct ColorValidator;
 
-impl StringValidator for UsizeValidator {
+impl StringValidator for ColorValidator {
     fn validate(&self, input: &str) -> Result<Validation, CustomUserError> {
-        match input.parse::<usize>() {
+        match color::parse(input) {
             Ok(_) => Ok(Validation::Valid),
-            Err(err) => Err(Box::new(err)),
+            Err(reason) => Ok(Validation::Invalid(reason.into())),
         }
     }
 }
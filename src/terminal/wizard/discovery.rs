@@ -0,0 +1,59 @@
+//! Discovery of email servers using DNS SRV records, see [RFC 6186].
+//!
+//! Used as a fallback when the autoconfig of the email provider
+//! cannot be found.
+//!
+//! [RFC 6186]: https://datatracker.ietf.org/doc/html/rfc6186
+
+use hickory_resolver::TokioAsyncResolver;
+use tracing::debug;
+
+/// Server advertised by a DNS SRV record.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SrvServer {
+    pub host: String,
+    pub port: u16,
+}
+
+/// Discover the IMAP server of the given domain, using the
+/// `_imaps._tcp` record. The server expects TLS encryption.
+pub async fn imap(domain: &str) -> Option<SrvServer> {
+    lookup(format!("_imaps._tcp.{domain}")).await
+}
+
+/// Discover the SMTP server of the given domain, using the
+/// `_submission._tcp` record. The server expects STARTTLS
+/// encryption.
+pub async fn smtp(domain: &str) -> Option<SrvServer> {
+    lookup(format!("_submission._tcp.{domain}")).await
+}
+
+/// Look up the given SRV record, then return the server with the
+/// lowest priority and the highest weight.
+async fn lookup(name: String) -> Option<SrvServer> {
+    let resolver = match TokioAsyncResolver::tokio_from_system_conf() {
+        Ok(resolver) => resolver,
+        Err(err) => {
+            debug!(?err, "cannot create DNS resolver, skipping SRV lookup");
+            return None;
+        }
+    };
+
+    let records = match resolver.srv_lookup(name.as_str()).await {
+        Ok(records) => records,
+        Err(err) => {
+            debug!(?err, "cannot look up SRV record {name}");
+            return None;
+        }
+    };
+
+    records
+        .iter()
+        // a target of "." means that the service is not available
+        .filter(|srv| !srv.target().is_root())
+        .min_by_key(|srv| (srv.priority(), u16::MAX - srv.weight()))
+        .map(|srv| SrvServer {
+            host: srv.target().to_utf8().trim_end_matches('.').to_owned(),
+            port: srv.port(),
+        })
+}
@@ -1,17 +1,207 @@
-use dirs::home_dir;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use dirs::{config_dir, home_dir};
 use email::maildir::config::MaildirConfig;
+use shellexpand_utils::expand;
 
 use crate::{terminal::prompt, Result};
 
+/// Maildir found on the file system, see [`detect`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DetectedMaildir {
+    pub root_dir: PathBuf,
+    /// Whether subfolders are stored as dot-prefixed directories.
+    pub maildirpp: bool,
+}
+
+impl fmt::Display for DetectedMaildir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.root_dir.display())?;
+
+        if self.maildirpp {
+            write!(f, " (Maildir++)")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum MaildirChoice {
+    Detected(DetectedMaildir),
+    Other,
+}
+
+impl fmt::Display for MaildirChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Detected(maildir) => write!(f, "{maildir}"),
+            Self::Other => write!(f, "Other path…"),
+        }
+    }
+}
+
 pub fn start(account_name: impl AsRef<str>) -> Result<MaildirConfig> {
     let account_name = account_name.as_ref();
 
     let default_root_dir = home_dir().map(|home| home.join("Mail").join(account_name));
-    let root_dir = prompt::path("Maildir path:", default_root_dir)?;
-    let maildirpp = prompt::bool("Enable Maildir++?", false)?;
+
+    let detected = detect();
+    let default_choice = detected
+        .iter()
+        .find(|maildir| Some(&maildir.root_dir) == default_root_dir.as_ref())
+        .or(detected.first())
+        .cloned()
+        .map(MaildirChoice::Detected);
+
+    let detected = if detected.is_empty() {
+        None
+    } else {
+        let choices = detected
+            .into_iter()
+            .map(MaildirChoice::Detected)
+            .chain([MaildirChoice::Other]);
+
+        match prompt::item("Maildir path:", choices, default_choice, None)? {
+            MaildirChoice::Detected(maildir) => Some(maildir),
+            MaildirChoice::Other => None,
+        }
+    };
+
+    let root_dir = match &detected {
+        Some(maildir) => maildir.root_dir.clone(),
+        None => prompt::path("Maildir path:", default_root_dir, None)?,
+    };
+
+    let maildirpp = prompt::bool(
+        "Enable Maildir++?",
+        detected.is_some_and(|maildir| maildir.maildirpp),
+        Some("Subfolders are stored as dot-prefixed directories (Courier, Dovecot)"),
+    )?;
 
     Ok(MaildirConfig {
         root_dir,
         maildirpp,
     })
 }
+
+/// Look for existing maildirs in common locations: `~/Maildir`,
+/// `~/Mail/*`, and the maildir stores declared in mbsync and
+/// OfflineIMAP configurations.
+///
+/// Only directories that are maildirs, or that contain maildirs,
+/// are returned.
+pub fn detect() -> Vec<DetectedMaildir> {
+    let mut candidates = Vec::new();
+
+    if let Some(home) = home_dir() {
+        candidates.push(home.join("Maildir"));
+
+        if let Ok(entries) = fs::read_dir(home.join("Mail")) {
+            let mut dirs: Vec<PathBuf> = entries
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| path.is_dir())
+                .collect();
+            dirs.sort();
+            candidates.extend(dirs);
+        }
+
+        candidates.extend(mbsync_paths(&home.join(".mbsyncrc")));
+        candidates.extend(offlineimap_paths(&home.join(".offlineimaprc")));
+    }
+
+    if let Some(config) = config_dir() {
+        candidates.extend(mbsync_paths(&config.join("isyncrc")));
+        candidates.extend(offlineimap_paths(
+            &config.join("offlineimap").join("config"),
+        ));
+    }
+
+    let mut detected: Vec<DetectedMaildir> = Vec::new();
+
+    for root_dir in candidates {
+        // compare without trailing slashes from config files
+        let root_dir = root_dir.components().collect::<PathBuf>();
+
+        if detected.iter().any(|maildir| maildir.root_dir == root_dir) {
+            continue;
+        }
+
+        if !is_maildir(&root_dir) && !subdirs(&root_dir).any(|dir| is_maildir(&dir)) {
+            continue;
+        }
+
+        let maildirpp = is_maildir(&root_dir)
+            && subdirs(&root_dir).any(|dir| {
+                let dot_prefixed = dir
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with('.'));
+                dot_prefixed && is_maildir(&dir)
+            });
+
+        detected.push(DetectedMaildir {
+            root_dir,
+            maildirpp,
+        });
+    }
+
+    detected
+}
+
+fn is_maildir(dir: &Path) -> bool {
+    ["cur", "new", "tmp"]
+        .into_iter()
+        .all(|subdir| dir.join(subdir).is_dir())
+}
+
+fn subdirs(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_dir())
+}
+
+/// Read the paths of maildir stores from the given mbsync
+/// configuration, for example `Path ~/Mail/account/`.
+fn mbsync_paths(config_path: &Path) -> Vec<PathBuf> {
+    let Ok(config) = fs::read_to_string(config_path) else {
+        return Vec::new();
+    };
+
+    let mut in_maildir_store = false;
+    let mut paths = Vec::new();
+
+    for line in config.lines().map(str::trim) {
+        let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+        if line.is_empty() {
+            in_maildir_store = false;
+        } else if key.eq_ignore_ascii_case("MaildirStore") {
+            in_maildir_store = true;
+        } else if in_maildir_store && key.eq_ignore_ascii_case("Path") {
+            paths.push(expand::path(value.trim()));
+        }
+    }
+
+    paths
+}
+
+/// Read the local folders of the given OfflineIMAP configuration,
+/// for example `localfolders = ~/Mail/account`.
+fn offlineimap_paths(config_path: &Path) -> Vec<PathBuf> {
+    let Ok(config) = fs::read_to_string(config_path) else {
+        return Vec::new();
+    };
+
+    config
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("localfolders"))
+        .map(|(_, value)| expand::path(value.trim()))
+        .collect()
+}
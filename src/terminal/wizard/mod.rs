@@ -1,27 +1,194 @@
 use std::{path::Path, process::exit};
 
-use crate::Result;
+use serde::{Deserialize, Deserializer};
+use toml::Value;
 
-use super::{print, prompt};
+use crate::{Error, Result};
 
+use super::{
+    answers::{answer_key, AnswerProvider, MapAnswers},
+    config::parse_config_file,
+    print, prompt,
+};
+
+#[cfg(any(feature = "imap", feature = "smtp"))]
+pub mod discovery;
 #[cfg(feature = "imap")]
 pub mod imap;
 #[cfg(feature = "maildir")]
 pub mod maildir;
 #[cfg(feature = "notmuch")]
 pub mod notmuch;
+#[cfg(any(feature = "imap", feature = "smtp"))]
+pub mod passwd;
+#[cfg(feature = "pgp")]
+pub mod pgp;
+pub mod preset;
+#[cfg(any(feature = "imap", feature = "smtp"))]
+pub mod probe;
 #[cfg(feature = "sendmail")]
 pub mod sendmail;
 #[cfg(feature = "smtp")]
 pub mod smtp;
+#[cfg(any(feature = "imap", feature = "smtp"))]
+pub mod tls;
 
 pub fn confirm_or_exit(path: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref();
     print::warn(format!("Cannot find configuration at {}.", path.display()));
 
-    if !prompt::bool("Would you like to create one with the wizard?", true)? {
-        exit(0);
+    match prompt::bool("Would you like to create one with the wizard?", true, None) {
+        Ok(true) => Ok(()),
+        Ok(false) | Err(Error::PromptCancelled) => exit(0),
+        Err(err) => Err(err),
+    }
+}
+
+/// Position in a wizard made of steps, supporting back navigation.
+///
+/// Steps go back when they fail with [`Error::PromptBack`], see
+/// [`prompt::set_back_navigation`]:
+///
+/// ```rust,ignore
+/// let mut steps = Steps::default();
+///
+/// while steps.current() < 2 {
+///     let res = match steps.current() {
+///         0 => prompt::text("Hostname:", None, &[], None).map(drop),
+///         _ => prompt::text("Login:", None, &[], None).map(drop),
+///     };
+///
+///     steps.advance(res)?;
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Steps {
+    current: usize,
+    back: bool,
+}
+
+impl Steps {
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Move to the next step, or to the previous one if the current
+    /// step went back.
+    ///
+    /// Going back from the first step fails with
+    /// [`Error::PromptBack`], so that the enclosing wizard can go
+    /// back as well.
+    pub fn advance(&mut self, res: Result<()>) -> Result<()> {
+        match res {
+            Ok(()) => {
+                self.current += 1;
+                self.back = false;
+                Ok(())
+            }
+            Err(Error::PromptBack) if self.current > 0 => {
+                self.current -= 1;
+                self.back = true;
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Result of a step without question, so that going back skips
+    /// it.
+    pub fn skip(&self) -> Result<()> {
+        if self.back {
+            Err(Error::PromptBack)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Answers of the wizard, used to run it without a TTY.
+///
+/// Answers are structured as nested tables, flattened into answer
+/// keys (see [`answer_key`]). For example, the prompt `IMAP
+/// hostname:` can be answered by any of:
+///
+/// ```toml
+/// imap-hostname = "imap.example.com"
+///
+/// [imap]
+/// hostname = "imap.example.com"
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WizardAnswers(MapAnswers);
+
+impl WizardAnswers {
+    /// Read answers from a TOML, YAML or JSON file, depending on the
+    /// extension of the given path.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| Error::ReadAnswersFileError(err, path.to_owned()))?;
+
+        Ok(Self::from(parse_config_file(path, &content)?))
+    }
+
+    /// Parse answers from `key=value` arguments, like command line
+    /// flags.
+    pub fn from_args(args: impl IntoIterator<Item = impl AsRef<str>>) -> Result<Self> {
+        let mut answers = Self::default();
+
+        for arg in args {
+            let arg = arg.as_ref();
+
+            let Some((key, val)) = arg.split_once('=') else {
+                return Err(Error::ParseAnswerArgError(arg.to_owned()));
+            };
+
+            answers.insert(key, val.to_owned());
+        }
+
+        Ok(answers)
+    }
+
+    /// Add the given answers, overriding existing ones.
+    pub fn merge(mut self, answers: Self) -> Self {
+        self.0 .0.extend(answers.0 .0);
+        self
+    }
+
+    fn insert(&mut self, key: &str, val: String) {
+        self.0 .0.insert(answer_key(key), val);
+    }
+
+    fn insert_value(&mut self, key: &str, val: Value) {
+        match val {
+            Value::Table(table) => {
+                for (subkey, val) in table {
+                    self.insert_value(&format!("{key} {subkey}"), val);
+                }
+            }
+            Value::String(val) => self.insert(key, val),
+            val => self.insert(key, val.to_string()),
+        }
     }
+}
+
+impl From<Value> for WizardAnswers {
+    fn from(val: Value) -> Self {
+        let mut answers = Self::default();
+        answers.insert_value("", val);
+        answers
+    }
+}
 
-    Ok(())
+impl<'de> Deserialize<'de> for WizardAnswers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Value::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl AnswerProvider for WizardAnswers {
+    fn get(&self, key: &str) -> Option<String> {
+        self.0.get(key)
+    }
 }
@@ -1,12 +1,102 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+use dirs::home_dir;
 use email::notmuch::config::NotmuchConfig;
+use shellexpand_utils::expand;
 
 use crate::{terminal::prompt, Result};
 
 pub fn start() -> Result<NotmuchConfig> {
-    let config = NotmuchConfig {
-        database_path: Some(prompt::path("Notmuch database path:", None::<&str>)?),
+    let config_path = detect_config_path();
+    let default_database_path = detect_database_path(config_path.as_ref());
+
+    let database_path = prompt::path(
+        "Notmuch database path:",
+        default_database_path.as_ref(),
+        None,
+    )?;
+
+    let mut config = NotmuchConfig {
+        database_path: Some(database_path),
         ..Default::default()
     };
 
+    if !prompt::bool("Configure advanced Notmuch options?", false, None)? {
+        return Ok(config);
+    }
+
+    config.maildir_path = prompt::some_text(
+        "Maildir path:",
+        None,
+        &[],
+        Some("Leave empty to use the database path"),
+    )?
+    .map(expand::path);
+
+    let default_config_path = config_path.map(|path| path.display().to_string());
+    config.config_path = prompt::some_text(
+        "Notmuch configuration path:",
+        default_config_path.as_deref(),
+        &[],
+        Some("Leave empty to use the default configuration"),
+    )?
+    .map(expand::path);
+
+    config.profile = prompt::some_text(
+        "Notmuch profile:",
+        env::var("NOTMUCH_PROFILE").ok().as_deref(),
+        &[],
+        Some("Leave empty to use the default profile"),
+    )?;
+
     Ok(config)
 }
+
+/// Find the Notmuch configuration file, from `$NOTMUCH_CONFIG` or
+/// at `~/.notmuch-config`.
+fn detect_config_path() -> Option<PathBuf> {
+    env::var_os("NOTMUCH_CONFIG")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".notmuch-config")))
+        .filter(|path| path.is_file())
+}
+
+/// Find the Notmuch database path using `notmuch config get
+/// database.path`, falling back to the `[database]` section of the
+/// given configuration file.
+fn detect_database_path(config_path: Option<&PathBuf>) -> Option<PathBuf> {
+    let output = Command::new("notmuch")
+        .args(["config", "get", "database.path"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success());
+
+    if let Some(output) = output {
+        let path = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+        if !path.is_empty() {
+            return Some(expand::path(path));
+        }
+    }
+
+    let config = fs::read_to_string(config_path?).ok()?;
+    let mut in_database_section = false;
+
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_database_section = line == "[database]";
+        } else if let Some((key, value)) = line.split_once('=') {
+            if in_database_section && key.trim() == "path" {
+                let path = expand::path(value.trim());
+
+                // relative paths are relative to the home directory
+                return match home_dir() {
+                    Some(home) if path.is_relative() => Some(home.join(path)),
+                    _ => Some(path),
+                };
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use secret::Secret;
+
+use crate::{
+    terminal::{
+        config::{encrypt_value, DEFAULT_ENCRYPT_CMD, ENCRYPTED_PREFIX},
+        print, prompt,
+    },
+    Result,
+};
+
+/// Where the wizard stores a password.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PasswdStorage {
+    #[cfg(feature = "keyring")]
+    Keyring,
+    Encrypted,
+    Raw,
+    Command,
+}
+
+impl fmt::Display for PasswdStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                #[cfg(feature = "keyring")]
+                Self::Keyring => "Ask my password, then save it in my system's global keyring",
+                Self::Encrypted =>
+                    "Ask my password, then save it encrypted in the configuration file",
+                Self::Raw => "Ask my password, then save it in the configuration file (not safe)",
+                Self::Command => "Ask me a shell command that exposes my password",
+            }
+        )
+    }
+}
+
+/// Ask where to store the password, then ask for the password.
+///
+/// The label names the password in prompts, like `IMAP`. The entry
+/// suffixes the keyring entry name, like `imap-passwd`. The system
+/// keyring is only offered when it is available.
+pub async fn start(account_name: &str, label: &str, entry: &str) -> Result<Secret> {
+    let mut storages = Vec::new();
+
+    #[cfg(feature = "keyring")]
+    if keyring_available().await {
+        storages.push(PasswdStorage::Keyring);
+    } else {
+        print::warn("Your system's global keyring is not available, it cannot store passwords.");
+    }
+
+    storages.extend([
+        PasswdStorage::Encrypted,
+        PasswdStorage::Raw,
+        PasswdStorage::Command,
+    ]);
+
+    let storage = prompt::item(
+        format!("{label} authentication strategy:"),
+        storages,
+        None,
+        None,
+    )?;
+
+    let password_prompt = format!("{label} password:");
+
+    let secret = match storage {
+        #[cfg(feature = "keyring")]
+        PasswdStorage::Keyring => {
+            let secret = Secret::try_new_keyring_entry(format!("{account_name}-{entry}"))?;
+            secret
+                .set_if_keyring(prompt::password(&password_prompt, None)?)
+                .await?;
+            secret
+        }
+        PasswdStorage::Encrypted => {
+            let cmd = prompt::text(
+                "Shell command to encrypt the password:",
+                Some(DEFAULT_ENCRYPT_CMD),
+                &[],
+                Some("The encrypted password is decrypted with the decrypt-cmd option"),
+            )?;
+
+            let passwd = prompt::password(&password_prompt, None)?;
+            let ciphertext = encrypt_value(&passwd, &cmd)?;
+
+            print::hint("Set the decrypt-cmd option if the default gpg command cannot decrypt it.");
+            Secret::new_raw(format!("{ENCRYPTED_PREFIX}{ciphertext}"))
+        }
+        PasswdStorage::Raw => Secret::new_raw(prompt::password(&password_prompt, None)?),
+        PasswdStorage::Command => Secret::new_command(prompt::text(
+            "Shell command:",
+            Some(&format!("pass show {account_name}")),
+            &[],
+            None,
+        )?),
+    };
+
+    Ok(secret)
+}
+
+/// Check that the system keyring can be reached, by looking for an
+/// entry that is not expected to exist.
+#[cfg(feature = "keyring")]
+async fn keyring_available() -> bool {
+    match Secret::try_new_keyring_entry("pimalaya-keyring-probe") {
+        Ok(secret) => secret.find().await.is_ok(),
+        Err(_) => false,
+    }
+}
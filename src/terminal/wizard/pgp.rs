@@ -0,0 +1,215 @@
+#[cfg(feature = "pgp-commands")]
+use email::account::config::pgp::CmdsPgpConfig;
+#[cfg(feature = "pgp-gpg")]
+use email::account::config::pgp::GpgConfig;
+use email::account::config::pgp::PgpConfig;
+#[cfg(feature = "pgp-native")]
+use email::account::config::pgp::{
+    NativePgpConfig, NativePgpPublicKeysResolver, NativePgpSecretKey,
+};
+use email_address::EmailAddress;
+#[cfg(feature = "pgp-native")]
+use secret::Secret;
+
+use crate::{
+    terminal::{print, prompt},
+    Result,
+};
+
+static BACKENDS: &[&str] = &[
+    #[cfg(feature = "pgp-gpg")]
+    GPG,
+    #[cfg(feature = "pgp-commands")]
+    COMMANDS,
+    #[cfg(feature = "pgp-native")]
+    NATIVE,
+];
+
+#[cfg(feature = "pgp-gpg")]
+const GPG: &str = "GPG, using keys from my GnuPG keyring";
+#[cfg(feature = "pgp-commands")]
+const COMMANDS: &str = "Shell commands, for example gpg or sq";
+#[cfg(feature = "pgp-native")]
+const NATIVE: &str = "Native Rust implementation, using key files";
+
+#[cfg(feature = "pgp-native")]
+static PASSPHRASES: &[&str] = &[
+    NO_PASSPHRASE,
+    RAW,
+    #[cfg(feature = "keyring")]
+    KEYRING,
+    CMD,
+];
+
+#[cfg(feature = "pgp-native")]
+const NO_PASSPHRASE: &str = "My secret key is not protected by a passphrase";
+#[cfg(feature = "pgp-native")]
+const RAW: &str = "Ask my passphrase, then save it in the configuration file (not safe)";
+#[cfg(all(feature = "pgp-native", feature = "keyring"))]
+const KEYRING: &str = "Ask my passphrase, then save it in my system's global keyring";
+#[cfg(feature = "pgp-native")]
+const CMD: &str = "Ask me a shell command that exposes my passphrase";
+
+#[cfg(feature = "pgp-native")]
+const DEFAULT_KEY_SERVERS: &str = "hkps://keys.openpgp.org, hkps://keys.mailvelope.com";
+
+/// Configure PGP encryption and signing of the given account.
+///
+/// Returns `None` when the user does not want to use PGP.
+#[allow(unused_variables)]
+pub async fn start(
+    account_name: impl AsRef<str>,
+    email: &EmailAddress,
+    config: Option<&PgpConfig>,
+) -> Result<Option<PgpConfig>> {
+    let account_name = account_name.as_ref();
+
+    if !prompt::bool(
+        "Would you like to encrypt and sign messages with PGP?",
+        config.is_some(),
+        None,
+    )? {
+        return Ok(None);
+    }
+
+    let default_backend = config.map(|config| match config {
+        #[cfg(feature = "pgp-commands")]
+        PgpConfig::Commands(_) => &COMMANDS,
+        #[cfg(feature = "pgp-gpg")]
+        PgpConfig::Gpg(_) => &GPG,
+        #[cfg(feature = "pgp-native")]
+        PgpConfig::Native(_) => &NATIVE,
+    });
+
+    let config = match prompt::item("PGP backend:", BACKENDS, default_backend, None)? {
+        #[cfg(feature = "pgp-gpg")]
+        &GPG => {
+            print::info(format!(
+                "GPG will use the keys of your GnuPG keyring matching {email}."
+            ));
+            PgpConfig::Gpg(GpgConfig::default())
+        }
+        #[cfg(feature = "pgp-commands")]
+        &COMMANDS => PgpConfig::Commands(configure_commands()?),
+        #[cfg(feature = "pgp-native")]
+        &NATIVE => PgpConfig::Native(configure_native(account_name).await?),
+        _ => unreachable!(),
+    };
+
+    Ok(Some(config))
+}
+
+#[cfg(feature = "pgp-commands")]
+fn configure_commands() -> Result<CmdsPgpConfig> {
+    let key_id = prompt::some_text(
+        "Key id used to sign messages:",
+        None::<&str>,
+        &[],
+        Some("Leave empty to use the default key of the command"),
+    )?;
+
+    let default_sign_cmd = match &key_id {
+        Some(id) => format!("gpg --sign --quiet --armor --local-user {id}"),
+        None => String::from("gpg --sign --quiet --armor"),
+    };
+
+    let encrypt_cmd = prompt::text(
+        "Shell command to encrypt messages:",
+        Some("gpg --encrypt --quiet --armor <recipients>"),
+        &[],
+        Some("<recipients> is replaced by the recipients of the message"),
+    )?;
+
+    let decrypt_cmd = prompt::text(
+        "Shell command to decrypt messages:",
+        Some("gpg --decrypt --quiet"),
+        &[],
+        None,
+    )?;
+
+    let sign_cmd = prompt::text(
+        "Shell command to sign messages:",
+        Some(&default_sign_cmd),
+        &[],
+        None,
+    )?;
+
+    let verify_cmd = prompt::text(
+        "Shell command to verify signatures:",
+        Some("gpg --verify --quiet"),
+        &[],
+        None,
+    )?;
+
+    Ok(CmdsPgpConfig {
+        encrypt_cmd: Some(encrypt_cmd.into()),
+        decrypt_cmd: Some(decrypt_cmd.into()),
+        sign_cmd: Some(sign_cmd.into()),
+        verify_cmd: Some(verify_cmd.into()),
+        ..Default::default()
+    })
+}
+
+#[cfg(feature = "pgp-native")]
+async fn configure_native(account_name: &str) -> Result<NativePgpConfig> {
+    let secret_key_path = prompt::path("Secret key path:", None::<&str>, None)?;
+
+    let secret_key_passphrase =
+        match prompt::item("Secret key passphrase strategy:", PASSPHRASES, None, None)? {
+            &NO_PASSPHRASE => Secret::default(),
+            #[cfg(feature = "keyring")]
+            &KEYRING => {
+                let secret = Secret::try_new_keyring_entry(format!(
+                    "{account_name}-pgp-secret-key-passphrase"
+                ))?;
+                secret
+                    .set_if_keyring(prompt::password("Secret key passphrase:", None)?)
+                    .await?;
+                secret
+            }
+            &RAW => Secret::new_raw(prompt::password("Secret key passphrase:", None)?),
+            &CMD => Secret::new_command(prompt::text(
+                "Shell command:",
+                Some(&format!("pass show {account_name}-pgp")),
+                &[],
+                None,
+            )?),
+            _ => unreachable!(),
+        };
+
+    let mut public_keys_resolvers = Vec::new();
+
+    if prompt::bool(
+        "Look for public keys of recipients using WKD?",
+        true,
+        Some("Web Key Directory, served by the domain of the recipient"),
+    )? {
+        public_keys_resolvers.push(NativePgpPublicKeysResolver::Wkd);
+    }
+
+    let key_servers = prompt::some_text(
+        "Key servers used to look for public keys:",
+        Some(DEFAULT_KEY_SERVERS),
+        &[],
+        Some("Comma-separated list, leave empty to disable key servers"),
+    )?;
+
+    if let Some(key_servers) = key_servers {
+        let key_servers: Vec<String> = key_servers
+            .split(',')
+            .map(str::trim)
+            .filter(|server| !server.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+
+        if !key_servers.is_empty() {
+            public_keys_resolvers.push(NativePgpPublicKeysResolver::KeyServers(key_servers));
+        }
+    }
+
+    Ok(NativePgpConfig {
+        secret_key: NativePgpSecretKey::Path(secret_key_path),
+        secret_key_passphrase,
+        public_keys_resolvers,
+    })
+}
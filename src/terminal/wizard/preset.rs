@@ -0,0 +1,230 @@
+use std::fmt;
+
+use crate::{terminal::prompt, Result};
+
+/// Settings of a well-known email provider, used by the wizard to
+/// pre-fill and skip prompts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Preset {
+    pub name: &'static str,
+    /// Email domains served by the provider.
+    pub domains: &'static [&'static str],
+    pub imap: Option<PresetServer>,
+    pub smtp: Option<PresetServer>,
+    pub oauth2: Option<PresetOAuth2>,
+    /// Folder aliases, as `(alias, folder)` pairs.
+    pub folder_aliases: &'static [(&'static str, &'static str)],
+    /// Warnings shown to the user before configuring the account.
+    pub quirks: &'static [&'static str],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PresetServer {
+    pub host: &'static str,
+    pub port: u16,
+    pub encryption: PresetEncryption,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PresetEncryption {
+    Tls,
+    StartTls,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PresetOAuth2 {
+    pub name: &'static str,
+    /// Whether the provider rejects password authentication.
+    pub required: bool,
+    /// Client id usable by anyone, if the provider has one.
+    pub client_id: Option<&'static str>,
+    pub auth_url: &'static str,
+    pub token_url: &'static str,
+    pub imap_scopes: &'static [&'static str],
+    pub smtp_scopes: &'static [&'static str],
+}
+
+impl fmt::Display for PresetOAuth2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub const GOOGLE_OAUTH2: PresetOAuth2 = PresetOAuth2 {
+    name: "Gmail / Google Workspace",
+    required: false,
+    client_id: None,
+    auth_url: "https://accounts.google.com/o/oauth2/v2/auth",
+    token_url: "https://www.googleapis.com/oauth2/v3/token",
+    imap_scopes: &["https://mail.google.com/"],
+    smtp_scopes: &["https://mail.google.com/"],
+};
+
+pub const MICROSOFT_OAUTH2: PresetOAuth2 = PresetOAuth2 {
+    name: "Outlook / Microsoft 365",
+    required: true,
+    client_id: None,
+    auth_url: "https://login.microsoftonline.com/common/oauth2/v2.0/authorize",
+    token_url: "https://login.microsoftonline.com/common/oauth2/v2.0/token",
+    imap_scopes: &[
+        "https://outlook.office.com/IMAP.AccessAsUser.All",
+        "offline_access",
+    ],
+    smtp_scopes: &["https://outlook.office.com/SMTP.Send", "offline_access"],
+};
+
+/// OAuth 2.0 presets, offered even when the email domain is not
+/// known, since both providers host custom domains.
+pub static OAUTH2_PRESETS: &[PresetOAuth2] = &[GOOGLE_OAUTH2, MICROSOFT_OAUTH2];
+
+pub static PRESETS: &[Preset] = &[
+    Preset {
+        name: "Gmail",
+        domains: &["gmail.com", "googlemail.com"],
+        imap: Some(PresetServer {
+            host: "imap.gmail.com",
+            port: 993,
+            encryption: PresetEncryption::Tls,
+        }),
+        smtp: Some(PresetServer {
+            host: "smtp.gmail.com",
+            port: 465,
+            encryption: PresetEncryption::Tls,
+        }),
+        oauth2: Some(GOOGLE_OAUTH2),
+        folder_aliases: &[
+            ("sent", "[Gmail]/Sent Mail"),
+            ("drafts", "[Gmail]/Drafts"),
+            ("trash", "[Gmail]/Trash"),
+        ],
+        quirks: &[
+            "Google passwords cannot be used directly, use an app password or OAuth 2.0 instead, see:",
+            "https://github.com/pimalaya/himalaya?tab=readme-ov-file#configuration",
+        ],
+    },
+    Preset {
+        name: "Outlook",
+        domains: &["outlook.com", "hotmail.com", "live.com", "msn.com"],
+        imap: Some(PresetServer {
+            host: "outlook.office365.com",
+            port: 993,
+            encryption: PresetEncryption::Tls,
+        }),
+        smtp: Some(PresetServer {
+            host: "smtp-mail.outlook.com",
+            port: 587,
+            encryption: PresetEncryption::StartTls,
+        }),
+        oauth2: Some(MICROSOFT_OAUTH2),
+        folder_aliases: &[("sent", "Sent"), ("trash", "Deleted")],
+        quirks: &["Outlook does not accept passwords anymore, OAuth 2.0 is required."],
+    },
+    Preset {
+        name: "Fastmail",
+        domains: &["fastmail.com", "fastmail.fm"],
+        imap: Some(PresetServer {
+            host: "imap.fastmail.com",
+            port: 993,
+            encryption: PresetEncryption::Tls,
+        }),
+        smtp: Some(PresetServer {
+            host: "smtp.fastmail.com",
+            port: 465,
+            encryption: PresetEncryption::Tls,
+        }),
+        oauth2: None,
+        folder_aliases: &[],
+        quirks: &["Fastmail requires an app password, generate one from Settings › Privacy & Security."],
+    },
+    Preset {
+        name: "iCloud",
+        domains: &["icloud.com", "me.com", "mac.com"],
+        imap: Some(PresetServer {
+            host: "imap.mail.me.com",
+            port: 993,
+            encryption: PresetEncryption::Tls,
+        }),
+        smtp: Some(PresetServer {
+            host: "smtp.mail.me.com",
+            port: 587,
+            encryption: PresetEncryption::StartTls,
+        }),
+        oauth2: None,
+        folder_aliases: &[("sent", "Sent Messages"), ("trash", "Deleted Messages")],
+        quirks: &["iCloud requires an app-specific password, generate one from appleid.apple.com."],
+    },
+    Preset {
+        name: "Proton Mail Bridge",
+        domains: &["proton.me", "protonmail.com", "protonmail.ch", "pm.me"],
+        imap: Some(PresetServer {
+            host: "127.0.0.1",
+            port: 1143,
+            encryption: PresetEncryption::StartTls,
+        }),
+        smtp: Some(PresetServer {
+            host: "127.0.0.1",
+            port: 1025,
+            encryption: PresetEncryption::StartTls,
+        }),
+        oauth2: None,
+        folder_aliases: &[],
+        quirks: &[
+            "Proton Mail requires the Proton Mail Bridge to be running.",
+            "Use the password displayed by the Bridge, not your Proton password.",
+        ],
+    },
+];
+
+/// Find the preset of the provider serving the given email domain.
+pub fn find(domain: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| {
+        preset
+            .domains
+            .iter()
+            .any(|d| d.eq_ignore_ascii_case(domain))
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum OAuth2PresetChoice {
+    Preset(PresetOAuth2),
+    None,
+}
+
+impl fmt::Display for OAuth2PresetChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Preset(preset) => write!(f, "Use the {preset} preset"),
+            Self::None => write!(f, "Enter URLs and scopes manually"),
+        }
+    }
+}
+
+/// Ask the user to choose between OAuth 2.0 presets of the given
+/// protocol, the given one being the default choice.
+pub fn prompt_oauth2(
+    protocol: &str,
+    default: Option<PresetOAuth2>,
+) -> Result<Option<PresetOAuth2>> {
+    let choices = OAUTH2_PRESETS
+        .iter()
+        .copied()
+        .map(OAuth2PresetChoice::Preset)
+        .chain([OAuth2PresetChoice::None]);
+
+    let default = default
+        .map(OAuth2PresetChoice::Preset)
+        .unwrap_or(OAuth2PresetChoice::None);
+
+    let choice = prompt::item(
+        format!("{protocol} OAuth 2.0 preset:"),
+        choices,
+        Some(default),
+        None,
+    )?;
+
+    match choice {
+        OAuth2PresetChoice::Preset(preset) => Ok(Some(preset)),
+        OAuth2PresetChoice::None => Ok(None),
+    }
+}
@@ -0,0 +1,152 @@
+use std::{io, time::Duration};
+
+use tokio::{net::TcpStream, task::JoinHandle, time::timeout};
+
+use crate::terminal::print;
+
+/// Maximum duration of a connection attempt.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connection attempt running in the background, so that wizards
+/// can warn about unreachable servers without making the user wait.
+///
+/// When the server uses implicit TLS, the TLS handshake is checked as
+/// well, which catches ports expecting STARTTLS and certificates not
+/// trusted by the system. This is only done with the `rustls` cargo
+/// feature. STARTTLS handshakes are not checked, since they need to
+/// speak the protocol first, and custom root certificates are only
+/// used when the configuration is tested.
+#[derive(Debug)]
+pub struct Probe {
+    host: String,
+    port: u16,
+    tls: bool,
+    task: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Probe {
+    /// Start connecting to the given host and port, using implicit
+    /// TLS if `tls` is `true`.
+    pub fn spawn(host: impl ToString, port: u16, tls: bool) -> Self {
+        let host = host.to_string();
+        let task_host = host.clone();
+
+        let task = tokio::spawn(async move {
+            match timeout(TIMEOUT, connect(task_host, port, tls)).await {
+                Ok(res) => res,
+                Err(_) => Err(io::ErrorKind::TimedOut.into()),
+            }
+        });
+
+        Self {
+            host,
+            port,
+            tls,
+            task: Some(task),
+        }
+    }
+
+    /// Whether the probe targets the given host and port, using
+    /// implicit TLS or not.
+    pub fn targets(&self, host: &str, port: u16, tls: bool) -> bool {
+        self.host == host && self.port == port && self.tls == tls
+    }
+
+    /// Warn if the connection failed.
+    ///
+    /// When not waiting, nothing is reported until the connection
+    /// attempt is over. The result is reported only once.
+    pub async fn report(&mut self, wait: bool) {
+        let Some(task) = self.task.take() else {
+            return;
+        };
+
+        if !wait && !task.is_finished() {
+            self.task = Some(task);
+            return;
+        }
+
+        let (host, port) = (&self.host, self.port);
+
+        match task.await {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => print::warn(format!("Cannot connect to {host}:{port}: {err}")),
+            Err(err) => print::warn(format!("Cannot check connection to {host}:{port}: {err}")),
+        }
+    }
+}
+
+async fn connect(host: String, port: u16, tls: bool) -> io::Result<()> {
+    let stream = TcpStream::connect((host.as_str(), port)).await?;
+
+    if tls {
+        handshake(&host, stream).await?;
+    }
+
+    Ok(())
+}
+
+/// Perform a TLS handshake over the given stream, verifying the
+/// server certificate against the system root certificates, the same
+/// way the IMAP client does.
+#[cfg(feature = "rustls")]
+async fn handshake(host: &str, stream: TcpStream) -> io::Result<()> {
+    use std::sync::Arc;
+
+    use tokio_rustls::{rustls::pki_types::ServerName, TlsConnector};
+
+    let name = ServerName::try_from(host.to_owned())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let connector = TlsConnector::from(Arc::new(rustls_platform_verifier::tls_config()));
+
+    match connector.connect(name, stream).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(io::Error::new(
+            err.kind(),
+            format!("TLS handshake failed: {err}"),
+        )),
+    }
+}
+
+/// TLS handshakes are only checked with rustls, see [`Probe`].
+#[cfg(not(feature = "rustls"))]
+async fn handshake(_host: &str, _stream: TcpStream) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{net::TcpListener, time::sleep};
+
+    use super::*;
+
+    /// Spawn a server greeting its client in plain text, like IMAP
+    /// servers expecting STARTTLS do.
+    async fn spawn_plain_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            stream.writable().await.unwrap();
+            stream.try_write(b"* OK IMAP4rev1 ready\r\n").unwrap();
+            sleep(TIMEOUT).await;
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn connect_without_tls() {
+        let port = spawn_plain_server().await;
+        assert!(connect("127.0.0.1".into(), port, false).await.is_ok());
+    }
+
+    #[cfg(feature = "rustls")]
+    #[tokio::test]
+    async fn connect_with_tls_to_plain_server() {
+        let port = spawn_plain_server().await;
+        let err = connect("127.0.0.1".into(), port, true).await.unwrap_err();
+        assert!(err.to_string().starts_with("TLS handshake failed"));
+    }
+}
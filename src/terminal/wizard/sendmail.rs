@@ -6,6 +6,8 @@ pub fn start() -> Result<SendmailConfig> {
     let cmd = prompt::text(
         "Sendmail-compatible shell command to send emails",
         Some(&SENDMAIL_DEFAULT_COMMAND),
+        &[],
+        None,
     )?;
 
     let config = SendmailConfig {
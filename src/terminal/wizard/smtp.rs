@@ -13,9 +13,21 @@ use email_address::EmailAddress;
 #[cfg(feature = "oauth2")]
 use oauth::v2_0::{AuthorizationCodeGrant, Client};
 use once_cell::sync::Lazy;
+#[cfg(feature = "oauth2")]
 use secret::Secret;
 
-use crate::{terminal::prompt, Result};
+#[cfg(feature = "oauth2")]
+use super::preset::prompt_oauth2;
+use super::{
+    discovery, passwd,
+    preset::{Preset, PresetEncryption},
+    probe::Probe,
+    tls, Steps,
+};
+use crate::{
+    terminal::{print, prompt},
+    Result,
+};
 
 static ENCRYPTIONS: Lazy<[Encryption; 3]> = Lazy::new(|| {
     [
@@ -25,22 +37,11 @@ static ENCRYPTIONS: Lazy<[Encryption; 3]> = Lazy::new(|| {
     ]
 });
 
-static SECRETS: &[&str] = &[
-    RAW,
-    #[cfg(feature = "keyring")]
-    KEYRING,
-    CMD,
-];
-
-const RAW: &str = "Ask my password, then save it in the configuration file (not safe)";
-#[cfg(feature = "keyring")]
-const KEYRING: &str = "Ask my password, then save it in my system's global keyring";
-const CMD: &str = "Ask me a shell command that exposes my password";
-
 pub async fn start(
     account_name: impl AsRef<str>,
     email: &EmailAddress,
     autoconfig: Option<&AutoConfig>,
+    preset: Option<&Preset>,
 ) -> Result<SmtpConfig> {
     let account_name = account_name.as_ref();
 
@@ -51,13 +52,14 @@ pub async fn start(
             .find(|server| matches!(server.server_type(), ServerType::Smtp))
     });
 
-    let autoconfig_host = autoconfig_server
-        .and_then(|s| s.hostname())
-        .map(ToOwned::to_owned);
+    let preset_server = preset.and_then(|preset| Some((preset.name, preset.smtp?)));
 
-    let default_host = autoconfig_host.unwrap_or_else(|| format!("smtp.{}", email.domain()));
-
-    let host = prompt::text("SMTP hostname:", Some(&default_host))?;
+    // fall back to DNS SRV records when neither autoconfig nor
+    // presets know the server
+    let srv_server = match (autoconfig_server, preset_server) {
+        (None, None) => discovery::smtp(email.domain()).await,
+        _ => None,
+    };
 
     let autoconfig_encryption = autoconfig_server
         .and_then(|smtp| {
@@ -67,32 +69,23 @@ pub async fn start(
                 SecurityType::Tls => Encryption::Tls(Default::default()),
             })
         })
+        .or_else(|| {
+            srv_server
+                .as_ref()
+                .map(|_| Encryption::StartTls(Default::default()))
+        })
         .unwrap_or_default();
 
     let autoconfig_port = autoconfig_server
         .and_then(|config| config.port())
         .map(ToOwned::to_owned)
+        .or(srv_server.as_ref().map(|srv| srv.port))
         .unwrap_or_else(|| match &autoconfig_encryption {
             Encryption::Tls(_) => 465,
             Encryption::StartTls(_) => 587,
             Encryption::None => 25,
         });
 
-    let encryption = prompt::item(
-        "SMTP encryption:",
-        ENCRYPTIONS.clone(),
-        Some(autoconfig_encryption.clone()),
-    )?;
-
-    let default_port = match encryption {
-        ref encryption if encryption == &autoconfig_encryption => autoconfig_port,
-        Encryption::Tls(_) => 465,
-        Encryption::StartTls(_) => 587,
-        Encryption::None => 25,
-    };
-
-    let port = prompt::u16("SMTP port:", Some(default_port))?;
-
     let autoconfig_login = autoconfig_server.map(|smtp| match smtp.username() {
         Some("%EMAILLOCALPART%") => email.local_part().to_owned(),
         Some("%EMAILADDRESS%") => email.to_string(),
@@ -101,162 +94,340 @@ pub async fn start(
 
     let default_login = autoconfig_login.unwrap_or_else(|| email.to_string());
 
-    let login = prompt::text("SMTP login:", Some(&default_login))?;
+    let mut use_preset = false;
+    let mut host = None;
+    let mut encryption = None;
+    let mut port = None;
+    let mut cert = None;
+    let mut login = None;
+    let mut auth = None;
+    let mut probe: Option<Probe> = None;
 
-    // ------------
+    let mut steps = Steps::default();
 
-    #[cfg(feature = "oauth2")]
-    let auth = {
-        const OAUTH2_MECHANISMS: [OAuth2Method; 2] =
-            [OAuth2Method::XOAuth2, OAuth2Method::OAuthBearer];
+    while steps.current() < 7 {
+        if let Some(probe) = &mut probe {
+            probe.report(false).await;
+        }
 
-        let autoconfig_oauth2 = autoconfig.and_then(|c| c.oauth2());
+        let res = match steps.current() {
+            0 => match preset_server {
+                Some((name, server)) => (|| {
+                    use_preset = prompt::bool(
+                        format!("Use the {name} SMTP server {}:{}?", server.host, server.port),
+                        true,
+                        None,
+                    )?;
+
+                    if use_preset {
+                        host = Some(server.host.to_owned());
+                        encryption = Some(match server.encryption {
+                            PresetEncryption::Tls => Encryption::Tls(Default::default()),
+                            PresetEncryption::StartTls => Encryption::StartTls(Default::default()),
+                        });
+                        port = Some(server.port);
+                    }
+
+                    Ok(())
+                })(),
+                None => steps.skip(),
+            },
+            1..=3 if use_preset => steps.skip(),
+            1 => (|| {
+                let autoconfig_host = autoconfig_server
+                    .and_then(|s| s.hostname())
+                    .map(ToOwned::to_owned);
+
+                let default_host = host
+                    .clone()
+                    .or(autoconfig_host)
+                    .or(srv_server.as_ref().map(|srv| srv.host.clone()))
+                    .unwrap_or_else(|| format!("smtp.{}", email.domain()));
+
+                host = Some(prompt::text("SMTP hostname:", Some(&default_host), &[], None)?);
+                Ok(())
+            })(),
+            2 => (|| {
+                let default_encryption = encryption
+                    .clone()
+                    .unwrap_or_else(|| autoconfig_encryption.clone());
+
+                encryption = Some(prompt::item(
+                    "SMTP encryption:",
+                    ENCRYPTIONS.clone(),
+                    Some(default_encryption),
+                    None,
+                )?);
+
+                Ok(())
+            })(),
+            3 => (|| {
+                let default_port = match encryption.as_ref() {
+                    _ if port.is_some() => port.unwrap_or_default(),
+                    Some(encryption) if encryption == &autoconfig_encryption => autoconfig_port,
+                    Some(Encryption::Tls(_)) => 465,
+                    Some(Encryption::StartTls(_)) => 587,
+                    Some(Encryption::None) | None => 25,
+                };
 
-        let default_oauth2_enabled = autoconfig_server
-            .and_then(|smtp| {
-                smtp.authentication_type()
-                    .into_iter()
-                    .find_map(|t| Option::from(matches!(t, AuthenticationType::OAuth2)))
-            })
-            .filter(|_| autoconfig_oauth2.is_some())
-            .unwrap_or_default();
-
-        let oauth2_enabled = prompt::bool("Enable OAuth 2.0?", default_oauth2_enabled)?;
-
-        if oauth2_enabled {
-            let mut config = OAuth2Config::default();
-
-            config.method = prompt::item(
-                "SMTP OAuth 2.0 mechanism:",
-                OAUTH2_MECHANISMS.clone(),
-                Some(OAuth2Method::XOAuth2),
-            )?;
-
-            config.client_id = prompt::text("SMTP OAuth 2.0 client id:", None)?;
-
-            let client_secret = match prompt::some_secret("SMTP OAuth 2.0 client secret:")? {
-                None => None,
-                Some(raw) => {
-                    let secret = Secret::try_new_keyring_entry(format!(
-                        "{account_name}-smtp-oauth2-client-secret"
-                    ))?;
-                    secret.set_if_keyring(&raw).await?;
-                    config.client_secret = Some(secret);
-                    Some(raw)
+                port = Some(prompt::number(
+                    "SMTP port:",
+                    Some(default_port),
+                    Some(1),
+                    None,
+                    None,
+                )?);
+
+                Ok(())
+            })(),
+            4 => (|| {
+                // there is no certificate to verify without encryption
+                if matches!(encryption, None | Some(Encryption::None)) {
+                    cert = None;
+                    return steps.skip();
                 }
-            };
-
-            config.redirect_scheme = Some(prompt::text(
-                "SMTP OAuth 2.0 redirect URL scheme:",
-                Some("http"),
-            )?);
-
-            config.redirect_host = Some(prompt::text(
-                "SMTP OAuth 2.0 redirect URL hostname:",
-                Some(OAuth2Config::LOCALHOST),
-            )?);
-
-            config.redirect_port = Some(prompt::u16(
-                "SMTP OAuth 2.0 redirect URL port:",
-                Some(OAuth2Config::get_first_available_port()?),
-            )?);
-
-            let default_auth_url = autoconfig_oauth2
-                .map(|config| config.auth_url().to_owned())
-                .unwrap_or_default();
-            config.auth_url =
-                prompt::text("SMTP OAuth 2.0 authorization URL:", Some(&default_auth_url))?;
-
-            let default_token_url = autoconfig_oauth2
-                .map(|config| config.token_url().to_owned())
-                .unwrap_or_default();
-            config.token_url = prompt::text("SMTP OAuth 2.0 token URL:", Some(&default_token_url))?;
-
-            let autoconfig_scopes = autoconfig_oauth2.map(|config| config.scope());
-
-            let prompt_scope = |prompt: &str| -> Result<Option<String>> {
-                Ok(match &autoconfig_scopes {
-                    Some(scopes) => Some(prompt::item(prompt, scopes.to_vec(), None)?.to_string()),
-                    None => Some(prompt::text(prompt, None)?).filter(|scope| !scope.is_empty()),
-                })
-            };
-
-            if let Some(scope) = prompt_scope("SMTP OAuth 2.0 main scope:")? {
-                config.scopes = OAuth2Scopes::Scope(scope);
-            }
 
-            let confirm_additional_scope = || -> Result<bool> {
-                let confirm = prompt::bool("More SMTP OAuth 2.0 scopes?", false)?;
-                Ok(confirm)
-            };
-
-            while confirm_additional_scope()? {
-                let mut scopes = match config.scopes {
-                    OAuth2Scopes::Scope(scope) => vec![scope],
-                    OAuth2Scopes::Scopes(scopes) => scopes,
+                cert = tls::start("SMTP", cert.as_deref())?;
+                Ok(())
+            })(),
+            5 => (|| {
+                let default_login = login.clone().unwrap_or_else(|| default_login.clone());
+                login = Some(prompt::text("SMTP login:", Some(&default_login), &[], None)?);
+                Ok(())
+            })(),
+            _ => async {
+                #[cfg(feature = "oauth2")]
+                let auth_config = {
+                    const OAUTH2_MECHANISMS: [OAuth2Method; 2] =
+                        [OAuth2Method::XOAuth2, OAuth2Method::OAuthBearer];
+
+                    let autoconfig_oauth2 = autoconfig.and_then(|c| c.oauth2());
+                    let preset_oauth2 = preset.and_then(|preset| preset.oauth2);
+
+                    let default_oauth2_enabled = autoconfig_server
+                        .and_then(|smtp| {
+                            smtp.authentication_type()
+                                .into_iter()
+                                .find_map(|t| Option::from(matches!(t, AuthenticationType::OAuth2)))
+                        })
+                        .filter(|_| autoconfig_oauth2.is_some())
+                        .unwrap_or_default()
+                        || preset_oauth2.is_some_and(|oauth2| oauth2.required);
+
+                    let oauth2_enabled = prompt::bool("Enable OAuth 2.0?", default_oauth2_enabled, None)?;
+
+                    if oauth2_enabled {
+                        let mut config = OAuth2Config::default();
+                        let preset_oauth2 = prompt_oauth2("SMTP", preset_oauth2)?;
+
+                        config.method = prompt::item(
+                            "SMTP OAuth 2.0 mechanism:",
+                            OAUTH2_MECHANISMS.clone(),
+                            Some(OAuth2Method::XOAuth2),
+                            None,
+                        )?;
+
+                        config.client_id = match preset_oauth2.and_then(|oauth2| oauth2.client_id) {
+                            Some(client_id) => client_id.to_owned(),
+                            None => prompt::text("SMTP OAuth 2.0 client id:", None, &[], None)?,
+                        };
+
+                        let client_secret = match prompt::some_secret("SMTP OAuth 2.0 client secret:", None)? {
+                            None => None,
+                            Some(raw) => {
+                                let secret = Secret::try_new_keyring_entry(format!(
+                                    "{account_name}-smtp-oauth2-client-secret"
+                                ))?;
+                                secret.set_if_keyring(&raw).await?;
+                                config.client_secret = Some(secret);
+                                Some(raw)
+                            }
+                        };
+
+                        config.redirect_scheme = Some(prompt::text(
+                            "SMTP OAuth 2.0 redirect URL scheme:",
+                            Some("http"),
+                            &[],
+                            None,
+                        )?);
+
+                        config.redirect_host = Some(prompt::text(
+                            "SMTP OAuth 2.0 redirect URL hostname:",
+                            Some(OAuth2Config::LOCALHOST),
+                            &[],
+                            None,
+                        )?);
+
+                        config.redirect_port = Some(prompt::number(
+                            "SMTP OAuth 2.0 redirect URL port:",
+                            Some(OAuth2Config::get_first_available_port()?),
+                            Some(1),
+                            None,
+                            None,
+                        )?);
+
+                        if let Some(oauth2) = preset_oauth2 {
+                            config.auth_url = oauth2.auth_url.to_owned();
+                            config.token_url = oauth2.token_url.to_owned();
+                        } else {
+                            let default_auth_url = autoconfig_oauth2
+                                .map(|config| config.auth_url().to_owned())
+                                .unwrap_or_default();
+                            config.auth_url = prompt::text(
+                                "SMTP OAuth 2.0 authorization URL:",
+                                Some(&default_auth_url),
+                                &[],
+                                None,
+                            )?;
+
+                            let default_token_url = autoconfig_oauth2
+                                .map(|config| config.token_url().to_owned())
+                                .unwrap_or_default();
+                            config.token_url = prompt::text(
+                                "SMTP OAuth 2.0 token URL:",
+                                Some(&default_token_url),
+                                &[],
+                                None,
+                            )?;
+                        }
+
+                        let autoconfig_scopes = autoconfig_oauth2.map(|config| config.scope());
+
+                        let prompt_scope = |prompt: &str| -> Result<Option<String>> {
+                            Ok(match &autoconfig_scopes {
+                                Some(scopes) => {
+                                    Some(prompt::item(prompt, scopes.to_vec(), None, None)?.to_string())
+                                }
+                                None => Some(prompt::text(prompt, None, &[], None)?)
+                                    .filter(|scope| !scope.is_empty()),
+                            })
+                        };
+
+                        let preset_scopes = preset_oauth2
+                            .map(|oauth2| oauth2.smtp_scopes)
+                            .filter(|scopes| !scopes.is_empty());
+
+                        if let Some(scopes) = preset_scopes {
+                            let scopes = scopes.iter().map(ToString::to_string).collect();
+                            config.scopes = OAuth2Scopes::Scopes(scopes);
+                        } else {
+                            if let Some(scope) = prompt_scope("SMTP OAuth 2.0 main scope:")? {
+                                config.scopes = OAuth2Scopes::Scope(scope);
+                            }
+
+                            let confirm_additional_scope = || -> Result<bool> {
+                                let confirm = prompt::bool("More SMTP OAuth 2.0 scopes?", false, None)?;
+                                Ok(confirm)
+                            };
+
+                            while confirm_additional_scope()? {
+                                let mut scopes = match config.scopes {
+                                    OAuth2Scopes::Scope(scope) => vec![scope],
+                                    OAuth2Scopes::Scopes(scopes) => scopes,
+                                };
+
+                                if let Some(scope) = prompt_scope("Additional SMTP OAuth 2.0 scope:")? {
+                                    scopes.push(scope)
+                                }
+
+                                config.scopes = OAuth2Scopes::Scopes(scopes);
+                            }
+                        }
+
+                        config.pkce = prompt::bool(
+                            "Enable PKCE verification?",
+                            true,
+                            Some("PKCE protects the authorization code exchange, most providers support it"),
+                        )?;
+
+                        crate::terminal::print::section(
+                            "To complete your OAuth 2.0 setup, click on the following link:",
+                        );
+
+                        let client = Client::new(
+                            config.client_id.clone(),
+                            client_secret,
+                            config.auth_url.clone(),
+                            config.token_url.clone(),
+                            config.redirect_scheme.clone().unwrap(),
+                            config.redirect_host.clone().unwrap(),
+                            config.redirect_port.clone().unwrap(),
+                        )?;
+
+                        let mut auth_code_grant = AuthorizationCodeGrant::new();
+
+                        if config.pkce {
+                            auth_code_grant = auth_code_grant.with_pkce();
+                        }
+
+                        for scope in config.scopes.clone() {
+                            auth_code_grant = auth_code_grant.with_scope(scope);
+                        }
+
+                        let (redirect_url, csrf_token) = auth_code_grant.get_redirect_url(&client);
+
+                        print::link("Open the authorization URL", redirect_url.to_string());
+                        eprintln!();
+
+                        let spinner = print::spinner("Waiting for the OAuth 2.0 redirection…");
+                        let redirection = auth_code_grant
+                            .wait_for_redirection(&client, csrf_token)
+                            .await;
+                        drop(spinner);
+                        let (access_token, refresh_token) = redirection?;
+
+                        config.access_token =
+                            Secret::try_new_keyring_entry(format!("{account_name}-smtp-oauth2-access-token"))?;
+                        config.access_token.set_if_keyring(access_token).await?;
+
+                        if let Some(refresh_token) = &refresh_token {
+                            config.refresh_token = Secret::try_new_keyring_entry(format!(
+                                "{account_name}-smtp-oauth2-refresh-token"
+                            ))?;
+                            config.refresh_token.set_if_keyring(refresh_token).await?;
+                        }
+
+                        SmtpAuthConfig::OAuth2(config)
+                    } else {
+                        configure_passwd(account_name).await?
+                    }
                 };
 
-                if let Some(scope) = prompt_scope("Additional SMTP OAuth 2.0 scope:")? {
-                    scopes.push(scope)
-                }
+                #[cfg(not(feature = "oauth2"))]
+                let auth_config = configure_passwd(account_name).await?;
 
-                config.scopes = OAuth2Scopes::Scopes(scopes);
+                auth = Some(auth_config);
+                Ok(())
             }
+            .await,
+        };
 
-            config.pkce = prompt::bool("Enable PKCE verification?", true)?;
-
-            crate::terminal::print::section(
-                "To complete your OAuth 2.0 setup, click on the following link:",
-            );
-
-            let client = Client::new(
-                config.client_id.clone(),
-                client_secret,
-                config.auth_url.clone(),
-                config.token_url.clone(),
-                config.redirect_scheme.clone().unwrap(),
-                config.redirect_host.clone().unwrap(),
-                config.redirect_port.clone().unwrap(),
-            )?;
+        steps.advance(res)?;
 
-            let mut auth_code_grant = AuthorizationCodeGrant::new();
+        // check that the server is reachable as soon as it is known
+        if let (Some(host), Some(port)) = (&host, port) {
+            let tls = matches!(encryption, Some(Encryption::Tls(_)));
 
-            if config.pkce {
-                auth_code_grant = auth_code_grant.with_pkce();
+            if !probe
+                .as_ref()
+                .is_some_and(|probe| probe.targets(host, port, tls))
+            {
+                probe = Some(Probe::spawn(host, port, tls));
             }
+        }
+    }
 
-            for scope in config.scopes.clone() {
-                auth_code_grant = auth_code_grant.with_scope(scope);
-            }
-
-            let (redirect_url, csrf_token) = auth_code_grant.get_redirect_url(&client);
-
-            println!("{redirect_url}");
-            println!();
-
-            let (access_token, refresh_token) = auth_code_grant
-                .wait_for_redirection(&client, csrf_token)
-                .await?;
-
-            config.access_token =
-                Secret::try_new_keyring_entry(format!("{account_name}-smtp-oauth2-access-token"))?;
-            config.access_token.set_if_keyring(access_token).await?;
-
-            if let Some(refresh_token) = &refresh_token {
-                config.refresh_token = Secret::try_new_keyring_entry(format!(
-                    "{account_name}-smtp-oauth2-refresh-token"
-                ))?;
-                config.refresh_token.set_if_keyring(refresh_token).await?;
-            }
+    if let Some(probe) = &mut probe {
+        probe.report(true).await;
+    }
 
-            SmtpAuthConfig::OAuth2(config)
-        } else {
-            configure_passwd(account_name).await?
-        }
+    let (Some(host), Some(mut encryption), Some(port), Some(login), Some(auth)) =
+        (host, encryption, port, login, auth)
+    else {
+        unreachable!("SMTP wizard steps should all be completed");
     };
 
-    #[cfg(not(feature = "oauth2"))]
-    let auth = configure_passwd(account_name).await?;
+    tls::set_cert(&mut encryption, cert);
 
     Ok(SmtpConfig {
         host,
@@ -268,22 +439,6 @@ pub async fn start(
 }
 
 pub(crate) async fn configure_passwd(account_name: &str) -> Result<SmtpAuthConfig> {
-    let secret = match prompt::item("SMTP authentication strategy:", SECRETS, None)? {
-        #[cfg(feature = "keyring")]
-        &KEYRING => {
-            let secret = Secret::try_new_keyring_entry(format!("{account_name}-smtp-passwd"))?;
-            secret
-                .set_if_keyring(prompt::password("SMTP password:")?)
-                .await?;
-            secret
-        }
-        &RAW => Secret::new_raw(prompt::password("SMTP password:")?),
-        &CMD => Secret::new_command(prompt::text(
-            "Shell command:",
-            Some(&format!("pass show {account_name}")),
-        )?),
-        _ => unreachable!(),
-    };
-
+    let secret = passwd::start(account_name, "SMTP", "smtp-passwd").await?;
     Ok(SmtpAuthConfig::Password(PasswordConfig(secret)))
 }
@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+
+use email::tls::Encryption;
+
+use crate::{terminal::prompt, Result};
+
+/// Ask for a custom root certificate used to verify the server of
+/// the given protocol.
+///
+/// Only the root certificate can be customized: the TLS config of
+/// the email crate 0.26 has no client certificate nor key, and no
+/// way to skip certificate verification, so these options are not
+/// offered until the email crate supports them.
+pub fn start(protocol: &str, cert: Option<&Path>) -> Result<Option<PathBuf>> {
+    let custom_cert = prompt::bool(
+        format!("Use a custom root certificate to verify the {protocol} server?"),
+        cert.is_some(),
+        Some("Needed for self-signed certificates, like the Proton Mail Bridge one"),
+    )?;
+
+    if !custom_cert {
+        return Ok(None);
+    }
+
+    let cert = prompt::path("Root certificate path (PEM):", cert, None)?;
+
+    Ok(Some(cert))
+}
+
+/// Set the root certificate of the given encryption, if any.
+pub fn set_cert(encryption: &mut Encryption, cert: Option<PathBuf>) {
+    if let Encryption::Tls(tls) | Encryption::StartTls(tls) = encryption {
+        tls.cert = cert;
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::{self, Write};
+
+use crossterm::terminal;
+use unicode_width::UnicodeWidthChar;
+
+/// Width used when the terminal width cannot be detected.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Return the current terminal width.
+///
+/// The width is queried on every call, so that resizes are taken
+/// into account.
+pub fn width() -> usize {
+    match terminal::size() {
+        Ok((cols, _)) if cols > 0 => cols as usize,
+        _ => DEFAULT_WIDTH,
+    }
+}
+
+/// Wrap the given text at the given width.
+///
+/// Lines are broken at spaces only, so words are never split: a word
+/// longer than the width is kept on its own line. Continuation lines
+/// keep the indentation of the line they come from, and spaces
+/// between words are kept as they are, except at line breaks. ANSI
+/// escape sequences do not count in the width.
+pub fn fill(text: &str, width: usize) -> String {
+    let mut output = String::with_capacity(text.len());
+
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+
+        fill_line(&mut output, line, width);
+    }
+
+    output
+}
+
+fn fill_line(output: &mut String, line: &str, width: usize) {
+    if visible_width(line) <= width {
+        output.push_str(line);
+        return;
+    }
+
+    let trimmed = line.trim_start_matches(' ');
+    let indent = &line[..line.len() - trimmed.len()];
+    let indent_width = indent.len();
+
+    output.push_str(indent);
+    let mut line_width = indent_width;
+    let mut first_word = true;
+    let mut rest = trimmed;
+
+    while !rest.is_empty() {
+        let word = rest.trim_start_matches(' ');
+        let spaces = rest.len() - word.len();
+        let (word, next) = word.split_at(word.find(' ').unwrap_or(word.len()));
+        rest = next;
+
+        // trailing spaces are dropped
+        if word.is_empty() {
+            break;
+        }
+
+        let word_width = visible_width(word);
+
+        if !first_word && line_width + spaces + word_width > width {
+            output.push('\n');
+            output.push_str(indent);
+            line_width = indent_width;
+            first_word = true;
+        }
+
+        if !first_word {
+            output.push_str(&" ".repeat(spaces));
+            line_width += spaces;
+        }
+
+        output.push_str(word);
+        line_width += word_width;
+        first_word = false;
+    }
+}
+
+/// Compute the display width of the given text, ignoring ANSI escape
+/// sequences (CSI and OSC).
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            width += c.width().unwrap_or_default();
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: ends with a byte in the range @ to ~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ends with BEL or ST (ESC \)
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    width
+}
+
+/// Writer that wraps complete lines before passing them to the inner
+/// writer.
+///
+/// Incomplete lines are buffered until a new line is written or the
+/// writer is flushed.
+pub struct WrapWriter<W: Write> {
+    inner: W,
+    width: Option<usize>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> WrapWriter<W> {
+    /// Create a writer wrapping at the current terminal width.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            width: None,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Wrap at a fixed width instead of the terminal width.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    fn write_wrapped(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let text = String::from_utf8_lossy(bytes);
+        let width = self.width.unwrap_or_else(width);
+        self.inner.write_all(fill(&text, width).as_bytes())
+    }
+}
+
+impl<W: Write> Write for WrapWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(bytes);
+
+        if let Some(pos) = self.buf.iter().rposition(|b| *b == b'\n') {
+            let lines: Vec<u8> = self.buf.drain(..=pos).collect();
+            self.write_wrapped(&lines)?;
+        }
+
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            let rest = std::mem::take(&mut self.buf);
+            self.write_wrapped(&rest)?;
+        }
+
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{fill, visible_width, WrapWriter};
+
+    #[test]
+    fn fill_keeps_short_lines() {
+        assert_eq!(fill("a  b\n\n  c", 10), "a  b\n\n  c");
+    }
+
+    #[test]
+    fn fill_breaks_at_spaces() {
+        assert_eq!(
+            fill("lorem ipsum dolor sit amet", 11),
+            "lorem ipsum\ndolor sit\namet"
+        );
+        assert_eq!(fill("a verylongword b", 4), "a\nverylongword\nb");
+    }
+
+    #[test]
+    fn fill_keeps_indentation_and_spaces() {
+        assert_eq!(
+            fill("  key:  value  and  more", 14),
+            "  key:  value\n  and  more"
+        );
+    }
+
+    #[test]
+    fn fill_ignores_escape_sequences() {
+        let text = "\x1b[1mbold\x1b[0m text";
+        assert_eq!(visible_width(text), 9);
+        assert_eq!(fill(text, 9), text);
+    }
+
+    #[test]
+    fn wrap_writer_wraps_complete_lines() {
+        let mut output = Vec::new();
+        let mut writer = WrapWriter::new(&mut output).with_width(5);
+
+        write!(writer, "aaa bbb").unwrap();
+        write!(writer, " ccc\nddd").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "aaa\nbbb\nccc\nddd");
+    }
+}